@@ -0,0 +1,419 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Anonymized repro requests.
+//!
+//! `wpt-runner anonymize` rewrites a failing `execute_graph` request (such as a failure bundle's
+//! `request.json`) so it can be attached to a public bug report without the graph's
+//! proprietary content:
+//!
+//! - operands, nodes and graph outputs are renumbered (`input0`, `const0`, `value0`, `node0`,
+//!   `output0`); inputs and constants keep their sorted order and nodes their declaration
+//!   order, so operand-valued options that refer to operands by index still point at the same
+//!   operand
+//! - float and 8-/4-bit constants are replaced by uniform noise over each tensor's own finite
+//!   range, so shapes, data types and rough magnitude survive; non-finite elements and
+//!   constants with a single distinct value are kept as is
+//! - inputs of those types become a `uniform` distribution over the same range
+//! - expected output `data`, `tolerance` and `postprocess` are dropped, since they depend on
+//!   the original weights
+//!
+//! 32- and 64-bit integer constants and inputs are kept: they are almost always shapes, axes
+//! or gather indices, and noise there would only replace the failure with a validation error.
+//! A graph's out-of-band `constants` section is inlined into `consts` first, so its weights and
+//! names are anonymized with the rest. With `--verify` both requests are parsed as the protocol
+//! parses them and executed, and the anonymized one must fail with the same error kind.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use half::{bf16, f16};
+use serde_json::{Map, Value, json};
+
+use crate::distribution::Rng;
+use crate::element_count::ElementCount;
+use crate::graph_json::string_list;
+use crate::{constants, encoding, handle_request, parse_request};
+
+const SESSION: &str = "anonymize";
+
+fn sorted_keys(value: Option<&Value>) -> Vec<String> {
+    let mut keys: Vec<String> = value
+        .and_then(Value::as_object)
+        .map(|map| map.keys().cloned().collect())
+        .unwrap_or_default();
+    keys.sort();
+    keys
+}
+
+/// `prefix` and `index`, zero padded so the new names sort like the old ones.
+fn numbered(prefix: &str, index: usize, count: usize) -> String {
+    let width = count.to_string().len();
+    format!("{prefix}{index:0width$}")
+}
+
+/// Element codec of the data types whose values get scrambled.
+struct Codec {
+    width: usize,
+    integer: bool,
+    decode: fn(&[u8]) -> f64,
+    encode: fn(f64) -> Vec<u8>,
+}
+
+fn codec(data_type: &str) -> Option<Codec> {
+    let codec = match data_type {
+        "float32" => Codec {
+            width: 4,
+            integer: false,
+            decode: |b| f64::from(f32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+            encode: |v| (v as f32).to_le_bytes().to_vec(),
+        },
+        "float16" => Codec {
+            width: 2,
+            integer: false,
+            decode: |b| f64::from(f16::from_le_bytes([b[0], b[1]])),
+            encode: |v| f16::from_f64(v).to_le_bytes().to_vec(),
+        },
+        "bfloat16" => Codec {
+            width: 2,
+            integer: false,
+            decode: |b| f64::from(bf16::from_le_bytes([b[0], b[1]])),
+            encode: |v| bf16::from_f64(v).to_le_bytes().to_vec(),
+        },
+        // 4-bit constants travel as one byte per element.
+        "int8" | "int4" => Codec {
+            width: 1,
+            integer: true,
+            decode: |b| f64::from(b[0] as i8),
+            encode: |v| vec![v as i8 as u8],
+        },
+        "uint8" | "uint4" => Codec {
+            width: 1,
+            integer: true,
+            decode: |b| f64::from(b[0]),
+            encode: |v| vec![v as u8],
+        },
+        _ => return None,
+    };
+    Some(codec)
+}
+
+/// Smallest and largest finite value, or `None` when there are none.
+fn finite_range(values: impl IntoIterator<Item = f64>) -> Option<(f64, f64)> {
+    values
+        .into_iter()
+        .filter(|v| v.is_finite())
+        .fold(None, |range, v| match range {
+            None => Some((v, v)),
+            Some((min, max)) => Some((v.min(min), v.max(max))),
+        })
+}
+
+fn noise(rng: &mut Rng, codec: &Codec, min: f64, max: f64) -> f64 {
+    if codec.integer {
+        min + rng.below((max - min) as u64 + 1) as f64
+    } else {
+        min + (max - min) * rng.unit()
+    }
+}
+
+/// Replace the finite elements of an `inlineBytes` constant with noise over their range.
+fn scramble_bytes(name: &str, data_type: &str, bytes: &[u8], seed: u64) -> Result<Vec<u8>, String> {
+    let Some(codec) = codec(data_type) else {
+        return Ok(bytes.to_vec());
+    };
+    if bytes.len() % codec.width != 0 {
+        return Err(format!(
+            "constant '{name}' has {} bytes, not a multiple of {} for {data_type}",
+            bytes.len(),
+            codec.width
+        ));
+    }
+    let values: Vec<f64> = bytes.chunks_exact(codec.width).map(codec.decode).collect();
+    let Some((min, max)) = finite_range(values.iter().copied()).filter(|(min, max)| min < max)
+    else {
+        return Ok(bytes.to_vec());
+    };
+    let mut rng = Rng::new(seed, name);
+    Ok(values
+        .into_iter()
+        .flat_map(|v| {
+            let v = if v.is_finite() {
+                noise(&mut rng, &codec, min, max)
+            } else {
+                v
+            };
+            (codec.encode)(v)
+        })
+        .collect())
+}
+
+/// Rename every string in `value` that is a key of `names`, recursing into arrays and objects.
+fn rename(value: &mut Value, names: &HashMap<String, String>) {
+    match value {
+        Value::String(s) => {
+            if let Some(renamed) = names.get(s.as_str()) {
+                *s = renamed.clone();
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(|v| rename(v, names)),
+        Value::Object(map) => map.values_mut().for_each(|v| rename(v, names)),
+        _ => {}
+    }
+}
+
+/// Rebuild the object at `value` with keys renamed through `names`.
+fn rename_keys(value: Option<&mut Value>, names: &HashMap<String, String>) {
+    let Some(Value::Object(map)) = value else {
+        return;
+    };
+    *map = std::mem::take(map)
+        .into_iter()
+        .map(|(key, v)| (names.get(&key).cloned().unwrap_or(key), v))
+        .collect::<Map<_, _>>();
+}
+
+/// Renames of the graph's operands and of its output names.
+fn graph_names(graph: &Value) -> (HashMap<String, String>, HashMap<String, String>) {
+    let mut operands = HashMap::new();
+    for (section, prefix) in [("inputs", "input"), ("consts", "const")] {
+        let keys = sorted_keys(graph.get(section));
+        for (index, key) in keys.iter().enumerate() {
+            operands.insert(key.clone(), numbered(prefix, index, keys.len()));
+        }
+    }
+    let node_outputs: Vec<&str> = graph
+        .get("nodes")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+        .iter()
+        .flat_map(|node| string_list(node.get("outputs")))
+        .collect();
+    for (index, name) in node_outputs.iter().enumerate() {
+        operands
+            .entry(name.to_string())
+            .or_insert_with(|| numbered("value", index, node_outputs.len()));
+    }
+    let output_keys = sorted_keys(graph.get("outputs"));
+    let outputs = output_keys
+        .iter()
+        .enumerate()
+        .map(|(index, key)| (key.clone(), numbered("output", index, output_keys.len())))
+        .collect();
+    (operands, outputs)
+}
+
+fn anonymize_graph(
+    graph: &mut Value,
+    operands: &HashMap<String, String>,
+    outputs: &HashMap<String, String>,
+    seed: u64,
+) -> Result<(), String> {
+    if let Some(consts) = graph.get_mut("consts").and_then(Value::as_object_mut) {
+        for (name, decl) in consts.iter_mut() {
+            let data_type = decl
+                .get("dataType")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string();
+            let init = decl
+                .get("init")
+                .ok_or_else(|| format!("constant '{name}' has no init"))?;
+            if init.get("kind").and_then(Value::as_str) != Some("inlineBytes") {
+                return Err(format!(
+                    "constant '{name}' must use inlineBytes init to be anonymized"
+                ));
+            }
+            let bytes = crate::weight_prepack::const_bytes(decl)
+                .ok_or_else(|| format!("constant '{name}' has malformed inlineBytes"))?;
+            let scrambled = scramble_bytes(&operands[name], &data_type, &bytes, seed)?;
+            decl["init"]["bytes"] = json!(scrambled);
+        }
+    }
+    rename_keys(graph.get_mut("inputs"), operands);
+    rename_keys(graph.get_mut("consts"), operands);
+    let nodes = graph
+        .get_mut("nodes")
+        .and_then(Value::as_array_mut)
+        .map(Vec::as_mut_slice)
+        .unwrap_or_default();
+    let node_count = nodes.len();
+    for (index, node) in nodes.iter_mut().enumerate() {
+        if node.get("id").is_some() {
+            node["id"] = json!(numbered("node", index, node_count));
+        }
+        for key in ["inputs", "outputs"] {
+            if let Some(value) = node.get_mut(key) {
+                rename(value, operands);
+            }
+        }
+        if let Some(options) = node.get_mut("options") {
+            rename(options, operands);
+        }
+    }
+    if let Some(map) = graph.get_mut("outputs") {
+        rename(map, operands);
+    }
+    rename_keys(graph.get_mut("outputs"), outputs);
+    if graph.get("name").is_some_and(Value::is_string) {
+        graph["name"] = json!("anonymized");
+    }
+    Ok(())
+}
+
+/// Replace an input's data by a `uniform` distribution over its finite range.
+fn anonymize_input(name: &str, input: &mut Value, seed: u64) -> Result<(), String> {
+    let data_type = input
+        .pointer("/descriptor/dataType")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    let Some(codec) = codec(&data_type) else {
+        return Ok(());
+    };
+    let shape = || -> Result<Vec<usize>, String> {
+        Ok(input
+            .pointer("/descriptor/shape")
+            .cloned()
+            .map(serde_json::from_value)
+            .transpose()
+            .map_err(|e| format!("input {name}: invalid shape: {e}"))?
+            .unwrap_or_default())
+    };
+    let values = match (
+        input.get("dataEncoding").and_then(Value::as_str),
+        input.get("data"),
+    ) {
+        (Some("base64"), Some(Value::String(encoded))) => {
+            let count = ElementCount::of(&shape()?)
+                .map_err(|e| e.to_string())?
+                .get();
+            encoding::decode_values(&data_type, encoded, count)?
+        }
+        (Some("npy"), Some(Value::String(encoded))) => {
+            encoding::decode_npy(&data_type, &shape()?, encoded)?
+        }
+        (_, Some(Value::Array(values))) => values.clone(),
+        // Already generated, or malformed and left for the runner to reject.
+        _ => return Ok(()),
+    };
+    let parsed = values
+        .iter()
+        .filter_map(|v| v.as_f64().or_else(|| v.as_str()?.parse().ok()));
+    let Some((min, max)) = finite_range(parsed) else {
+        return Ok(());
+    };
+    // Integer types floor uniform samples, so the exclusive bound is one past the maximum.
+    let max = if codec.integer { max + 1.0 } else { max };
+    let descriptor = input["descriptor"].take();
+    *input = json!({
+        "descriptor": descriptor,
+        "distribution": { "kind": "uniform", "min": min, "max": max, "seed": seed },
+    });
+    Ok(())
+}
+
+/// An anonymized copy of the `execute_graph` request `request`.
+pub fn anonymize(request: &Value, seed: u64) -> Result<Value, String> {
+    if request.get("cmd").and_then(Value::as_str) != Some("execute_graph") {
+        return Err("only execute_graph requests can be anonymized".to_string());
+    }
+    let mut request = request.clone();
+    let graph = request
+        .get_mut("graph")
+        .ok_or_else(|| "request has no graph".to_string())?;
+    constants::inline(graph).map_err(|e| e.to_string())?;
+    let (operands, outputs) = graph_names(graph);
+    anonymize_graph(graph, &operands, &outputs, seed)?;
+
+    if let Some(inputs) = request.get_mut("inputs").and_then(Value::as_object_mut) {
+        for (name, input) in inputs.iter_mut() {
+            anonymize_input(name, input, seed)?;
+        }
+    }
+    rename_keys(request.get_mut("inputs"), &operands);
+    rename_keys(request.get_mut("inputTensors"), &operands);
+    if let Some(names) = request.get_mut("freeze_inputs") {
+        rename(names, &operands);
+    }
+    if let Some(expected) = request
+        .get_mut("expected_outputs")
+        .and_then(Value::as_object_mut)
+    {
+        for output in expected.values_mut().filter_map(Value::as_object_mut) {
+            for key in ["data", "tolerance", "postprocess"] {
+                output.remove(key);
+            }
+        }
+    }
+    rename_keys(request.get_mut("expected_outputs"), &outputs);
+    rename_keys(request.get_mut("outputTensors"), &outputs);
+    request["id"] = json!("anonymized");
+    Ok(request)
+}
+
+/// Error kind of `request` when run, or `None` when it succeeds.
+fn failure_kind(request: &Value) -> Result<Option<String>, String> {
+    let parsed = parse_request(&request.to_string())
+        .map_err(|e| format!("invalid request: {e}"))?
+        .map_err(|e| format!("invalid request: {e}"))?;
+    let response = handle_request(parsed, SESSION);
+    Ok(response.error.map(|error| {
+        eprintln!("[ANONYMIZE] {}: {}", error.kind, error.message);
+        error.kind
+    }))
+}
+
+/// Anonymize the request in `path`, writing it as one protocol line to `out` or stdout.
+pub fn run(path: &Path, out: Option<&Path>, seed: u64, verify: bool) -> Result<(), String> {
+    let raw = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    let request: Value = serde_json::from_str(raw.trim())
+        .map_err(|e| format!("{}: invalid request: {e}", path.display()))?;
+    let anonymized = anonymize(&request, seed)?;
+
+    if verify {
+        let Some(original) = failure_kind(&request)? else {
+            return Err("the original request does not fail; nothing to reproduce".to_string());
+        };
+        match failure_kind(&anonymized)? {
+            Some(kind) if kind == original => {
+                eprintln!("[ANONYMIZE] anonymized request still fails with {kind}");
+            }
+            Some(kind) => {
+                return Err(format!(
+                    "anonymized request fails with {kind} instead of {original}; try another --seed"
+                ));
+            }
+            None => {
+                return Err(format!(
+                    "anonymized request no longer fails (original: {original}); try another --seed"
+                ));
+            }
+        }
+    }
+
+    let line = format!("{anonymized}\n");
+    match out {
+        Some(out) => std::fs::write(out, line).map_err(|e| format!("{}: {e}", out.display())),
+        None => {
+            print!("{line}");
+            Ok(())
+        }
+    }
+}
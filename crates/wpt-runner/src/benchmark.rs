@@ -0,0 +1,183 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Latency statistics for `benchmark` requests.
+//!
+//! A benchmark builds its graph once, bypassing the compile cache so conversion is actually
+//! measured, then runs warmup iterations that are discarded and timed iterations that are
+//! summarized. rustnn's executors create their runtime session inside each run call, so the
+//! `run` stage includes session creation; it cannot be timed apart from the runner.
+//!
+//! One request may ask for at most `RUSTNNPT_MAX_BENCHMARK_WARMUPS` warmups (default 100) and
+//! `RUSTNNPT_MAX_BENCHMARK_ITERATIONS` iterations (default 1000), so a `--listen` runner's worker
+//! is never held indefinitely; more fails with `PayloadLimitError`.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    Backend, BuiltGraph, ContextOptions, InputTensor, RunnerError, WarningPayload, compile_graph,
+    distribution, op_limits, payload_limits, prepare_graph, runtime_outputs, validated_graph_info,
+};
+
+/// Iterations run when the request does not say.
+const DEFAULT_WARMUPS: usize = 3;
+const DEFAULT_ITERATIONS: usize = 10;
+
+const DEFAULT_MAX_WARMUPS: usize = 100;
+const DEFAULT_MAX_ITERATIONS: usize = 1000;
+
+pub fn default_warmups() -> usize {
+    DEFAULT_WARMUPS
+}
+
+pub fn default_iterations() -> usize {
+    DEFAULT_ITERATIONS
+}
+
+#[derive(Debug, Serialize)]
+pub struct StageStats {
+    pub samples: usize,
+    #[serde(rename = "meanMs")]
+    pub mean_ms: f64,
+    #[serde(rename = "medianMs")]
+    pub median_ms: f64,
+    #[serde(rename = "p95Ms")]
+    pub p95_ms: f64,
+    #[serde(rename = "minMs")]
+    pub min_ms: f64,
+    #[serde(rename = "maxMs")]
+    pub max_ms: f64,
+}
+
+impl StageStats {
+    /// Summary of at least one sample; p95 is the nearest-rank percentile.
+    pub fn of(samples: &[Duration]) -> Self {
+        let mut ms: Vec<f64> = samples.iter().map(|d| d.as_secs_f64() * 1000.0).collect();
+        ms.sort_by(f64::total_cmp);
+        let n = ms.len();
+        let median = if n % 2 == 0 {
+            (ms[n / 2 - 1] + ms[n / 2]) / 2.0
+        } else {
+            ms[n / 2]
+        };
+        let p95 = ms[(n * 95).div_ceil(100).max(1) - 1];
+        Self {
+            samples: n,
+            mean_ms: ms.iter().sum::<f64>() / n as f64,
+            median_ms: median,
+            p95_ms: p95,
+            min_ms: ms[0],
+            max_ms: ms[n - 1],
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct BenchmarkReport {
+    pub target: String,
+    pub warmups: usize,
+    pub iterations: usize,
+    /// `prepare`, `validate` and `convert` from the single build, `run` per timed iteration.
+    pub stages: BTreeMap<&'static str, StageStats>,
+}
+
+fn timed<T>(run: impl FnOnce() -> Result<T, RunnerError>) -> Result<(T, Duration), RunnerError> {
+    let started = Instant::now();
+    let value = run()?;
+    Ok((value, started.elapsed()))
+}
+
+/// Build `graph` once and time `iterations` runs on `inputs` after `warmups` discarded ones.
+///
+/// The graph is checked against `session`'s operator limits first, as every build is.
+pub fn benchmark(
+    session: &str,
+    graph: Value,
+    mut inputs: BTreeMap<String, InputTensor>,
+    context_options: ContextOptions,
+    warmups: usize,
+    iterations: usize,
+    warnings: &mut Vec<WarningPayload>,
+) -> Result<BenchmarkReport, RunnerError> {
+    if iterations == 0 {
+        return Err(RunnerError::BadRequest(
+            "benchmark needs at least one iteration".to_string(),
+        ));
+    }
+    for (what, count, var, default) in [
+        (
+            "warmups",
+            warmups,
+            "RUSTNNPT_MAX_BENCHMARK_WARMUPS",
+            DEFAULT_MAX_WARMUPS,
+        ),
+        (
+            "iterations",
+            iterations,
+            "RUSTNNPT_MAX_BENCHMARK_ITERATIONS",
+            DEFAULT_MAX_ITERATIONS,
+        ),
+    ] {
+        let max = payload_limits::limit(var, default);
+        if count > max {
+            return Err(RunnerError::PayloadLimit(format!(
+                "benchmark asks for {count} {what}, over the limit of {max}"
+            )));
+        }
+    }
+    context_options.validate()?;
+    op_limits::check_graph(session, &graph)?;
+    distribution::generate_inputs(&mut inputs)?;
+    let backend = Backend::from_context(&context_options)?;
+    let (prepared, prepare) =
+        timed(|| prepare_graph(graph, Some(&mut inputs), &context_options, warnings))?;
+    let (graph_info, validate) = timed(|| validated_graph_info(&prepared))?;
+    if context_options.remote_address.is_none() && !backend.is_available() {
+        return Err(backend.unavailable());
+    }
+    let (compiled, convert) = timed(|| compile_graph(backend, &graph_info, &prepared))?;
+    let built = BuiltGraph {
+        prepared,
+        compiled: Arc::new(compiled),
+        context_options,
+    };
+
+    for _ in 0..warmups {
+        runtime_outputs(&built, &inputs)?;
+    }
+    let runs = (0..iterations)
+        .map(|_| timed(|| runtime_outputs(&built, &inputs)).map(|(_, elapsed)| elapsed))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(BenchmarkReport {
+        target: built.compiled.target.clone(),
+        warmups,
+        iterations,
+        stages: BTreeMap::from([
+            ("prepare", StageStats::of(&[prepare])),
+            ("validate", StageStats::of(&[validate])),
+            ("convert", StageStats::of(&[convert])),
+            ("run", StageStats::of(&runs)),
+        ]),
+    })
+}
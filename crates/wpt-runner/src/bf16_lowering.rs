@@ -0,0 +1,139 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! bfloat16 support by widening to float32.
+//!
+//! rustnn's `TensorData` and executors have no bfloat16 element type, so bfloat16 graph inputs,
+//! inline constants and dtype-valued node options are rewritten to float32 before conversion.
+//! Input values are rounded to bfloat16 first, and outputs are rounded back when they are cast
+//! to an expected bfloat16 descriptor, so only intermediate precision differs from a native
+//! implementation.
+
+use std::collections::BTreeMap;
+
+use half::bf16;
+use serde_json::Value;
+
+use crate::{InputTensor, RunnerError, WarningPayload, encoding, graph_json, parse_f32};
+
+/// Node option keys whose value is an `MLOperandDataType`.
+pub const DTYPE_OPTION_KEYS: &[&str] = &["to", "outputDataType", "dataType"];
+
+/// Round `v` to the nearest bfloat16.
+pub fn round(v: f32) -> f32 {
+    bf16::from_f32(v).to_f32()
+}
+
+pub fn to_bits(v: f32) -> u16 {
+    bf16::from_f32(v).to_bits()
+}
+
+/// A bfloat16 element value, given as a number or as its 16-bit hex pattern.
+pub fn parse(v: &Value) -> Result<f32, RunnerError> {
+    match encoding::hex_bits(v, 16) {
+        Some(bits) => bits
+            .map(|bits| bf16::from_bits(bits as u16).to_f32())
+            .map_err(RunnerError::BadRequest),
+        None => parse_f32(v).map(round),
+    }
+}
+
+fn widen_const_bytes(raw: &[u8]) -> Vec<u8> {
+    raw.chunks_exact(2)
+        .flat_map(|pair| {
+            bf16::from_bits(u16::from_le_bytes([pair[0], pair[1]]))
+                .to_f32()
+                .to_le_bytes()
+        })
+        .collect()
+}
+
+/// Rewrite bfloat16 inputs, constants and dtype options in `graph` to float32.
+pub fn lower_graph(
+    graph: &mut Value,
+    warnings: &mut Vec<WarningPayload>,
+) -> Result<(), RunnerError> {
+    let mut rewritten = Vec::new();
+
+    if let Some(inputs) = graph.get_mut("inputs").and_then(Value::as_object_mut) {
+        for (name, desc) in inputs.iter_mut() {
+            if desc.get("dataType").and_then(Value::as_str) == Some("bfloat16") {
+                desc["dataType"] = Value::from("float32");
+                rewritten.push(format!("input '{name}'"));
+            }
+        }
+    }
+
+    if let Some(consts) = graph.get_mut("consts").and_then(Value::as_object_mut) {
+        for (name, decl) in consts.iter_mut() {
+            if decl.get("dataType").and_then(Value::as_str) != Some("bfloat16") {
+                continue;
+            }
+            graph_json::rewrite_const_bytes(name, decl, "bfloat16 widening", 2, |raw| {
+                Ok(widen_const_bytes(raw))
+            })?;
+            decl["dataType"] = Value::from("float32");
+            rewritten.push(format!("constant '{name}'"));
+        }
+    }
+
+    if let Some(nodes) = graph.get_mut("nodes").and_then(Value::as_array_mut) {
+        for (index, node) in nodes.iter_mut().enumerate() {
+            let id = node
+                .get("id")
+                .and_then(Value::as_str)
+                .map_or_else(|| format!("#{index}"), str::to_string);
+            let Some(options) = node.get_mut("options").and_then(Value::as_object_mut) else {
+                continue;
+            };
+            for key in DTYPE_OPTION_KEYS {
+                if options.get(*key).and_then(Value::as_str) == Some("bfloat16") {
+                    options.insert((*key).to_string(), Value::from("float32"));
+                    rewritten.push(format!("node '{id}' option {key}"));
+                }
+            }
+        }
+    }
+
+    if !rewritten.is_empty() {
+        warnings.push(WarningPayload {
+            kind: "Bfloat16Emulation".to_string(),
+            message: format!(
+                "computed bfloat16 operands in float32: {}",
+                rewritten.join(", ")
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// Round bfloat16 runtime inputs to bfloat16 precision and retype them as float32.
+pub fn lower_inputs(inputs: &mut BTreeMap<String, InputTensor>) -> Result<(), RunnerError> {
+    for input in inputs.values_mut() {
+        if input.descriptor.data_type != "bfloat16" {
+            continue;
+        }
+        input.data = input
+            .data
+            .iter()
+            .map(|v| parse(v).map(encoding::float_value_bits))
+            .collect::<Result<Vec<_>, _>>()?;
+        input.descriptor.data_type = "float32".to_string();
+    }
+    Ok(())
+}
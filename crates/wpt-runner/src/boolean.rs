@@ -0,0 +1,122 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Boolean outputs of logical operators.
+//!
+//! WebNN has no boolean operand type: comparison and logical operators produce `uint8` tensors
+//! holding 0 or 1. Executors hand every output back as generic numbers, so the runner tracks
+//! which graph outputs come from a logical operator, reports them as `uint8` and rejects any
+//! value other than 0 or 1 instead of passing it through.
+//!
+//! The same holds for inputs: `where` takes its condition as a `uint8` tensor, and the spec
+//! offers no implicit conversion. Converters would otherwise accept a float condition and pick
+//! their own truthiness, so such graphs are rejected with the offending node named.
+
+use std::collections::{BTreeSet, HashSet};
+
+use serde_json::Value;
+
+use crate::{RunnerError, output_dtype};
+
+/// Operators whose output is a `uint8` tensor of 0/1 values.
+const LOGICAL_OPS: &[&str] = &[
+    "equal",
+    "notEqual",
+    "greater",
+    "greaterOrEqual",
+    "lesser",
+    "lesserOrEqual",
+    "logicalNot",
+    "logicalAnd",
+    "logicalOr",
+    "logicalXor",
+    "isNaN",
+    "isInfinite",
+];
+
+pub fn is_logical(op: &str) -> bool {
+    LOGICAL_OPS.contains(&op)
+}
+
+/// Names of graph outputs that export the result of a logical operator.
+pub fn boolean_outputs(graph: &Value) -> BTreeSet<String> {
+    let produced: HashSet<&str> = graph
+        .get("nodes")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+        .iter()
+        .filter(|node| {
+            node.get("op")
+                .and_then(Value::as_str)
+                .is_some_and(is_logical)
+        })
+        .filter_map(|node| node.get("outputs").and_then(Value::as_array))
+        .flatten()
+        .filter_map(Value::as_str)
+        .collect();
+    graph
+        .get("outputs")
+        .and_then(Value::as_object)
+        .into_iter()
+        .flatten()
+        .filter(|(_, operand)| operand.as_str().is_some_and(|op| produced.contains(op)))
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+/// Fail if a boolean output holds anything but 0 or 1.
+pub fn check_values(name: &str, data: &[f64]) -> Result<(), RunnerError> {
+    match data.iter().position(|&v| v != 0.0 && v != 1.0) {
+        Some(index) => Err(RunnerError::RuntimeExecution(format!(
+            "output {name}: boolean result has value {} at index {index}, expected 0 or 1",
+            data[index]
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Fail if a `where` node's condition is not a `uint8` operand.
+pub fn check_where_conditions(graph: &Value) -> Result<(), RunnerError> {
+    let types = output_dtype::operand_types(graph);
+    let nodes = graph
+        .get("nodes")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    for (index, node) in nodes.iter().enumerate() {
+        if node.get("op").and_then(Value::as_str) != Some("where") {
+            continue;
+        }
+        let Some(condition) = node.pointer("/inputs/0").and_then(Value::as_str) else {
+            continue;
+        };
+        let Some(data_type) = types.get(condition).filter(|t| **t != "uint8") else {
+            continue;
+        };
+        let node_id = node
+            .get("id")
+            .and_then(Value::as_str)
+            .map_or_else(|| format!("#{index}"), |id| format!("'{id}'"));
+        return Err(RunnerError::GraphValidation(format!(
+            "where node {node_id}: condition '{condition}' is {data_type}, but where requires a \
+             uint8 condition; produce it with a comparison or cast it to uint8 explicitly"
+        )));
+    }
+    Ok(())
+}
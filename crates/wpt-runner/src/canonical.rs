@@ -0,0 +1,41 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! `--canonical-output`: byte-stable responses for recorded sessions and golden files.
+//!
+//! Response maps are `BTreeMap`s and serde_json formats numbers with a fixed shortest
+//! round-trip algorithm, so key order and integer output are already stable. Float outputs are
+//! not: they come back from executors widened to f64, and the digits of a widened f32 depend on
+//! how the executor rounded on the way. In canonical mode every float output is written with the
+//! shortest digits that round-trip at f32 precision instead.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static CANONICAL: AtomicBool = AtomicBool::new(false);
+
+pub fn enable() {
+    CANONICAL.store(true, Ordering::Relaxed);
+}
+
+/// `x` as it should be serialized; identity unless canonical mode is on.
+pub fn float(x: f64) -> f64 {
+    if !CANONICAL.load(Ordering::Relaxed) {
+        return x;
+    }
+    (x as f32).to_string().parse().unwrap_or(x)
+}
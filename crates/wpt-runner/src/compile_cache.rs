@@ -0,0 +1,224 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Compiled-graph cache keyed by graph content hash.
+//!
+//! Conformance runs execute the same graph many times with different inputs, so validation and
+//! conversion are skipped when the prepared webnn-graph-json was already compiled for the same
+//! target. Entries live in memory for the lifetime of the runner and, when
+//! `RUSTNNPT_COMPILE_CACHE_DIR` is set, are also persisted there as `CompiledGraph` JSON so
+//! later runner processes start warm. `RUSTNNPT_COMPILE_CACHE=0` disables the cache.
+//!
+//! Executors still create their runtime session per request; only the converted artifact is
+//! reused.
+//!
+//! Entries are partitioned by the harness session id sent in the `handshake` request, so
+//! harnesses sharing one listening runner neither see nor evict each other's graphs.
+//!
+//! The 64-bit key only picks the slot. Every entry also records the SHA-256 digest of the graph
+//! it was compiled from and the target, and a hit is only used when both match the request, so
+//! a key collision recompiles instead of running another graph's model.
+
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::compiled::{self, CompiledGraph};
+
+/// In-memory entries kept per session before the oldest is evicted.
+const MAX_ENTRIES: usize = 64;
+
+/// SHA-256 of a prepared graph's serialized JSON.
+pub type GraphDigest = [u8; 32];
+
+#[derive(Default)]
+struct MemoryCache {
+    entries: HashMap<u64, (GraphDigest, Arc<CompiledGraph>)>,
+    order: VecDeque<u64>,
+}
+
+/// On-disk entry: the compiled graph with the digest of the graph it was compiled from.
+#[derive(Serialize, Deserialize)]
+struct DiskEntry {
+    #[serde(rename = "graphDigest")]
+    graph_digest: String,
+    compiled: CompiledGraph,
+}
+
+/// Per-session caches; requests without a session share the `""` partition.
+fn memory() -> &'static Mutex<HashMap<String, MemoryCache>> {
+    static CACHE: OnceLock<Mutex<HashMap<String, MemoryCache>>> = OnceLock::new();
+    CACHE.get_or_init(Mutex::default)
+}
+
+fn debug(message: impl std::fmt::Display) {
+    if std::env::var("RUSTNNPT_DEBUG").as_deref() == Ok("1") {
+        eprintln!("[RUNNER] compile cache: {message}");
+    }
+}
+
+/// FNV-1a, so keys stay stable across runner builds and processes.
+pub fn fnv1a(bytes: &[u8], mut hash: u64) -> u64 {
+    for &b in bytes {
+        hash ^= u64::from(b);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+pub const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+pub fn enabled() -> bool {
+    std::env::var("RUSTNNPT_COMPILE_CACHE").as_deref() != Ok("0")
+}
+
+pub fn graph_digest(graph: &Value) -> GraphDigest {
+    Sha256::digest(graph.to_string().as_bytes()).into()
+}
+
+fn hex(digest: &GraphDigest) -> String {
+    digest.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Cache key for the graph with `digest` compiled by this runner for `target` within `session`.
+pub fn key(digest: &GraphDigest, target: &str, session: &str) -> u64 {
+    let fingerprint = compiled::capability_fingerprint(target);
+    let hash = fnv1a(digest, FNV_OFFSET);
+    let hash = fnv1a(fingerprint.as_bytes(), hash);
+    if session.is_empty() {
+        hash
+    } else {
+        fnv1a(session.as_bytes(), fnv1a(&[0], hash))
+    }
+}
+
+fn disk_path(key: u64) -> Option<PathBuf> {
+    let dir = std::env::var_os("RUSTNNPT_COMPILE_CACHE_DIR")?;
+    Some(PathBuf::from(dir).join(format!("{key:016x}.json")))
+}
+
+fn load_from_disk(key: u64, digest: &GraphDigest, target: &str) -> Option<CompiledGraph> {
+    let path = disk_path(key)?;
+    let raw = std::fs::read(&path).ok()?;
+    let entry = match serde_json::from_slice::<DiskEntry>(&raw) {
+        Ok(entry) => entry,
+        Err(err) => {
+            debug(format!("ignoring unreadable {}: {err}", path.display()));
+            return None;
+        }
+    };
+    if entry.graph_digest != hex(digest) || entry.compiled.target != target {
+        debug(format!(
+            "ignoring {}: compiled from another graph",
+            path.display()
+        ));
+        return None;
+    }
+    if let Err(err) = entry.compiled.check_fingerprint() {
+        debug(format!("ignoring {}: {err}", path.display()));
+        return None;
+    }
+    Some(entry.compiled)
+}
+
+fn write_entry(path: &Path, entry: &DiskEntry) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    // Write then rename so concurrent runners never read a partial entry.
+    let tmp = path.with_extension(format!("json.{}.tmp", std::process::id()));
+    std::fs::write(&tmp, serde_json::to_vec(entry)?)?;
+    std::fs::rename(&tmp, path)
+}
+
+fn store_to_disk(key: u64, digest: &GraphDigest, compiled: CompiledGraph) -> CompiledGraph {
+    let Some(path) = disk_path(key) else {
+        return compiled;
+    };
+    let entry = DiskEntry {
+        graph_digest: hex(digest),
+        compiled,
+    };
+    if let Err(err) = write_entry(&path, &entry) {
+        debug(format!("failed to persist {}: {err}", path.display()));
+    }
+    entry.compiled
+}
+
+fn remember(cache: &mut MemoryCache, key: u64, digest: GraphDigest, compiled: Arc<CompiledGraph>) {
+    if cache.entries.insert(key, (digest, compiled)).is_none() {
+        cache.order.push_back(key);
+    }
+    while cache.order.len() > MAX_ENTRIES {
+        if let Some(oldest) = cache.order.pop_front() {
+            cache.entries.remove(&oldest);
+        }
+    }
+}
+
+/// Look up the graph with `digest` compiled for `target`, in memory and then on disk.
+pub fn get(
+    session: &str,
+    key: u64,
+    digest: &GraphDigest,
+    target: &str,
+) -> Option<Arc<CompiledGraph>> {
+    let mut sessions = memory().lock().unwrap_or_else(|e| e.into_inner());
+    let cache = sessions.entry(session.to_string()).or_default();
+    if let Some((stored, hit)) = cache.entries.get(&key) {
+        if stored == digest && hit.target == target {
+            debug(format!("memory hit {key:016x}"));
+            return Some(Arc::clone(hit));
+        }
+        debug(format!("key collision on {key:016x}; recompiling"));
+        return None;
+    }
+    let compiled = Arc::new(load_from_disk(key, digest, target)?);
+    debug(format!("disk hit {key:016x}"));
+    remember(cache, key, *digest, Arc::clone(&compiled));
+    Some(compiled)
+}
+
+pub fn insert(
+    session: &str,
+    key: u64,
+    digest: &GraphDigest,
+    compiled: CompiledGraph,
+) -> Arc<CompiledGraph> {
+    let compiled = Arc::new(store_to_disk(key, digest, compiled));
+    let mut sessions = memory().lock().unwrap_or_else(|e| e.into_inner());
+    let cache = sessions.entry(session.to_string()).or_default();
+    remember(cache, key, *digest, Arc::clone(&compiled));
+    compiled
+}
+
+/// Drop the in-memory entries of `session`; called when its connection closes.
+pub fn end_session(session: &str) {
+    let mut sessions = memory().lock().unwrap_or_else(|e| e.into_inner());
+    sessions.remove(session);
+}
+
+/// In-memory entries across all sessions.
+pub fn entry_count() -> usize {
+    let sessions = memory().lock().unwrap_or_else(|e| e.into_inner());
+    sessions.values().map(|cache| cache.entries.len()).sum()
+}
@@ -0,0 +1,198 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Executor-neutral container for a converted graph.
+//!
+//! Every backend splits into a compile step (converter output wrapped in a [`CompiledGraph`]) and
+//! a run step that only sees the container and the runtime inputs. The container serializes to
+//! JSON with base64 payloads so it can be written to disk or handed to another process as is.
+
+use std::collections::BTreeMap;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+
+/// Bumped whenever the serialized layout changes.
+pub const FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OperandDescriptor {
+    pub name: String,
+    #[serde(rename = "dataType")]
+    pub data_type: String,
+    pub shape: Vec<usize>,
+}
+
+/// Maps an engine-level tensor name back to the graph operand name.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EngineBinding {
+    pub binding: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompiledGraph {
+    #[serde(rename = "formatVersion")]
+    pub format_version: u32,
+    /// Converter/executor id: `onnx`, `coreml` or `trtx`.
+    pub target: String,
+    #[serde(with = "base64_bytes")]
+    pub artifact: Vec<u8>,
+    #[serde(
+        default,
+        with = "base64_opt_bytes",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub weights: Option<Vec<u8>>,
+    pub inputs: Vec<OperandDescriptor>,
+    pub outputs: Vec<String>,
+    /// Graph inputs in engine binding order, for executors that do not bind by operand name.
+    #[serde(
+        rename = "inputBindings",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub input_bindings: Vec<EngineBinding>,
+    #[serde(
+        rename = "outputBindings",
+        default,
+        skip_serializing_if = "BTreeMap::is_empty"
+    )]
+    pub output_bindings: BTreeMap<String, String>,
+    /// Runner version, target and compiled-in backends; artifacts only run where it matches.
+    pub fingerprint: String,
+}
+
+impl CompiledGraph {
+    /// Fail if this artifact was produced by a different runner version or layout.
+    ///
+    /// Enabled backend features are recorded but not compared: a harness host without the
+    /// executor can still compile for a remote runner that has it.
+    pub fn check_fingerprint(&self) -> Result<(), String> {
+        if self.format_version != FORMAT_VERSION {
+            return Err(format!(
+                "compiled graph format version {} is not supported (expected {FORMAT_VERSION})",
+                self.format_version
+            ));
+        }
+        let expected = fingerprint_prefix(&self.target);
+        if !self.fingerprint.starts_with(&expected) {
+            return Err(format!(
+                "compiled graph fingerprint '{}' does not match this runner ('{expected}...')",
+                self.fingerprint
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn fingerprint_prefix(target: &str) -> String {
+    format!("wpt-runner/{}/{target}/", env!("CARGO_PKG_VERSION"))
+}
+
+/// Identify what produced an artifact: runner version, target and enabled backend features.
+pub fn capability_fingerprint(target: &str) -> String {
+    let mut features = Vec::new();
+    if cfg!(feature = "backend-onnx") {
+        features.push("onnx");
+    }
+    if cfg!(all(target_os = "macos", feature = "backend-coreml")) {
+        features.push("coreml");
+    }
+    if cfg!(feature = "backend-trtx") {
+        features.push("trtx");
+    }
+    if cfg!(feature = "backend-trtx-mock") {
+        features.push("trtx-mock");
+    }
+    format!("{}{}", fingerprint_prefix(target), features.join("+"))
+}
+
+/// Input descriptors as declared in webnn-graph-json, in declaration order.
+pub fn input_descriptors(graph: &Value) -> Vec<OperandDescriptor> {
+    let Some(inputs) = graph.get("inputs").and_then(Value::as_object) else {
+        return Vec::new();
+    };
+    inputs
+        .iter()
+        .map(|(name, desc)| OperandDescriptor {
+            name: name.clone(),
+            data_type: desc
+                .get("dataType")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            shape: desc
+                .get("shape")
+                .and_then(Value::as_array)
+                .map(|dims| {
+                    dims.iter()
+                        .filter_map(Value::as_u64)
+                        .map(|d| d as usize)
+                        .collect()
+                })
+                .unwrap_or_default(),
+        })
+        .collect()
+}
+
+/// Graph output names as declared in webnn-graph-json.
+pub fn output_names(graph: &Value) -> Vec<String> {
+    graph
+        .get("outputs")
+        .and_then(Value::as_object)
+        .map(|outputs| outputs.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
+mod base64_bytes {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&STANDARD.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        STANDARD.decode(encoded).map_err(serde::de::Error::custom)
+    }
+}
+
+mod base64_opt_bytes {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        bytes: &Option<Vec<u8>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match bytes {
+            Some(bytes) => serializer.serialize_some(&STANDARD.encode(bytes)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Vec<u8>>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|encoded| STANDARD.decode(encoded).map_err(serde::de::Error::custom))
+            .transpose()
+    }
+}
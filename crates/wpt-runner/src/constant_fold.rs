@@ -0,0 +1,275 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Constant folding on webnn-graph-json.
+//!
+//! WPT graphs often wrap constants in `reshape`, `transpose` or `cast` nodes. When such a node's
+//! only input is an inline constant with no other consumer, the result is computed once and
+//! stored in that constant, so the emitted model carries the folded tensor instead of the node.
+//! Operand-valued options (`bias`, `scale`, ...) count as consumers. The constant keeps its
+//! name: those options refer to constants by sorted index, which adding or removing
+//! declarations would shift. Options naming a folded node's output are pointed at the constant,
+//! and index-valued options are renumbered since they also count node outputs.
+//!
+//! Only conversions with a single defined result are folded; a cast of values that do not fit
+//! the target type is left to the backend.
+
+use std::collections::HashMap;
+
+use half::f16;
+use serde_json::Value;
+
+use crate::element_count::{ElementCount, element_size};
+use crate::graph_json::string_list;
+use crate::inference_rewrite::{OPERAND_OPTIONS, operand_order, references, renumber_options};
+use crate::int4;
+use crate::weight_prepack::{const_bytes, permute};
+
+struct Constant {
+    data_type: String,
+    shape: Vec<usize>,
+    bytes: Vec<u8>,
+}
+
+enum Scalar {
+    Float(f64),
+    Int(i128),
+}
+
+fn dims(value: Option<&Value>) -> Option<Vec<usize>> {
+    value?
+        .as_array()?
+        .iter()
+        .map(|d| d.as_u64().map(|d| d as usize))
+        .collect()
+}
+
+fn load(decl: &Value) -> Option<Constant> {
+    let data_type = decl.get("dataType")?.as_str()?.to_string();
+    // 4-bit constants travel one byte per element and bfloat16 is lowered before this runs.
+    if int4::is_4bit(&data_type) || data_type == "bfloat16" {
+        return None;
+    }
+    let shape = dims(decl.get("shape"))?;
+    let bytes = const_bytes(decl)?;
+    let byte_len = ElementCount::of(&shape).and_then(|count| count.byte_len(&data_type));
+    (byte_len.ok() == Some(bytes.len())).then_some(Constant {
+        data_type,
+        shape,
+        bytes,
+    })
+}
+
+fn words<const N: usize>(bytes: &[u8]) -> impl Iterator<Item = [u8; N]> + '_ {
+    bytes.chunks_exact(N).filter_map(|c| c.try_into().ok())
+}
+
+fn decode(data_type: &str, bytes: &[u8]) -> Option<Vec<Scalar>> {
+    let values = match data_type {
+        "float32" => words(bytes)
+            .map(|b| Scalar::Float(f64::from(f32::from_le_bytes(b))))
+            .collect(),
+        "float16" => words(bytes)
+            .map(|b| Scalar::Float(f16::from_bits(u16::from_le_bytes(b)).to_f64()))
+            .collect(),
+        "int8" => bytes
+            .iter()
+            .map(|&b| Scalar::Int(i128::from(b as i8)))
+            .collect(),
+        "uint8" => bytes.iter().map(|&b| Scalar::Int(i128::from(b))).collect(),
+        "int32" => words(bytes)
+            .map(|b| Scalar::Int(i128::from(i32::from_le_bytes(b))))
+            .collect(),
+        "uint32" => words(bytes)
+            .map(|b| Scalar::Int(i128::from(u32::from_le_bytes(b))))
+            .collect(),
+        "int64" => words(bytes)
+            .map(|b| Scalar::Int(i128::from(i64::from_le_bytes(b))))
+            .collect(),
+        "uint64" => words(bytes)
+            .map(|b| Scalar::Int(i128::from(u64::from_le_bytes(b))))
+            .collect(),
+        _ => return None,
+    };
+    Some(values)
+}
+
+/// Float to integer casts truncate toward zero; non-finite values have no integer result.
+fn integer(value: &Scalar) -> Option<i128> {
+    match *value {
+        Scalar::Float(f) if f.is_finite() => Some(f.trunc() as i128),
+        Scalar::Float(_) => None,
+        Scalar::Int(i) => Some(i),
+    }
+}
+
+fn encode(data_type: &str, values: &[Scalar]) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(values.len() * element_size(data_type));
+    for value in values {
+        match data_type {
+            "float32" => bytes.extend(match *value {
+                Scalar::Float(f) => (f as f32).to_le_bytes(),
+                Scalar::Int(i) => (i as f32).to_le_bytes(),
+            }),
+            "float16" => bytes.extend(
+                match *value {
+                    Scalar::Float(f) => f16::from_f64(f),
+                    Scalar::Int(i) => f16::from_f64(i as f64),
+                }
+                .to_bits()
+                .to_le_bytes(),
+            ),
+            "int8" => bytes.extend(i8::try_from(integer(value)?).ok()?.to_le_bytes()),
+            "uint8" => bytes.extend(u8::try_from(integer(value)?).ok()?.to_le_bytes()),
+            "int32" => bytes.extend(i32::try_from(integer(value)?).ok()?.to_le_bytes()),
+            "uint32" => bytes.extend(u32::try_from(integer(value)?).ok()?.to_le_bytes()),
+            "int64" => bytes.extend(i64::try_from(integer(value)?).ok()?.to_le_bytes()),
+            "uint64" => bytes.extend(u64::try_from(integer(value)?).ok()?.to_le_bytes()),
+            _ => return None,
+        }
+    }
+    Some(bytes)
+}
+
+/// Result of `op` on `input`, or `None` when the node should be left in the graph.
+fn fold(op: &str, options: Option<&Value>, input: Constant) -> Option<Constant> {
+    let option = |key: &str| options.and_then(|o| o.get(key));
+    match op {
+        "identity" => Some(input),
+        "reshape" => {
+            let shape = dims(option("newShape"))?;
+            let same_count =
+                ElementCount::of(&shape).ok()?.get() == ElementCount::of(&input.shape).ok()?.get();
+            same_count.then_some(Constant { shape, ..input })
+        }
+        "transpose" => {
+            let rank = input.shape.len();
+            let perm = match option("permutation") {
+                Some(perm) => dims(Some(perm))?,
+                None => (0..rank).rev().collect(),
+            };
+            let mut axes = perm.clone();
+            axes.sort_unstable();
+            if !axes.into_iter().eq(0..rank) {
+                return None;
+            }
+            let elem = element_size(&input.data_type);
+            Some(Constant {
+                bytes: permute(&input.bytes, elem, &input.shape, &perm)?,
+                shape: perm.iter().map(|&p| input.shape[p]).collect(),
+                data_type: input.data_type,
+            })
+        }
+        "cast" => {
+            let to = option("to")?.as_str()?;
+            let bytes = encode(to, &decode(&input.data_type, &input.bytes)?)?;
+            Some(Constant {
+                data_type: to.to_string(),
+                shape: input.shape,
+                bytes,
+            })
+        }
+        _ => None,
+    }
+}
+
+fn rename(value: &mut Value, renamed: &HashMap<String, String>) {
+    match value {
+        Value::String(name) => {
+            if let Some(to) = renamed.get(name.as_str()) {
+                *name = to.clone();
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(|item| rename(item, renamed)),
+        _ => {}
+    }
+}
+
+/// Fold `identity`/`reshape`/`transpose`/`cast` nodes over single-consumer inline constants,
+/// chains included.
+///
+/// Returns the ids of the removed nodes.
+pub fn fold_constants(graph: &mut Value) -> Vec<String> {
+    let order = operand_order(graph);
+    let mut consumers = references(graph, &order);
+    let mut folded = Vec::new();
+    let nodes = match graph.get_mut("nodes") {
+        Some(Value::Array(nodes)) => std::mem::take(nodes),
+        _ => return folded,
+    };
+
+    // Output of a folded node -> the constant now holding its value.
+    let mut renamed = HashMap::new();
+    let mut kept = Vec::with_capacity(nodes.len());
+    for mut node in nodes {
+        if let Some(inputs) = node.get_mut("inputs") {
+            rename(inputs, &renamed);
+        }
+        if let Some(options) = node.get_mut("options").and_then(Value::as_object_mut) {
+            for key in OPERAND_OPTIONS {
+                if let Some(operand) = options.get_mut(*key) {
+                    rename(operand, &renamed);
+                }
+            }
+        }
+        let op = node.get("op").and_then(Value::as_str).unwrap_or_default();
+        let (inputs, outputs) = (
+            string_list(node.get("inputs")),
+            string_list(node.get("outputs")),
+        );
+        let ([source], [output]) = (inputs.as_slice(), outputs.as_slice()) else {
+            kept.push(node);
+            continue;
+        };
+        let result = graph
+            .get("consts")
+            .and_then(|consts| consts.get(*source))
+            .filter(|_| consumers.get(*source) == Some(&1))
+            .and_then(load)
+            .and_then(|input| fold(op, node.get("options"), input));
+        let Some(result) = result else {
+            kept.push(node);
+            continue;
+        };
+
+        let (source, output) = (source.to_string(), output.to_string());
+        let decl = &mut graph["consts"][source.as_str()];
+        decl["dataType"] = Value::from(result.data_type);
+        decl["shape"] = Value::from(result.shape);
+        decl["init"]["bytes"] = Value::from(result.bytes);
+        let output_consumers = consumers.get(&output).copied().unwrap_or(0);
+        consumers.insert(source.clone(), output_consumers);
+        folded.push(
+            node.get("id")
+                .and_then(Value::as_str)
+                .map_or_else(|| output.clone(), str::to_string),
+        );
+        renamed.insert(output, source);
+    }
+
+    graph["nodes"] = Value::Array(kept);
+    if let Some(outputs) = graph.get_mut("outputs").and_then(Value::as_object_mut) {
+        outputs
+            .values_mut()
+            .for_each(|operand| rename(operand, &renamed));
+    }
+    if !folded.is_empty() {
+        renumber_options(graph, &order, &renamed);
+    }
+    folded
+}
@@ -0,0 +1,94 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Graph constants sent out of band.
+//!
+//! A graph may carry a `constants` section next to `consts`, mapping each name to a
+//! `descriptor` and the base64 storage bytes of the tensor (`int4`/`uint4` packed, as in base64
+//! tensor transport). Large weights then cost a third more than their size on the wire instead
+//! of several bytes per byte as a JSON array. Each entry becomes an ordinary inline constant
+//! before anything else sees the graph, so operand indices count them among `consts`.
+
+use std::collections::BTreeMap;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::element_count::ElementCount;
+use crate::{RunnerError, TensorDescriptor, int4};
+
+#[derive(Deserialize)]
+struct Constant {
+    descriptor: TensorDescriptor,
+    data: String,
+}
+
+/// Inline-bytes layout of `bytes`: 4-bit types take one byte per element there.
+fn inline_bytes(
+    name: &str,
+    descriptor: &TensorDescriptor,
+    bytes: Vec<u8>,
+) -> Result<Vec<u8>, RunnerError> {
+    let data_type = descriptor.data_type.as_str();
+    let count = ElementCount::of(&descriptor.shape)?;
+    if int4::is_4bit(data_type) {
+        let values = int4::unpack(data_type, &bytes, count.get())
+            .map_err(|e| RunnerError::InputData(format!("constant {name}: {e}")))?;
+        return Ok(values.into_iter().map(|v| v as u8).collect());
+    }
+    let expected = count.byte_len(data_type)?;
+    if bytes.len() != expected {
+        return Err(RunnerError::InputData(format!(
+            "constant {name} of {data_type} {:?} needs {expected} bytes, got {}",
+            descriptor.shape,
+            bytes.len()
+        )));
+    }
+    Ok(bytes)
+}
+
+/// Move the `constants` section of `graph` into `consts`.
+pub fn inline(graph: &mut Value) -> Result<(), RunnerError> {
+    let Some(section) = graph.as_object_mut().and_then(|g| g.remove("constants")) else {
+        return Ok(());
+    };
+    let constants = serde_json::from_value::<BTreeMap<String, Constant>>(section)
+        .map_err(|e| RunnerError::BadRequest(format!("invalid graph constants: {e}")))?;
+    for (name, constant) in constants {
+        let declared = ["inputs", "consts"]
+            .iter()
+            .any(|section| graph.get(section).and_then(|s| s.get(&name)).is_some());
+        if declared {
+            return Err(RunnerError::BadRequest(format!(
+                "constant {name} is already declared by the graph"
+            )));
+        }
+        let bytes = STANDARD.decode(&constant.data).map_err(|e| {
+            RunnerError::BadRequest(format!("constant {name}: invalid base64 data: {e}"))
+        })?;
+        let bytes = inline_bytes(&name, &constant.descriptor, bytes)?;
+        graph["consts"][name.as_str()] = json!({
+            "dataType": constant.descriptor.data_type,
+            "shape": constant.descriptor.shape,
+            "init": { "kind": "inlineBytes", "bytes": bytes },
+        });
+    }
+    Ok(())
+}
@@ -0,0 +1,378 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Deviation statistics for tolerance research.
+//!
+//! `deviation_stats` runs one graph on a sweep of generated input sets, once on a `reference`
+//! and once on a `candidate` configuration (another backend or device, or the same graph
+//! narrowed to `float16`), and returns per output the distribution of ULP distances between the
+//! two. Distances are counted in the candidate output's `dataType`, the type a WPT tolerance is
+//! written for, so the percentiles can back the values in WPT metadata instead of guesses.
+//!
+//! Sample `i` draws every `distribution` input with its `seed` plus `i`; inputs with literal
+//! `data` are reused for every sample. Both configurations see the same values.
+
+use std::collections::BTreeMap;
+
+use half::f16;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    ContextOptions, InputTensor, OutputTensor, RunnerError, WarningPayload, bf16_lowering,
+    distribution, execute_graph, graph_json, tolerance,
+};
+
+/// Input sets run when the request does not say.
+const DEFAULT_SAMPLES: usize = 8;
+
+pub fn default_samples() -> usize {
+    DEFAULT_SAMPLES
+}
+
+/// One configuration a `deviation_stats` request runs the graph on.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Side {
+    #[serde(default)]
+    context_options: ContextOptions,
+    /// Converter/executor; overrides `context_options.backend`.
+    #[serde(default)]
+    target: Option<String>,
+    /// `float16` runs the graph with its float32 operands narrowed to float16.
+    #[serde(default)]
+    precision: Option<String>,
+}
+
+impl Side {
+    pub fn options(&self) -> ContextOptions {
+        let mut options = self.context_options.clone();
+        if self.target.is_some() {
+            options.backend = self.target.clone();
+        }
+        // Values are read back from `data`, which spilling would leave empty.
+        options.spill_threshold_bytes = None;
+        options
+    }
+
+    fn narrowed(&self) -> Result<bool, RunnerError> {
+        match self.precision.as_deref() {
+            None | Some("float32") => Ok(false),
+            Some("float16") => Ok(true),
+            Some(other) => Err(RunnerError::BadRequest(format!(
+                "unsupported precision '{other}'. Supported: float32, float16"
+            ))),
+        }
+    }
+
+    /// `graph` as this side runs it.
+    pub fn graph(&self, graph: &Value) -> Result<Value, RunnerError> {
+        let mut graph = graph.clone();
+        if self.narrowed()? {
+            narrow_graph(&mut graph)?;
+        }
+        Ok(graph)
+    }
+
+    pub fn validate(&self) -> Result<(), RunnerError> {
+        self.narrowed()?;
+        self.options().validate()
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct UlpPercentiles {
+    p50: u64,
+    p90: u64,
+    p99: u64,
+    p999: u64,
+    max: u64,
+}
+
+/// Elements whose distance is at most `ulp`, and above the previous bucket's bound.
+#[derive(Debug, Serialize)]
+pub struct Bucket {
+    ulp: u64,
+    count: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OutputDeviation {
+    /// Type the ULP distances are counted in: the candidate's output `dataType`.
+    #[serde(rename = "dataType")]
+    data_type: String,
+    /// Finite element pairs behind the statistics, over all samples.
+    elements: usize,
+    /// Pairs where only one side is finite, or both are non-finite but differ.
+    #[serde(rename = "nonFiniteMismatches")]
+    non_finite_mismatches: usize,
+    #[serde(rename = "maxAbsError")]
+    max_abs_error: f64,
+    #[serde(rename = "meanUlpError")]
+    mean_ulp_error: f64,
+    ulp: UlpPercentiles,
+    /// Distances in power-of-two buckets (0, 1, 2, 4, ...); empty buckets are left out.
+    histogram: Vec<Bucket>,
+}
+
+#[derive(Default)]
+struct Accumulator {
+    data_type: String,
+    ulps: Vec<u64>,
+    non_finite_mismatches: usize,
+    max_abs_error: f64,
+}
+
+fn element(name: &str, value: &Value) -> Result<f64, RunnerError> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        // Non-finite floats and 64-bit integers above 2^53 come back as strings.
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+    .ok_or_else(|| RunnerError::RuntimeExecution(format!("output {name}: invalid value {value}")))
+}
+
+fn values(name: &str, output: &OutputTensor) -> Result<Vec<f64>, RunnerError> {
+    output
+        .data
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+        .iter()
+        .map(|v| element(name, v))
+        .collect()
+}
+
+impl Accumulator {
+    fn record(&mut self, reference: f64, candidate: f64) {
+        if !reference.is_finite() || !candidate.is_finite() {
+            let same = (reference.is_nan() && candidate.is_nan()) || reference == candidate;
+            if !same {
+                self.non_finite_mismatches += 1;
+            }
+            return;
+        }
+        self.max_abs_error = self.max_abs_error.max((reference - candidate).abs());
+        self.ulps.push(tolerance::ulp_distance(
+            &self.data_type,
+            reference,
+            candidate,
+        ));
+    }
+
+    fn finish(mut self) -> OutputDeviation {
+        self.ulps.sort_unstable();
+        let at = |q: f64| {
+            let index = ((self.ulps.len() as f64 * q).ceil() as usize).saturating_sub(1);
+            self.ulps.get(index).copied().unwrap_or(0)
+        };
+        let mut histogram: Vec<Bucket> = Vec::new();
+        for &ulp in &self.ulps {
+            let bound = if ulp == 0 {
+                0
+            } else {
+                ulp.checked_next_power_of_two().unwrap_or(u64::MAX)
+            };
+            match histogram.last_mut() {
+                Some(bucket) if bucket.ulp == bound => bucket.count += 1,
+                _ => histogram.push(Bucket {
+                    ulp: bound,
+                    count: 1,
+                }),
+            }
+        }
+        let sum: f64 = self.ulps.iter().map(|&ulp| ulp as f64).sum();
+        OutputDeviation {
+            elements: self.ulps.len(),
+            non_finite_mismatches: self.non_finite_mismatches,
+            max_abs_error: self.max_abs_error,
+            mean_ulp_error: if self.ulps.is_empty() {
+                0.0
+            } else {
+                sum / self.ulps.len() as f64
+            },
+            ulp: UlpPercentiles {
+                p50: at(0.5),
+                p90: at(0.9),
+                p99: at(0.99),
+                p999: at(0.999),
+                max: self.ulps.last().copied().unwrap_or(0),
+            },
+            histogram,
+            data_type: self.data_type,
+        }
+    }
+}
+
+fn narrow_const_bytes(raw: &[u8]) -> Vec<u8> {
+    raw.chunks_exact(4)
+        .flat_map(|b| f16::from_f32(f32::from_le_bytes([b[0], b[1], b[2], b[3]])).to_le_bytes())
+        .collect()
+}
+
+/// Rewrite float32 inputs, constants and dtype options in `graph` to float16.
+fn narrow_graph(graph: &mut Value) -> Result<(), RunnerError> {
+    if let Some(inputs) = graph.get_mut("inputs").and_then(Value::as_object_mut) {
+        for desc in inputs.values_mut() {
+            if desc.get("dataType").and_then(Value::as_str) == Some("float32") {
+                desc["dataType"] = Value::from("float16");
+            }
+        }
+    }
+    if let Some(consts) = graph.get_mut("consts").and_then(Value::as_object_mut) {
+        for (name, decl) in consts.iter_mut() {
+            if decl.get("dataType").and_then(Value::as_str) != Some("float32") {
+                continue;
+            }
+            graph_json::rewrite_const_bytes(name, decl, "float16 precision", 4, |raw| {
+                Ok(narrow_const_bytes(raw))
+            })?;
+            decl["dataType"] = Value::from("float16");
+        }
+    }
+    if let Some(nodes) = graph.get_mut("nodes").and_then(Value::as_array_mut) {
+        for node in nodes {
+            let Some(options) = node.get_mut("options").and_then(Value::as_object_mut) else {
+                continue;
+            };
+            for key in bf16_lowering::DTYPE_OPTION_KEYS {
+                if options.get(*key).and_then(Value::as_str) == Some("float32") {
+                    options.insert((*key).to_string(), Value::from("float16"));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Input set `sample`: distribution inputs drawn with their seed offset by `sample`.
+pub fn sample_inputs(
+    templates: &BTreeMap<String, InputTensor>,
+    sample: usize,
+) -> Result<BTreeMap<String, InputTensor>, RunnerError> {
+    let mut inputs = templates
+        .iter()
+        .map(|(name, template)| {
+            let distribution = template.distribution.clone().map(|mut spec| {
+                spec.seed = spec.seed.wrapping_add(sample as u64);
+                spec
+            });
+            let input = InputTensor {
+                descriptor: template.descriptor.clone(),
+                data: template.data.clone(),
+                distribution,
+            };
+            (name.clone(), input)
+        })
+        .collect();
+    distribution::generate_inputs(&mut inputs)?;
+    Ok(inputs)
+}
+
+/// `inputs` retyped for `side`; the values stay as drawn, so narrowing rounds them.
+fn side_inputs(
+    side: &Side,
+    inputs: &BTreeMap<String, InputTensor>,
+) -> Result<BTreeMap<String, InputTensor>, RunnerError> {
+    let narrowed = side.narrowed()?;
+    Ok(inputs
+        .iter()
+        .map(|(name, input)| {
+            let mut descriptor = input.descriptor.clone();
+            if narrowed && descriptor.data_type == "float32" {
+                descriptor.data_type = "float16".to_string();
+            }
+            let input = InputTensor {
+                descriptor,
+                data: input.data.clone(),
+                distribution: None,
+            };
+            (name.clone(), input)
+        })
+        .collect())
+}
+
+fn run_side(
+    side: &Side,
+    graph: &Value,
+    drawn: &BTreeMap<String, InputTensor>,
+    session: &str,
+    warnings: &mut Vec<WarningPayload>,
+) -> Result<BTreeMap<String, OutputTensor>, RunnerError> {
+    execute_graph(
+        graph.clone(),
+        side_inputs(side, drawn)?,
+        BTreeMap::new(),
+        side.options(),
+        session,
+        warnings,
+    )
+}
+
+/// Run `graph` on `samples` input sets on both sides and compare their outputs.
+pub fn deviation_stats(
+    graph: &Value,
+    inputs: &BTreeMap<String, InputTensor>,
+    samples: usize,
+    [reference, candidate]: [&Side; 2],
+    session: &str,
+    warnings: &mut Vec<WarningPayload>,
+) -> Result<BTreeMap<String, OutputDeviation>, RunnerError> {
+    if samples == 0 {
+        return Err(RunnerError::BadRequest(
+            "deviation_stats needs at least one sample".to_string(),
+        ));
+    }
+    reference.validate()?;
+    candidate.validate()?;
+    let graphs = [reference.graph(graph)?, candidate.graph(graph)?];
+
+    let mut accumulators: BTreeMap<String, Accumulator> = BTreeMap::new();
+    for sample in 0..samples {
+        let drawn = sample_inputs(inputs, sample)?;
+        let reference_outputs = run_side(reference, &graphs[0], &drawn, session, warnings)?;
+        let candidate_outputs = run_side(candidate, &graphs[1], &drawn, session, warnings)?;
+        for (name, expected) in &reference_outputs {
+            let actual = candidate_outputs.get(name).ok_or_else(|| {
+                RunnerError::RuntimeExecution(format!("candidate returned no output {name}"))
+            })?;
+            if actual.descriptor.shape != expected.descriptor.shape {
+                return Err(RunnerError::ShapeMismatch(format!(
+                    "output {name}: candidate returned shape {:?} but reference {:?}",
+                    actual.descriptor.shape, expected.descriptor.shape
+                )));
+            }
+            let accumulator = accumulators
+                .entry(name.clone())
+                .or_insert_with(|| Accumulator {
+                    data_type: actual.descriptor.data_type.clone(),
+                    ..Default::default()
+                });
+            for (r, c) in values(name, expected)?
+                .into_iter()
+                .zip(values(name, actual)?)
+            {
+                accumulator.record(r, c);
+            }
+        }
+    }
+    Ok(accumulators
+        .into_iter()
+        .map(|(name, accumulator)| (name, accumulator.finish()))
+        .collect())
+}
@@ -0,0 +1,117 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Dispatch-time validation of runtime inputs against the graph's declared input descriptors.
+//!
+//! Backends report mismatches late and generically (ORT fails after session setup with a bare
+//! shape error), so we compare every provided input with its declaration up front and name the
+//! offending input and dimension.
+//!
+//! Failures map onto the WebNN dispatch algorithm: a set of named inputs that does not match the
+//! graph's input descriptors (missing, extra, dtype or shape mismatch) is a `TypeError`, and data
+//! whose length does not fit the descriptor is a `DataError`.
+
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+use crate::element_count::ElementCount;
+use crate::{InputTensor, RunnerError};
+
+fn declared_shape(name: &str, desc: &Value) -> Result<Vec<usize>, RunnerError> {
+    desc.get("shape")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+        .iter()
+        .map(|d| {
+            d.as_u64().map(|d| d as usize).ok_or_else(|| {
+                RunnerError::BadRequest(format!("graph input '{name}' has invalid dimension {d}"))
+            })
+        })
+        .collect()
+}
+
+/// Check that the provided inputs are exactly the declared ones, with matching dtype, shape and
+/// data length.
+pub fn check_inputs(
+    graph: &Value,
+    inputs: &BTreeMap<String, InputTensor>,
+) -> Result<(), RunnerError> {
+    let empty = serde_json::Map::new();
+    let declared = graph
+        .get("inputs")
+        .and_then(Value::as_object)
+        .unwrap_or(&empty);
+
+    if let Some(missing) = declared.keys().find(|name| !inputs.contains_key(*name)) {
+        return Err(RunnerError::InputValidation(format!(
+            "missing input '{missing}' declared by the graph"
+        )));
+    }
+
+    for (name, input) in inputs {
+        let Some(desc) = declared.get(name) else {
+            return Err(RunnerError::InputValidation(format!(
+                "input '{name}' is not declared by the graph (declared inputs: {:?})",
+                declared.keys().collect::<Vec<_>>()
+            )));
+        };
+        let provided = &input.descriptor;
+
+        let data_type = desc
+            .get("dataType")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        if data_type != provided.data_type {
+            return Err(RunnerError::InputValidation(format!(
+                "input '{name}': graph declares dataType {data_type} but request provides {}",
+                provided.data_type
+            )));
+        }
+
+        let shape = declared_shape(name, desc)?;
+        if shape.len() != provided.shape.len() {
+            return Err(RunnerError::InputValidation(format!(
+                "input '{name}': graph declares rank {} (shape {shape:?}) but request provides rank {} (shape {:?})",
+                shape.len(),
+                provided.shape.len(),
+                provided.shape
+            )));
+        }
+        if let Some(axis) = (0..shape.len()).find(|&axis| shape[axis] != provided.shape[axis]) {
+            return Err(RunnerError::InputValidation(format!(
+                "input '{name}' dimension {axis}: graph declares {} but request provides {} (declared shape {shape:?}, provided {:?})",
+                shape[axis], provided.shape[axis], provided.shape
+            )));
+        }
+
+        // A single value is broadcast to the whole tensor by `normalize_input_values`.
+        let count = ElementCount::of(&provided.shape)?;
+        count.check_allocation(&provided.data_type)?;
+        let expected = count.get();
+        if input.data.len() != expected && input.data.len() != 1 {
+            return Err(RunnerError::InputData(format!(
+                "input '{name}': expected {expected} values for shape {:?}, got {}",
+                provided.shape,
+                input.data.len()
+            )));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,221 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Generated input data.
+//!
+//! An input may carry a `distribution` instead of `data`; the runner then fills it with
+//! pseudo-random values for its descriptor, so smoke tests can stress numerically interesting
+//! regions without shipping the tensors. Values are a pure function of the spec, the `seed` and
+//! the input name, so a failing case reproduces from the request alone and two inputs with the
+//! same spec still differ.
+
+use std::collections::BTreeMap;
+
+use half::{bf16, f16};
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::element_count::ElementCount;
+use crate::{InputTensor, RunnerError, compile_cache, encoding, int4};
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Distribution {
+    /// Uniform in `[min, max)`; integer types take the floor.
+    Uniform {
+        #[serde(default)]
+        min: f64,
+        #[serde(default = "one")]
+        max: f64,
+    },
+    /// Gaussian; integer types round to the nearest value.
+    Normal {
+        #[serde(default)]
+        mean: f64,
+        #[serde(default = "one", rename = "stdDev")]
+        std_dev: f64,
+    },
+    /// Integers within `span` of the data type's minimum or maximum.
+    NearOverflow {
+        #[serde(default = "default_span")]
+        span: u64,
+    },
+    /// Floats that are mostly subnormal, with signed zeros and the smallest normals mixed in.
+    Subnormal,
+}
+
+fn one() -> f64 {
+    1.0
+}
+
+fn default_span() -> u64 {
+    4
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DistributionSpec {
+    #[serde(flatten)]
+    pub distribution: Distribution,
+    #[serde(default)]
+    pub seed: u64,
+}
+
+/// SplitMix64; tiny, and stable across platforms and runner builds.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64, name: &str) -> Self {
+        Self(seed ^ compile_cache::fnv1a(name.as_bytes(), compile_cache::FNV_OFFSET))
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `[0, 1)`.
+    pub fn unit(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    pub fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n.max(1)
+    }
+
+    fn normal(&mut self) -> f64 {
+        // Box-Muller; `1 - unit` keeps the logarithm finite.
+        let r = (-2.0 * (1.0 - self.unit()).ln()).sqrt();
+        r * (std::f64::consts::TAU * self.unit()).cos()
+    }
+}
+
+fn integer_range(data_type: &str) -> Option<(i128, i128)> {
+    let range = match data_type {
+        "int4" | "uint4" => {
+            let (min, max) = int4::range(data_type);
+            (i128::from(min), i128::from(max))
+        }
+        "int8" => (i8::MIN.into(), i8::MAX.into()),
+        "uint8" => (0, u8::MAX.into()),
+        "int32" => (i32::MIN.into(), i32::MAX.into()),
+        "uint32" => (0, u32::MAX.into()),
+        "int64" => (i64::MIN.into(), i64::MAX.into()),
+        "uint64" => (0, u64::MAX.into()),
+        _ => return None,
+    };
+    Some(range)
+}
+
+fn integer_value(v: i128) -> Value {
+    match i64::try_from(v) {
+        Ok(v) => Value::from(v),
+        // Only uint64 values above i64::MAX get here, and the range clamp keeps them in u64.
+        Err(_) => Value::from(v as u64),
+    }
+}
+
+/// A subnormal (or, one time in eight, a signed zero or smallest normal) of `data_type`.
+fn subnormal(rng: &mut Rng, data_type: &str) -> Option<f64> {
+    let mantissa_bits = match data_type {
+        "float64" => 52,
+        "float32" => 23,
+        "float16" => 10,
+        "bfloat16" => 7,
+        _ => return None,
+    };
+    let mantissa = if rng.below(8) == 0 {
+        // 0 or the smallest normal: the edges flush-to-zero implementations get wrong.
+        rng.below(2) << mantissa_bits
+    } else {
+        1 + rng.below((1 << mantissa_bits) - 1)
+    };
+    let sign = rng.below(2);
+    let value = match data_type {
+        "float64" => f64::from_bits((sign << 63) | mantissa),
+        "float32" => f64::from(f32::from_bits(((sign << 31) | mantissa) as u32)),
+        "float16" => f16::from_bits(((sign << 15) | mantissa) as u16).to_f64(),
+        _ => bf16::from_bits(((sign << 15) | mantissa) as u16).to_f64(),
+    };
+    Some(value)
+}
+
+fn sample(
+    rng: &mut Rng,
+    distribution: &Distribution,
+    data_type: &str,
+) -> Result<Value, RunnerError> {
+    let integers = integer_range(data_type);
+    let unsupported = |kind: &str, wants: &str| {
+        RunnerError::BadRequest(format!(
+            "{kind} distribution needs {wants} dataType, got {data_type}"
+        ))
+    };
+    let real = match distribution {
+        Distribution::Uniform { min, max } => min + (max - min) * rng.unit(),
+        Distribution::Normal { mean, std_dev } => mean + std_dev * rng.normal(),
+        Distribution::NearOverflow { span } => {
+            let (min, max) = integers.ok_or_else(|| unsupported("nearOverflow", "an integer"))?;
+            let offset = i128::from(rng.below(*span));
+            let value = if rng.below(2) == 0 {
+                (min + offset).min(max)
+            } else {
+                (max - offset).max(min)
+            };
+            return Ok(integer_value(value));
+        }
+        Distribution::Subnormal => {
+            let value =
+                subnormal(rng, data_type).ok_or_else(|| unsupported("subnormal", "a float"))?;
+            return Ok(encoding::float64_value(value));
+        }
+    };
+    Ok(match integers {
+        Some((min, max)) => {
+            let rounded = match distribution {
+                Distribution::Normal { .. } => real.round(),
+                _ => real.floor(),
+            };
+            // Saturating float-to-int casts, then the data type's own range.
+            integer_value((rounded as i128).clamp(min, max))
+        }
+        None if data_type == "float64" => encoding::float64_value(real),
+        None => encoding::float_value(real as f32),
+    })
+}
+
+/// Fill every input that carries a distribution with its generated values.
+pub fn generate_inputs(inputs: &mut BTreeMap<String, InputTensor>) -> Result<(), RunnerError> {
+    for (name, input) in inputs.iter_mut() {
+        let Some(spec) = input.distribution.take() else {
+            continue;
+        };
+        let data_type = input.descriptor.data_type.as_str();
+        let count = ElementCount::of(&input.descriptor.shape)?;
+        count.check_allocation(data_type)?;
+        // The samples are held as JSON values until conversion, several times their byte size.
+        count.check_value_allocation()?;
+        let mut rng = Rng::new(spec.seed, name);
+        input.data = (0..count.get())
+            .map(|_| sample(&mut rng, &spec.distribution, data_type))
+            .collect::<Result<_, _>>()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,117 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Dynamic graph input dimensions.
+//!
+//! A graph input's `shape` may name a dimension (`"N"`) or leave it open (`null`). rustnn only
+//! validates static graphs, so before validation every such dimension is bound from the shape of
+//! the tensor the request provides for that input; a named dimension must bind to the same size
+//! in every input that uses it. The graph handed to rustnn is then fully static, and compiled
+//! artifacts are cached per resolved shape.
+
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+use crate::{InputTensor, RunnerError};
+
+fn is_dynamic(dim: &Value) -> bool {
+    dim.is_null() || dim.is_string()
+}
+
+fn describe(dim: &Value) -> String {
+    match dim.as_str() {
+        Some(symbol) => format!("'{symbol}'"),
+        None => "null".to_string(),
+    }
+}
+
+/// Fail if a graph input has a dynamic dimension; used when there are no inputs to bind it from.
+pub fn check_static(graph: &Value) -> Result<(), RunnerError> {
+    let Some(declared) = graph.get("inputs").and_then(Value::as_object) else {
+        return Ok(());
+    };
+    for (name, desc) in declared {
+        let shape = desc.get("shape").and_then(Value::as_array);
+        if let Some((axis, dim)) = shape
+            .into_iter()
+            .flatten()
+            .enumerate()
+            .find(|(_, dim)| is_dynamic(dim))
+        {
+            return Err(RunnerError::GraphValidation(format!(
+                "graph input '{name}' has dynamic dimension {} at axis {axis}; it is only resolved from the inputs of an execution request",
+                describe(dim)
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Replace each dynamic input dimension of `graph` with the size provided in `inputs`.
+///
+/// Inputs that were not provided are left alone for the dispatch check to report.
+pub fn resolve(
+    graph: &mut Value,
+    inputs: &BTreeMap<String, InputTensor>,
+) -> Result<(), RunnerError> {
+    let Some(declared) = graph.get_mut("inputs").and_then(Value::as_object_mut) else {
+        return Ok(());
+    };
+    // Named dimension -> (size, input it was first bound from).
+    let mut bound: BTreeMap<String, (usize, String)> = BTreeMap::new();
+    for (name, desc) in declared {
+        let Some(shape) = desc.get_mut("shape").and_then(Value::as_array_mut) else {
+            continue;
+        };
+        if !shape.iter().any(is_dynamic) {
+            continue;
+        }
+        let Some(input) = inputs.get(name) else {
+            continue;
+        };
+        let provided = &input.descriptor.shape;
+        if provided.len() != shape.len() {
+            return Err(RunnerError::InputValidation(format!(
+                "input '{name}' has rank {} but the graph declares rank {}",
+                provided.len(),
+                shape.len()
+            )));
+        }
+        for (axis, (dim, &size)) in shape.iter_mut().zip(provided).enumerate() {
+            if !is_dynamic(dim) {
+                continue;
+            }
+            if let Some(symbol) = dim.as_str() {
+                match bound.get(symbol) {
+                    Some((first, from)) if *first != size => {
+                        return Err(RunnerError::InputValidation(format!(
+                            "dimension '{symbol}' is {first} in input '{from}' but {size} in input '{name}' (axis {axis})"
+                        )));
+                    }
+                    Some(_) => {}
+                    None => {
+                        bound.insert(symbol.to_string(), (size, name.clone()));
+                    }
+                }
+            }
+            *dim = Value::from(size);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,103 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Overflow-checked tensor sizes.
+//!
+//! Shapes come straight from requests and fixtures, so every element count and byte length the
+//! runner derives from one goes through [`ElementCount`]. Overflow and tensors beyond the
+//! allocation limit are reported as `DataError` before anything is allocated, instead of
+//! surfacing as a capacity panic inside executor binding code.
+
+use crate::RunnerError;
+
+/// Largest tensor the runner will materialize; `RUSTNNPT_MAX_TENSOR_BYTES` overrides it.
+const DEFAULT_MAX_TENSOR_BYTES: usize = 1 << 32;
+
+pub fn max_tensor_bytes() -> usize {
+    std::env::var("RUSTNNPT_MAX_TENSOR_BYTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_MAX_TENSOR_BYTES)
+}
+
+/// Bytes per element of `data_type`; 4-bit types are counted as one byte each.
+pub fn element_size(data_type: &str) -> usize {
+    match data_type {
+        "float32" | "int32" | "uint32" => 4,
+        "float16" | "bfloat16" => 2,
+        "float64" | "int64" | "uint64" => 8,
+        _ => 1,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ElementCount(usize);
+
+impl ElementCount {
+    /// Element count of `shape`. Shapes with a zero dimension count as one element, matching
+    /// how single values are broadcast.
+    pub fn of(shape: &[usize]) -> Result<Self, RunnerError> {
+        shape
+            .iter()
+            .try_fold(1usize, |count, &dim| count.checked_mul(dim))
+            .map(|count| Self(count.max(1)))
+            .ok_or_else(|| {
+                RunnerError::InputData(format!("element count of shape {shape:?} overflows"))
+            })
+    }
+
+    pub fn get(self) -> usize {
+        self.0
+    }
+
+    /// Byte length of a `data_type` tensor with this many elements.
+    pub fn byte_len(self, data_type: &str) -> Result<usize, RunnerError> {
+        self.0.checked_mul(element_size(data_type)).ok_or_else(|| {
+            RunnerError::InputData(format!(
+                "{data_type} tensor of {} elements overflows its byte length",
+                self.0
+            ))
+        })
+    }
+
+    /// Fail if a `data_type` tensor of this size exceeds the allocation limit.
+    pub fn check_allocation(self, data_type: &str) -> Result<(), RunnerError> {
+        let bytes = self.byte_len(data_type)?;
+        let limit = max_tensor_bytes();
+        if bytes > limit {
+            return Err(RunnerError::InputData(format!(
+                "{data_type} tensor of {} elements needs {bytes} bytes, over the {limit} byte limit",
+                self.0
+            )));
+        }
+        Ok(())
+    }
+
+    /// Fail if holding this many elements as JSON values, the way request data is kept before it
+    /// is converted for an executor, exceeds the allocation limit.
+    pub fn check_value_allocation(self) -> Result<(), RunnerError> {
+        let limit = max_tensor_bytes();
+        match self.0.checked_mul(std::mem::size_of::<serde_json::Value>()) {
+            Some(bytes) if bytes <= limit => Ok(()),
+            _ => Err(RunnerError::InputData(format!(
+                "{} elements held as JSON values exceed the {limit} byte limit",
+                self.0
+            ))),
+        }
+    }
+}
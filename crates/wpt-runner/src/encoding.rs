@@ -0,0 +1,209 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! `dataEncoding: "base64"` and `"npy"` tensor transport.
+//!
+//! Instead of one JSON value per element, `data` is a base64 string of the raw little-endian
+//! element bytes for the tensor's `dataType`. With `"npy"` it is a base64 `.npy` file instead,
+//! as written by `numpy.save`, whose shape must match the descriptor and whose dtype must hold
+//! the `dataType` (see [`npz::storage_type`]). Decoded inputs are turned back into JSON values
+//! so every later check sees the same representation as the default encoding.
+//!
+//! JSON numbers cannot carry NaN payloads, and JavaScript serializes -0 as 0, so float elements
+//! may also be sent as hex bit patterns of their `dataType` (`"0x7fc00001"` for float32,
+//! `"0x8000"` for float16, 16 digits for float64). Expected outputs with `bitExact` are
+//! returned the same way.
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use half::{bf16, f16};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::element_count::ElementCount;
+use crate::{int4, npz};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DataEncoding {
+    #[default]
+    Json,
+    Base64,
+    Npy,
+}
+
+impl DataEncoding {
+    pub fn is_json(&self) -> bool {
+        *self == Self::Json
+    }
+}
+
+pub fn float_value(v: f32) -> Value {
+    float64_value(f64::from(v))
+}
+
+pub fn float64_value(v: f64) -> Value {
+    // Non-finite floats have no JSON number; `parse_f32` accepts Rust's spelling of them.
+    if v.is_finite() {
+        Value::from(v)
+    } else {
+        Value::String(v.to_string())
+    }
+}
+
+fn chunks<const N: usize>(bytes: &[u8], data_type: &str) -> Result<Vec<[u8; N]>, String> {
+    if bytes.len() % N != 0 {
+        return Err(format!(
+            "base64 payload of {} bytes is not a multiple of {N} for {data_type}",
+            bytes.len()
+        ));
+    }
+    Ok(bytes
+        .chunks_exact(N)
+        .map(|chunk| {
+            let mut arr = [0u8; N];
+            arr.copy_from_slice(chunk);
+            arr
+        })
+        .collect())
+}
+
+fn decode_base64(encoded: &str) -> Result<Vec<u8>, String> {
+    STANDARD
+        .decode(encoded)
+        .map_err(|e| format!("invalid base64 data: {e}"))
+}
+
+/// Decode a base64 `data` string into one JSON value per element of `data_type`.
+pub fn decode_values(
+    data_type: &str,
+    encoded: &str,
+    element_count: usize,
+) -> Result<Vec<Value>, String> {
+    decode_bytes(data_type, &decode_base64(encoded)?, element_count)
+}
+
+/// Decode a base64 `.npy` file into one JSON value per element of a `data_type` tensor of
+/// `shape`.
+pub fn decode_npy(data_type: &str, shape: &[usize], encoded: &str) -> Result<Vec<Value>, String> {
+    let bytes = decode_base64(encoded)?;
+    let array = npz::parse_npy(&bytes)?;
+    if array.shape != shape {
+        return Err(format!(
+            "npy array of shape {:?} does not match shape {shape:?}",
+            array.shape
+        ));
+    }
+    let stored = npz::storage_type(&array.descr, data_type)
+        .ok_or_else(|| format!("npy dtype {} cannot hold {data_type} data", array.descr))?;
+    let count = ElementCount::of(&array.shape)
+        .map_err(|e| e.to_string())?
+        .get();
+    decode_bytes(stored, array.data, count)
+}
+
+/// One JSON value per element of little-endian `data_type` storage bytes.
+pub fn decode_bytes(
+    data_type: &str,
+    bytes: &[u8],
+    element_count: usize,
+) -> Result<Vec<Value>, String> {
+    let values = match data_type {
+        "float32" => chunks::<4>(bytes, data_type)?
+            .into_iter()
+            .map(|b| float_value(f32::from_le_bytes(b)))
+            .collect(),
+        "float64" => chunks::<8>(bytes, data_type)?
+            .into_iter()
+            .map(|b| float64_value(f64::from_le_bytes(b)))
+            .collect(),
+        "float16" => chunks::<2>(bytes, data_type)?
+            .into_iter()
+            .map(|b| float_value(f16::from_bits(u16::from_le_bytes(b)).to_f32()))
+            .collect(),
+        "bfloat16" => chunks::<2>(bytes, data_type)?
+            .into_iter()
+            .map(|b| float_value(bf16::from_bits(u16::from_le_bytes(b)).to_f32()))
+            .collect(),
+        "int8" => bytes.iter().map(|&b| Value::from(b as i8)).collect(),
+        "uint8" => bytes.iter().map(|&b| Value::from(b)).collect(),
+        "int4" | "uint4" => int4::unpack(data_type, bytes, element_count)?
+            .into_iter()
+            .map(Value::from)
+            .collect(),
+        "int32" => chunks::<4>(bytes, data_type)?
+            .into_iter()
+            .map(|b| Value::from(i32::from_le_bytes(b)))
+            .collect(),
+        "uint32" => chunks::<4>(bytes, data_type)?
+            .into_iter()
+            .map(|b| Value::from(u32::from_le_bytes(b)))
+            .collect(),
+        "int64" => chunks::<8>(bytes, data_type)?
+            .into_iter()
+            .map(|b| Value::from(i64::from_le_bytes(b)))
+            .collect(),
+        "uint64" => chunks::<8>(bytes, data_type)?
+            .into_iter()
+            .map(|b| Value::from(u64::from_le_bytes(b)))
+            .collect(),
+        other => return Err(format!("base64 data is not supported for dataType {other}")),
+    };
+    Ok(values)
+}
+
+pub fn encode_bytes(bytes: &[u8]) -> Value {
+    Value::String(STANDARD.encode(bytes))
+}
+
+/// Bits of a `"0x..."` element of a `width`-bit float type, or `None` for any other value.
+pub fn hex_bits(v: &Value, width: u32) -> Option<Result<u64, String>> {
+    let digits = v.as_str()?.strip_prefix("0x")?;
+    Some(
+        u64::from_str_radix(digits, 16)
+            .ok()
+            .filter(|_| digits.len() <= width as usize / 4)
+            .ok_or_else(|| format!("invalid {width}-bit float bit pattern: 0x{digits}")),
+    )
+}
+
+fn bit_pattern(bits: u64, width: u32) -> Value {
+    Value::String(format!("0x{bits:0digits$x}", digits = width as usize / 4))
+}
+
+/// Hex bit patterns of float elements cast to `data_type`; `None` for non-float types, whose
+/// JSON values are already exact.
+pub fn float_bit_patterns(data: &[f64], data_type: &str) -> Option<Vec<Value>> {
+    let pattern: fn(f64) -> Value = match data_type {
+        "float64" => |v| bit_pattern(v.to_bits(), 64),
+        "float32" => |v| bit_pattern(u64::from((v as f32).to_bits()), 32),
+        "float16" => |v| bit_pattern(u64::from(f16::from_f32(v as f32).to_bits()), 16),
+        "bfloat16" => |v| bit_pattern(u64::from(bf16::from_f32(v as f32).to_bits()), 16),
+        _ => return None,
+    };
+    Some(data.iter().map(|x| pattern(*x)).collect())
+}
+
+/// `float_value`, except that NaN keeps its payload as a float32 bit pattern.
+pub fn float_value_bits(v: f32) -> Value {
+    if v.is_nan() {
+        bit_pattern(u64::from(v.to_bits()), 32)
+    } else {
+        float_value(v)
+    }
+}
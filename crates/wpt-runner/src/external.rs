@@ -0,0 +1,214 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Differential runs against an external oracle process.
+//!
+//! With context option `compareExternal` set to a command line, the runner also hands every
+//! compiled graph and its inputs to that process and compares the oracle's outputs with the
+//! rustnn executor's. The oracle speaks the remote executor protocol on stdin/stdout: one
+//! `run_compiled` request line in, one response line with `raw_outputs` out (see
+//! `scripts/ort-oracle.py`, which runs onnx artifacts with the onnxruntime Python package).
+//! Feeding both sides the same converted artifact isolates bugs in rustnn's runtime binding
+//! layer from conversion bugs.
+//!
+//! Oracles are started on first use and kept alive per command line. Requests to one oracle are
+//! serialized, while different oracles run concurrently. A failed exchange, or one without a
+//! reply within [`ORACLE_TIMEOUT`], kills the process so the next request starts a fresh one.
+
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use serde_json::Value;
+
+use crate::compiled::CompiledGraph;
+use crate::tolerance::{self, Tolerance, Verdict};
+use crate::{InputTensor, RunnerError, RuntimeOutput, remote};
+
+/// Longest an oracle may take to answer one request.
+const ORACLE_TIMEOUT: Duration = Duration::from_secs(120);
+
+struct Oracle {
+    child: Child,
+    stdin: ChildStdin,
+    /// Reply lines, read on a separate thread so a hung oracle can be timed out; an empty line
+    /// means stdout closed.
+    replies: Receiver<std::io::Result<String>>,
+}
+
+impl Oracle {
+    fn spawn(command: &str) -> Result<Self, String> {
+        let mut parts = command.split_whitespace();
+        let program = parts.next().ok_or("empty compareExternal command")?;
+        let mut child = Command::new(program)
+            .args(parts)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(|e| format!("cannot start '{command}': {e}"))?;
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            return Err(format!("'{command}' has no stdio pipes"));
+        };
+        let (tx, replies) = mpsc::channel();
+        std::thread::spawn(move || {
+            let mut stdout = BufReader::new(stdout);
+            loop {
+                let mut reply = String::new();
+                let read = stdout.read_line(&mut reply).map(|_| reply);
+                let done = !matches!(&read, Ok(reply) if !reply.is_empty());
+                if tx.send(read).is_err() || done {
+                    return;
+                }
+            }
+        });
+        Ok(Self {
+            child,
+            stdin,
+            replies,
+        })
+    }
+
+    fn exchange(&mut self, line: &[u8]) -> Result<String, String> {
+        self.stdin.write_all(line).map_err(|e| e.to_string())?;
+        self.stdin.flush().map_err(|e| e.to_string())?;
+        match self.replies.recv_timeout(ORACLE_TIMEOUT) {
+            Ok(reply) => reply.map_err(|e| e.to_string()),
+            Err(RecvTimeoutError::Timeout) => {
+                // Kill it now, so requests queued behind this one fail fast and a late reply
+                // cannot be taken for theirs.
+                let _ = self.child.kill();
+                Err(format!("no response within {} s", ORACLE_TIMEOUT.as_secs()))
+            }
+            Err(RecvTimeoutError::Disconnected) => Ok(String::new()),
+        }
+    }
+}
+
+impl Drop for Oracle {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+type SharedOracle = Arc<Mutex<Oracle>>;
+
+fn oracles() -> &'static Mutex<HashMap<String, SharedOracle>> {
+    static ORACLES: OnceLock<Mutex<HashMap<String, SharedOracle>>> = OnceLock::new();
+    ORACLES.get_or_init(Mutex::default)
+}
+
+/// The running oracle for `command`, started if there is none.
+fn oracle(command: &str) -> Result<SharedOracle, String> {
+    let mut running = oracles().lock().unwrap_or_else(|e| e.into_inner());
+    Ok(match running.entry(command.to_string()) {
+        Entry::Occupied(entry) => Arc::clone(entry.get()),
+        Entry::Vacant(entry) => {
+            Arc::clone(entry.insert(Arc::new(Mutex::new(Oracle::spawn(command)?))))
+        }
+    })
+}
+
+/// Forget `oracle` so the next request for `command` starts a fresh process.
+fn discard(command: &str, oracle: &SharedOracle) {
+    let mut running = oracles().lock().unwrap_or_else(|e| e.into_inner());
+    if running
+        .get(command)
+        .is_some_and(|entry| Arc::ptr_eq(entry, oracle))
+    {
+        running.remove(command);
+    }
+}
+
+/// Run `compiled` on the oracle started by `command`.
+pub fn run(
+    command: &str,
+    compiled: &CompiledGraph,
+    inputs: &BTreeMap<String, InputTensor>,
+) -> Result<Vec<RuntimeOutput>, RunnerError> {
+    let failed = |e: String| RunnerError::RuntimeExecution(format!("oracle '{command}': {e}"));
+    let line = remote::request_line(compiled, inputs).map_err(failed)?;
+
+    let shared = oracle(command).map_err(failed)?;
+    let result = shared
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .exchange(&line);
+    match result {
+        Ok(reply) if !reply.is_empty() => {
+            remote::parse_reply(&format!("oracle '{command}'"), &reply)
+        }
+        result => {
+            discard(command, &shared);
+            Err(failed(match result {
+                Err(e) => e,
+                Ok(_) => "exited without a response".to_string(),
+            }))
+        }
+    }
+}
+
+/// `reference` as expected values for [`tolerance::check`].
+fn reference_values(reference: &RuntimeOutput) -> Vec<Value> {
+    match (&reference.int64_data, &reference.uint64_data) {
+        (Some(values), _) => values.iter().map(|&v| Value::from(v)).collect(),
+        (_, Some(values)) => values.iter().map(|&v| Value::from(v)).collect(),
+        _ => reference
+            .data
+            .iter()
+            .map(|&v| {
+                if v.is_finite() {
+                    Value::from(v)
+                } else if v.is_nan() {
+                    Value::from("NaN")
+                } else if v > 0.0 {
+                    Value::from("Infinity")
+                } else {
+                    Value::from("-Infinity")
+                }
+            })
+            .collect(),
+    }
+}
+
+/// Compare rustnn's `output` with the oracle's. Without a `tolerance` they must agree exactly.
+pub fn compare(
+    tolerance: Option<&Tolerance>,
+    data_type: &str,
+    output: &RuntimeOutput,
+    reference: &RuntimeOutput,
+) -> Result<Verdict, RunnerError> {
+    if output.shape != reference.shape {
+        return Err(RunnerError::RuntimeExecution(format!(
+            "output {}: oracle returned shape {:?} but rustnn returned {:?}",
+            output.name, reference.shape, output.shape
+        )));
+    }
+    let exact = Tolerance::default();
+    tolerance::check(
+        tolerance.unwrap_or(&exact),
+        data_type,
+        output,
+        &reference_values(reference),
+    )
+}
@@ -0,0 +1,216 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Failure bundles for reproducing CI failures locally.
+//!
+//! With `RUSTNNPT_FAILURE_DIR` set, every `execute_graph` or `run_op` request that fails with
+//! `GraphConversionError` or `RuntimeExecutionError` leaves a directory named after its id, and
+//! so does every such item of an `execute_batch`. Ids that are not a plain file name (empty,
+//! `.`/`..`, or with characters other than ASCII letters, digits, `-`, `_` and `.`) get the
+//! offending characters replaced and a hash of the id appended, so they stay inside the root
+//! and distinct ids never share a directory:
+//!
+//! - `request.json`: the request line as received; `wpt-runner < request.json` replays it
+//! - `error.json`: the error payload of the response
+//! - `graph.json`: the graph as sent (synthesized for `run_op`)
+//! - `prepared.json`: the graph after the runner's rewrites, as handed to rustnn
+//! - `model.<target>` and `weights.bin`: the converter output, when conversion succeeded
+//! - `inputs.npz`: the runtime inputs after the runner's rewrites, one array per input
+//! - `repro.js`: the graph as `MLGraphBuilder` calls on the request's inputs, to run in a browser
+//! - `environment.json`: runner version, backend fingerprint, platform and rustnn/ORT env vars
+//!
+//! The bundle is rebuilt from the request after the failure instead of being captured along the
+//! way, so requests pay nothing while dumping is off; every stage up to conversion is
+//! deterministic. A stage that fails while rebuilding is noted in `rebuild-error.txt`.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde_json::{Value, json};
+
+use crate::{
+    Backend, Request, Response, RunnerError, compile_cache, compiled, distribution, freeze, npz,
+    parse_request, prepare_graph, repro_js, synthesize_op_graph, to_tensor_data,
+    validate_and_compile,
+};
+
+const DUMPED_KINDS: &[&str] = &["GraphConversionError", "RuntimeExecutionError"];
+
+/// A single path component for `id`.
+fn dir_name(id: &str) -> String {
+    let name: String = id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if name == id && !name.chars().all(|c| c == '.') {
+        return name;
+    }
+    let hash = compile_cache::fnv1a(id.as_bytes(), compile_cache::FNV_OFFSET);
+    format!("{}-{hash:016x}", name.trim_start_matches('.'))
+}
+
+fn dump_dir(id: &str) -> Option<PathBuf> {
+    let root = std::env::var_os("RUSTNNPT_FAILURE_DIR")?;
+    Some(PathBuf::from(root).join(dir_name(id)))
+}
+
+/// Write the bundle for `response` if it is a dumped failure of the request in `raw`.
+pub fn on_response(raw: &str, response: &Response) {
+    if let Some(responses) = &response.responses {
+        on_batch_response(raw, responses);
+    }
+    let Some(error) = &response.error else {
+        return;
+    };
+    if !DUMPED_KINDS.contains(&error.kind.as_str()) {
+        return;
+    }
+    let Some(dir) = dump_dir(&response.id) else {
+        return;
+    };
+    let written = std::fs::create_dir_all(&dir)
+        .and_then(|()| std::fs::write(dir.join("request.json"), raw))
+        .and_then(|()| write_json(&dir, "error.json", &json!(error)));
+    if let Err(err) = written {
+        eprintln!("[RUNNER] failure dump {}: {err}", dir.display());
+        return;
+    }
+    if let Err(err) = rebuild(&dir, raw) {
+        let _ = std::fs::write(dir.join("rebuild-error.txt"), err.to_string());
+    }
+    eprintln!("[RUNNER] failure bundle written to {}", dir.display());
+}
+
+/// Dump the failed items of the `execute_batch` request in `raw`, each as its own request.
+fn on_batch_response(raw: &str, responses: &[Response]) {
+    if std::env::var_os("RUSTNNPT_FAILURE_DIR").is_none() {
+        return;
+    }
+    let Ok(batch) = serde_json::from_str::<Value>(raw) else {
+        return;
+    };
+    let Some(items) = batch.get("requests").and_then(Value::as_array) else {
+        return;
+    };
+    for (item, response) in items.iter().zip(responses) {
+        if response.error.is_some() {
+            on_response(&item.to_string(), response);
+        }
+    }
+}
+
+fn write_json(dir: &Path, name: &str, value: &Value) -> std::io::Result<()> {
+    let text = serde_json::to_string_pretty(value).map_err(std::io::Error::other)?;
+    std::fs::write(dir.join(name), text)
+}
+
+fn io_failed(err: std::io::Error) -> RunnerError {
+    RunnerError::RuntimeExecution(format!("writing bundle: {err}"))
+}
+
+fn environment(target: &str) -> Value {
+    let vars: BTreeMap<String, String> = std::env::vars()
+        .filter(|(key, _)| {
+            key.starts_with("RUSTNN") || key.starts_with("ORT_") || key == "RUST_BACKTRACE"
+        })
+        .collect();
+    json!({
+        "runnerVersion": env!("CARGO_PKG_VERSION"),
+        "target": target,
+        "fingerprint": compiled::capability_fingerprint(target),
+        "os": std::env::consts::OS,
+        "arch": std::env::consts::ARCH,
+        "env": vars,
+    })
+}
+
+/// Re-run the request's pipeline up to conversion, writing each stage's artifact.
+fn rebuild(dir: &Path, raw: &str) -> Result<(), RunnerError> {
+    let request = parse_request(raw)?
+        .map_err(|e| RunnerError::BadRequest(format!("invalid json request: {e}")))?;
+    let (graph, mut inputs, freeze_inputs, mut context_options) = match request {
+        Request::ExecuteGraph {
+            graph,
+            inputs,
+            freeze_inputs,
+            mut context_options,
+            target,
+            ..
+        } => {
+            if target.is_some() {
+                context_options.backend = target;
+            }
+            (graph, inputs, freeze_inputs, context_options)
+        }
+        Request::RunOp(mut request) => {
+            distribution::generate_inputs(&mut request.inputs)?;
+            let graph = synthesize_op_graph(
+                &request.op,
+                &request.inputs,
+                request.input_order,
+                request.options,
+                request.outputs,
+            )?;
+            (graph, request.inputs, Vec::new(), request.context_options)
+        }
+        _ => return Ok(()),
+    };
+    // The bundle replays locally, so a remote executor from the failing run is not needed.
+    context_options.remote_address = None;
+
+    let backend = Backend::from_context(&context_options)?;
+    write_json(dir, "environment.json", &environment(backend.target())).map_err(io_failed)?;
+
+    let mut graph = graph;
+    write_json(dir, "graph.json", &graph).map_err(io_failed)?;
+    distribution::generate_inputs(&mut inputs)?;
+    // Before freezing, so the repro still takes the frozen inputs as inputs.
+    let repro = repro_js::to_js(&graph, &inputs);
+    freeze::freeze_inputs(&mut graph, &mut inputs, &freeze_inputs)?;
+    let mut warnings = Vec::new();
+    let prepared = prepare_graph(graph, Some(&mut inputs), &context_options, &mut warnings)?;
+    let prepared_json = serde_json::to_value(&prepared.graph)
+        .map_err(|e| RunnerError::GraphConversion(format!("serializing prepared graph: {e}")))?;
+    write_json(dir, "prepared.json", &prepared_json).map_err(io_failed)?;
+
+    let mut arrays = BTreeMap::new();
+    for (name, input) in &inputs {
+        let data = to_tensor_data(&input.descriptor, &input.data)?;
+        arrays.insert(name.clone(), npz::npy(&input.descriptor.shape, data));
+    }
+    let archive = npz::archive(&arrays).map_err(RunnerError::InputData)?;
+    std::fs::write(dir.join("inputs.npz"), archive).map_err(io_failed)?;
+    std::fs::write(dir.join("repro.js"), repro?).map_err(io_failed)?;
+
+    let compiled = validate_and_compile(backend, &prepared)?;
+    std::fs::write(
+        dir.join(format!("model.{}", backend.target())),
+        &compiled.artifact,
+    )
+    .map_err(io_failed)?;
+    if let Some(weights) = &compiled.weights {
+        std::fs::write(dir.join("weights.bin"), weights).map_err(io_failed)?;
+    }
+    Ok(())
+}
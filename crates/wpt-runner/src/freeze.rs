@@ -0,0 +1,87 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Graph freezing: bind selected runtime inputs as inline constants.
+//!
+//! Frozen inputs are moved from the graph's `inputs` to `consts` before the graph is parsed, so
+//! the usual validation runs on the specialized graph and converters can constant-fold through
+//! them.
+
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+use crate::{
+    InputTensor, RunnerError, bf16_lowering, int4, normalize_input_values, parse_i64,
+    tensor_data_to_le_bytes, to_tensor_data,
+};
+
+/// Little-endian bytes for an input tensor, in the layout webnn-graph-json `inlineBytes` expects.
+fn inline_bytes(input: &InputTensor) -> Result<Vec<u8>, RunnerError> {
+    match input.descriptor.data_type.as_str() {
+        // 4-bit types travel as one byte per element, like the harness packs them.
+        "int4" | "uint4" => normalize_input_values(&input.descriptor, &input.data)?
+            .iter()
+            .map(|v| {
+                let n = parse_i64(v)?;
+                int4::check_range(&input.descriptor.data_type, n)
+                    .map_err(RunnerError::BadRequest)?;
+                Ok(n as u8)
+            })
+            .collect(),
+        "bfloat16" => normalize_input_values(&input.descriptor, &input.data)?
+            .iter()
+            .map(|v| bf16_lowering::parse(v).map(bf16_lowering::to_bits))
+            .collect::<Result<Vec<_>, _>>()
+            .map(|bits| bits.into_iter().flat_map(u16::to_le_bytes).collect()),
+        _ => to_tensor_data(&input.descriptor, &input.data).map(tensor_data_to_le_bytes),
+    }
+}
+
+/// Convert the runtime inputs named in `names` into constants of `graph`.
+pub fn freeze_inputs(
+    graph: &mut Value,
+    inputs: &mut BTreeMap<String, InputTensor>,
+    names: &[String],
+) -> Result<(), RunnerError> {
+    for name in names {
+        let input = inputs.remove(name).ok_or_else(|| {
+            RunnerError::BadRequest(format!("freeze_inputs: no runtime input named '{name}'"))
+        })?;
+        let declared = graph
+            .get_mut("inputs")
+            .and_then(Value::as_object_mut)
+            .and_then(|declared| declared.remove(name));
+        if declared.is_none() {
+            return Err(RunnerError::BadRequest(format!(
+                "freeze_inputs: graph does not declare input '{name}'"
+            )));
+        }
+
+        let bytes = inline_bytes(&input)?;
+        graph["consts"][name.as_str()] = serde_json::json!({
+            "dataType": input.descriptor.data_type,
+            "shape": input.descriptor.shape,
+            "init": {
+                "kind": "inlineBytes",
+                "bytes": bytes,
+            },
+        });
+    }
+    Ok(())
+}
@@ -0,0 +1,273 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Structural diff of two webnn-graph-json documents.
+//!
+//! Used to check what the runner's rewrites did to a graph, and to snapshot graphs in converter
+//! tests. Inputs, constants and outputs are matched by name and nodes by their output names, so
+//! a rewritten node still lines up with the one it replaced. Inline constant data is compared
+//! element-wise, float types within an absolute `tolerance`.
+
+use std::collections::BTreeSet;
+
+use half::f16;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::graph_json::{section, string_list};
+use crate::weight_prepack::const_bytes;
+
+/// Graph sections compared entry by entry; any other top-level key is compared as a value.
+const SECTIONS: [&str; 4] = ["inputs", "consts", "nodes", "outputs"];
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Difference {
+    /// Dotted location, e.g. `consts.w.shape` or `nodes[conv_out].options.strides`.
+    pub path: String,
+    pub kind: DiffKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub before: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<Value>,
+    /// Summary for differences too large to inline, such as constant data.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GraphDiff {
+    pub equal: bool,
+    pub differences: Vec<Difference>,
+}
+
+struct Differ {
+    tolerance: f64,
+    differences: Vec<Difference>,
+}
+
+impl Differ {
+    fn push(&mut self, path: String, before: Option<&Value>, after: Option<&Value>) {
+        let kind = match (before, after) {
+            (None, _) => DiffKind::Added,
+            (_, None) => DiffKind::Removed,
+            _ => DiffKind::Changed,
+        };
+        self.differences.push(Difference {
+            path,
+            kind,
+            before: before.cloned(),
+            after: after.cloned(),
+            detail: None,
+        });
+    }
+
+    /// Recurse into objects key by key; anything else is compared whole.
+    fn values(&mut self, path: &str, before: &Value, after: &Value) {
+        match (before, after) {
+            (Value::Object(before), Value::Object(after)) => {
+                self.entries(path, &entries(before), &entries(after), Self::values)
+            }
+            // 1 and 1.0 are the same option value.
+            (Value::Number(a), Value::Number(b)) if a.as_f64() == b.as_f64() => {}
+            _ if before != after => self.push(path.to_string(), Some(before), Some(after)),
+            _ => {}
+        }
+    }
+
+    fn entries(
+        &mut self,
+        path: &str,
+        before: &[(String, &Value)],
+        after: &[(String, &Value)],
+        compare: fn(&mut Self, &str, &Value, &Value),
+    ) {
+        let find = |side: &[(String, &Value)], key: &str| {
+            side.iter().find(|(k, _)| k == key).map(|(_, v)| *v)
+        };
+        let keys: BTreeSet<&str> = before
+            .iter()
+            .chain(after)
+            .map(|(k, _)| k.as_str())
+            .collect();
+        for key in keys {
+            let child = join(path, key);
+            match (find(before, key), find(after, key)) {
+                (Some(b), Some(a)) => compare(self, &child, b, a),
+                (b, a) => self.push(child, b, a),
+            }
+        }
+    }
+
+    fn constant(&mut self, path: &str, before: &Value, after: &Value) {
+        let without_init = |decl: &Value| {
+            let mut decl = decl.as_object().cloned().unwrap_or_default();
+            decl.remove("init");
+            Value::Object(decl)
+        };
+        self.values(path, &without_init(before), &without_init(after));
+        let data_type = before.get("dataType").and_then(Value::as_str);
+        if data_type != after.get("dataType").and_then(Value::as_str) {
+            return;
+        }
+        match (const_bytes(before), const_bytes(after)) {
+            (Some(b), Some(a)) => {
+                if let Some(detail) = self.data(data_type.unwrap_or_default(), &b, &a) {
+                    self.differences.push(Difference {
+                        path: join(path, "data"),
+                        kind: DiffKind::Changed,
+                        before: None,
+                        after: None,
+                        detail: Some(detail),
+                    });
+                }
+            }
+            _ => self.values(
+                &join(path, "init"),
+                before.get("init").unwrap_or(&Value::Null),
+                after.get("init").unwrap_or(&Value::Null),
+            ),
+        }
+    }
+
+    /// Summary of the elements that differ, or `None` when the data matches.
+    fn data(&self, data_type: &str, before: &[u8], after: &[u8]) -> Option<String> {
+        if before.len() != after.len() {
+            return Some(format!(
+                "{} bytes differ from {} bytes",
+                before.len(),
+                after.len()
+            ));
+        }
+        let (Some(before), Some(after)) = (floats(data_type, before), floats(data_type, after))
+        else {
+            let count = before.iter().zip(after).filter(|(b, a)| b != a).count();
+            return (count > 0).then(|| format!("{count} of {} bytes differ", before.len()));
+        };
+        let mut count = 0;
+        let mut max = 0f64;
+        for (b, a) in before.iter().zip(&after) {
+            let delta = (b - a).abs();
+            let same = delta <= self.tolerance || (b.is_nan() && a.is_nan()) || b == a;
+            if !same {
+                count += 1;
+                max = max.max(delta);
+            }
+        }
+        (count > 0).then(|| {
+            format!(
+                "{count} of {} elements differ by more than {} (max {max})",
+                before.len(),
+                self.tolerance
+            )
+        })
+    }
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() || key.starts_with('[') {
+        format!("{path}{key}")
+    } else {
+        format!("{path}.{key}")
+    }
+}
+
+fn entries<'a>(map: impl IntoIterator<Item = (&'a String, &'a Value)>) -> Vec<(String, &'a Value)> {
+    map.into_iter().map(|(k, v)| (k.clone(), v)).collect()
+}
+
+/// Nodes keyed by their output names; node ids are not kept stable by rewrites.
+fn nodes(graph: &Value) -> Vec<(String, &Value)> {
+    let nodes = graph.get("nodes").and_then(Value::as_array);
+    nodes
+        .into_iter()
+        .flatten()
+        .enumerate()
+        .map(|(index, node)| {
+            let outputs = string_list(node.get("outputs"));
+            let key = if outputs.is_empty() {
+                format!("#{index}")
+            } else {
+                outputs.join(",")
+            };
+            (format!("[{key}]"), node)
+        })
+        .collect()
+}
+
+fn floats(data_type: &str, bytes: &[u8]) -> Option<Vec<f64>> {
+    match data_type {
+        "float32" => Some(
+            bytes
+                .chunks_exact(4)
+                .map(|b| f64::from(f32::from_le_bytes([b[0], b[1], b[2], b[3]])))
+                .collect(),
+        ),
+        "float16" => Some(
+            bytes
+                .chunks_exact(2)
+                .map(|b| f16::from_bits(u16::from_le_bytes([b[0], b[1]])).to_f64())
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+/// Differences from `before` to `after`.
+pub fn diff(before: &Value, after: &Value, tolerance: f64) -> GraphDiff {
+    let mut differ = Differ {
+        tolerance,
+        differences: Vec::new(),
+    };
+    differ.entries(
+        "inputs",
+        &entries(section(before, "inputs")),
+        &entries(section(after, "inputs")),
+        Differ::values,
+    );
+    differ.entries(
+        "consts",
+        &entries(section(before, "consts")),
+        &entries(section(after, "consts")),
+        Differ::constant,
+    );
+    differ.entries("nodes", &nodes(before), &nodes(after), Differ::values);
+    differ.entries(
+        "outputs",
+        &entries(section(before, "outputs")),
+        &entries(section(after, "outputs")),
+        Differ::values,
+    );
+    let rest = |graph: &Value| {
+        let mut rest = graph.as_object().cloned().unwrap_or_default();
+        rest.retain(|key, _| !SECTIONS.contains(&key.as_str()));
+        Value::Object(rest)
+    };
+    differ.values("", &rest(before), &rest(after));
+    GraphDiff {
+        equal: differ.differences.is_empty(),
+        differences: differ.differences,
+    }
+}
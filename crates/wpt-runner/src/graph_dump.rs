@@ -0,0 +1,237 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! DOT and Mermaid renderings of a validated graph.
+//!
+//! Unlike the backend plan, this is a plain picture of the prepared graph with every edge
+//! labeled by the operand it carries, its data type and the shape rustnn inferred for it, which
+//! is what matters when working out why a large graph fails to convert. Operands a node reads
+//! through operand-valued options (`bias`, `scale`, ...) get edges too, labeled with the option.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::graph_json::string_list;
+use crate::inference_rewrite::{operand_order, option_operands};
+use crate::plan::quote;
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DumpFormat {
+    #[default]
+    Dot,
+    Mermaid,
+}
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Input,
+    Constant,
+    Operation,
+    Output,
+}
+
+struct Vertex {
+    kind: Kind,
+    label: Vec<String>,
+}
+
+struct Edge {
+    from: usize,
+    to: usize,
+    label: String,
+}
+
+/// `name: float32[1,3,224,224]`, with `?` for whatever could not be inferred.
+fn operand_label(
+    name: &str,
+    types: &HashMap<String, &str>,
+    shapes: &HashMap<String, Vec<usize>>,
+) -> String {
+    let data_type = types.get(name).copied().unwrap_or("?");
+    let shape = shapes.get(name).map_or_else(
+        || "?".to_string(),
+        |dims| {
+            let dims: Vec<String> = dims.iter().map(usize::to_string).collect();
+            dims.join(",")
+        },
+    );
+    format!("{name}: {data_type}[{shape}]")
+}
+
+fn build(
+    graph: &Value,
+    types: &HashMap<String, &str>,
+    shapes: &HashMap<String, Vec<usize>>,
+) -> (Vec<Vertex>, Vec<Edge>) {
+    let mut vertices = Vec::new();
+    let mut edges = Vec::new();
+    // Operand name -> vertex that produces it.
+    let mut producers: HashMap<&str, usize> = HashMap::new();
+    for (section, kind) in [("inputs", Kind::Input), ("consts", Kind::Constant)] {
+        for name in graph
+            .get(section)
+            .and_then(Value::as_object)
+            .into_iter()
+            .flatten()
+            .map(|(k, _)| k)
+        {
+            producers.insert(name, vertices.len());
+            vertices.push(Vertex {
+                kind,
+                label: vec![operand_label(name, types, shapes)],
+            });
+        }
+    }
+
+    let nodes = graph
+        .get("nodes")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let order = operand_order(graph);
+    for (index, node) in nodes.iter().enumerate() {
+        let op = node.get("op").and_then(Value::as_str).unwrap_or("?");
+        let node_id = node
+            .get("id")
+            .and_then(Value::as_str)
+            .map_or_else(|| format!("#{index}"), str::to_string);
+        let vertex = vertices.len();
+        vertices.push(Vertex {
+            kind: Kind::Operation,
+            label: vec![node_id, op.to_string()],
+        });
+        for input in string_list(node.get("inputs")) {
+            if let Some(&from) = producers.get(input) {
+                edges.push(Edge {
+                    from,
+                    to: vertex,
+                    label: operand_label(input, types, shapes),
+                });
+            }
+        }
+        for (key, operand) in option_operands(node, &order) {
+            if let Some(&from) = producers.get(operand.as_str()) {
+                edges.push(Edge {
+                    from,
+                    to: vertex,
+                    label: format!("{key} = {}", operand_label(&operand, types, shapes)),
+                });
+            }
+        }
+        for output in string_list(node.get("outputs")) {
+            producers.insert(output, vertex);
+        }
+    }
+
+    let outputs = graph
+        .get("outputs")
+        .and_then(Value::as_object)
+        .into_iter()
+        .flatten();
+    for (name, operand) in outputs {
+        let vertex = vertices.len();
+        vertices.push(Vertex {
+            kind: Kind::Output,
+            label: vec![name.clone()],
+        });
+        let Some(operand) = operand.as_str() else {
+            continue;
+        };
+        if let Some(&from) = producers.get(operand) {
+            edges.push(Edge {
+                from,
+                to: vertex,
+                label: operand_label(operand, types, shapes),
+            });
+        }
+    }
+    (vertices, edges)
+}
+
+/// Render the prepared `graph` in `format`, labeling edges with the operand `types` and the
+/// `shapes` from validation.
+pub fn render(
+    graph: &Value,
+    types: &HashMap<String, &str>,
+    shapes: &HashMap<String, Vec<usize>>,
+    format: DumpFormat,
+) -> String {
+    let (vertices, edges) = build(graph, types, shapes);
+    match format {
+        DumpFormat::Dot => to_dot(&vertices, &edges),
+        DumpFormat::Mermaid => to_mermaid(&vertices, &edges),
+    }
+}
+
+fn to_dot(vertices: &[Vertex], edges: &[Edge]) -> String {
+    let mut dot = String::new();
+    let _ = writeln!(dot, "digraph webnn {{");
+    let _ = writeln!(dot, "  rankdir=TB;");
+    for (index, vertex) in vertices.iter().enumerate() {
+        let shape = match vertex.kind {
+            Kind::Input => "ellipse",
+            Kind::Constant => "box, style=rounded",
+            Kind::Operation => "box",
+            Kind::Output => "doubleoctagon",
+        };
+        let label = quote(&vertex.label.join("\n")).replace('\n', "\\n");
+        let _ = writeln!(dot, "  n{index} [label={label}, shape={shape}];");
+    }
+    for edge in edges {
+        let label = quote(&edge.label);
+        let _ = writeln!(dot, "  n{} -> n{} [label={label}];", edge.from, edge.to);
+    }
+    let _ = writeln!(dot, "}}");
+    dot
+}
+
+/// Mermaid labels are HTML: quotes become entities and lines are joined with `<br/>`.
+fn mermaid_text(lines: &[String]) -> String {
+    let lines: Vec<String> = lines
+        .iter()
+        .map(|line| {
+            line.replace('"', "#quot;")
+                .replace('<', "#lt;")
+                .replace('>', "#gt;")
+        })
+        .collect();
+    format!("\"{}\"", lines.join("<br/>"))
+}
+
+fn to_mermaid(vertices: &[Vertex], edges: &[Edge]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "flowchart TB");
+    for (index, vertex) in vertices.iter().enumerate() {
+        let label = mermaid_text(&vertex.label);
+        let _ = match vertex.kind {
+            Kind::Input => writeln!(out, "  n{index}([{label}])"),
+            Kind::Constant => writeln!(out, "  n{index}[({label})]"),
+            Kind::Operation => writeln!(out, "  n{index}[{label}]"),
+            Kind::Output => writeln!(out, "  n{index}[[{label}]]"),
+        };
+    }
+    for edge in edges {
+        let label = mermaid_text(std::slice::from_ref(&edge.label));
+        let _ = writeln!(out, "  n{} -->|{label}| n{}", edge.from, edge.to);
+    }
+    out
+}
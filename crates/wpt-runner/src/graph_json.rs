@@ -0,0 +1,87 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Accessors over raw webnn-graph-json values shared by the runner's graph passes.
+
+use serde_json::Value;
+
+use crate::RunnerError;
+
+/// Operand names of a node's `inputs` or `outputs`, which may be a single name or a list.
+pub fn string_list(value: Option<&Value>) -> Vec<&str> {
+    match value {
+        Some(Value::String(s)) => vec![s.as_str()],
+        Some(Value::Array(items)) => items.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Entries of the `inputs`, `consts` or `outputs` object of `graph`, empty when it is absent.
+pub fn section<'a>(graph: &'a Value, key: &str) -> Vec<(&'a String, &'a Value)> {
+    graph
+        .get(key)
+        .and_then(Value::as_object)
+        .map(|map| map.iter().collect())
+        .unwrap_or_default()
+}
+
+/// Replace the `inlineBytes` data of constant `name` with `rewrite` applied to its raw bytes,
+/// after checking they hold whole `width`-byte elements. `pass` names the rewrite in errors.
+pub fn rewrite_const_bytes(
+    name: &str,
+    decl: &mut Value,
+    pass: &str,
+    width: usize,
+    rewrite: impl FnOnce(&[u8]) -> Result<Vec<u8>, RunnerError>,
+) -> Result<(), RunnerError> {
+    let data_type = decl
+        .get("dataType")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    let init = decl
+        .get_mut("init")
+        .ok_or_else(|| RunnerError::BadRequest(format!("constant '{name}' has no init")))?;
+    if init.get("kind").and_then(Value::as_str) != Some("inlineBytes") {
+        return Err(RunnerError::GraphConversion(format!(
+            "{pass}: constant '{name}' must use inlineBytes init"
+        )));
+    }
+    let raw = init
+        .get("bytes")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+        .iter()
+        .map(|b| {
+            b.as_u64()
+                .and_then(|b| u8::try_from(b).ok())
+                .ok_or_else(|| {
+                    RunnerError::BadRequest(format!("constant '{name}' has invalid byte {b}"))
+                })
+        })
+        .collect::<Result<Vec<u8>, _>>()?;
+    if raw.len() % width != 0 {
+        return Err(RunnerError::BadRequest(format!(
+            "constant '{name}' has {} bytes, not a multiple of {width} for {data_type}",
+            raw.len()
+        )));
+    }
+    init["bytes"] = Value::from(rewrite(&raw)?);
+    Ok(())
+}
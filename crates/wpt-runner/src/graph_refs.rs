@@ -0,0 +1,197 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Strict operand reference resolution over raw webnn-graph-json.
+//!
+//! `from_graph_json` only reports "unknown operand" for dangling references. Generated fixtures
+//! with renamed operands are much easier to fix when the diagnostic says what was declared and
+//! which declared name was probably meant, so we resolve references here first.
+
+use std::collections::BTreeSet;
+
+use serde_json::Value;
+
+use crate::graph_json::string_list;
+use crate::inference_rewrite::{OPERAND_OPTIONS, operand_order};
+
+/// Max declared operand names listed in a diagnostic before eliding the rest.
+const MAX_LISTED_OPERANDS: usize = 20;
+/// Max near-miss suggestions per unknown reference.
+const MAX_SUGGESTIONS: usize = 3;
+/// Unknown names longer than this get no suggestions: edit distance is quadratic in length.
+const MAX_SUGGESTED_NAME_CHARS: usize = 128;
+/// Max declared names scored for suggestions per unknown reference.
+const MAX_SCORED_CANDIDATES: usize = 4096;
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut cur = vec![0usize; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        cur[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != cb);
+            cur[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut cur);
+    }
+    prev[b.len()]
+}
+
+/// Declared names closest to `name`, best match first.
+///
+/// Only candidates whose length is within the distance threshold are scored, at most
+/// [`MAX_SCORED_CANDIDATES`] of them, so a hostile graph cannot make diagnostics expensive.
+fn suggestions<'a>(name: &str, declared: &'a BTreeSet<String>) -> Vec<&'a str> {
+    let len = name.chars().count();
+    if len > MAX_SUGGESTED_NAME_CHARS {
+        return Vec::new();
+    }
+    let threshold = (len / 3).max(1);
+    let mut scored = declared
+        .iter()
+        .filter(|candidate| candidate.chars().count().abs_diff(len) <= threshold)
+        .take(MAX_SCORED_CANDIDATES)
+        .map(|candidate| (levenshtein(name, candidate), candidate.as_str()))
+        .filter(|(dist, _)| *dist <= threshold)
+        .collect::<Vec<_>>();
+    scored.sort();
+    scored
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, n)| n)
+        .collect()
+}
+
+fn format_declared(declared: &BTreeSet<String>) -> String {
+    let listed = declared
+        .iter()
+        .take(MAX_LISTED_OPERANDS)
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .join(", ");
+    if declared.len() > MAX_LISTED_OPERANDS {
+        format!(
+            "[{listed}, ... ({} more)]",
+            declared.len() - MAX_LISTED_OPERANDS
+        )
+    } else {
+        format!("[{listed}]")
+    }
+}
+
+fn unknown_operand_message(context: &str, name: &str, declared: &BTreeSet<String>) -> String {
+    let hint = match suggestions(name, declared).as_slice() {
+        [] => String::new(),
+        [one] => format!("; did you mean '{one}'?"),
+        many => format!(
+            "; did you mean one of {}?",
+            many.iter()
+                .map(|n| format!("'{n}'"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+    format!(
+        "{context} references unknown operand '{name}'{hint} (declared operands: {})",
+        format_declared(declared)
+    )
+}
+
+/// Walk inputs, consts, nodes and outputs in declaration order and make sure every referenced
+/// operand name was declared (as a graph input, constant, or an earlier node output).
+/// Operand-valued options are checked too: an index must be in range and name an operand declared
+/// before the node.
+///
+/// Returns the diagnostic for the first dangling reference.
+pub fn check_operand_references(graph: &Value) -> Result<(), String> {
+    let order = operand_order(graph);
+    let mut declared = BTreeSet::new();
+    for section in ["inputs", "consts"] {
+        if let Some(map) = graph.get(section).and_then(Value::as_object) {
+            declared.extend(map.keys().cloned());
+        }
+    }
+
+    let nodes = graph
+        .get("nodes")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    for (index, node) in nodes.iter().enumerate() {
+        let op = node.get("op").and_then(Value::as_str).unwrap_or("?");
+        let context = match node.get("id").and_then(Value::as_str) {
+            Some(id) => format!("node '{id}' ({op})"),
+            None => format!("node #{index} ({op})"),
+        };
+        for name in string_list(node.get("inputs")) {
+            if !declared.contains(name) {
+                return Err(unknown_operand_message(&context, name, &declared));
+            }
+        }
+        for key in OPERAND_OPTIONS {
+            let name = match node.get("options").and_then(|o| o.get(*key)) {
+                Some(Value::String(name)) => name.as_str(),
+                Some(Value::Number(index)) => {
+                    let resolved = index
+                        .as_u64()
+                        .and_then(|i| usize::try_from(i).ok())
+                        .and_then(|i| order.get(i));
+                    match resolved {
+                        Some(name) => name.as_str(),
+                        None => {
+                            return Err(format!(
+                                "{context} option '{key}' index {index} is out of range \
+                                 ({} operands)",
+                                order.len()
+                            ));
+                        }
+                    }
+                }
+                _ => continue,
+            };
+            if !declared.contains(name) {
+                return Err(unknown_operand_message(
+                    &format!("{context} option '{key}'"),
+                    name,
+                    &declared,
+                ));
+            }
+        }
+        for name in string_list(node.get("outputs")) {
+            declared.insert(name.to_string());
+        }
+    }
+
+    if let Some(outputs) = graph.get("outputs").and_then(Value::as_object) {
+        for (output_name, operand) in outputs {
+            if let Some(name) = operand.as_str() {
+                if !declared.contains(name) {
+                    return Err(unknown_operand_message(
+                        &format!("graph output '{output_name}'"),
+                        name,
+                        &declared,
+                    ));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,158 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Built graphs, in the style of WebNN's `MLGraph`.
+//!
+//! `build_graph` prepares, validates and compiles a graph once and answers with a handle;
+//! `compute` then runs that graph on new inputs without repeating any of it, not even the
+//! compile-cache lookup, which hashes the whole graph. This is the build-once/compute-many
+//! shape WPT's performance-sensitive tests assume. rustnn executors still create their runtime
+//! session per run; the handle keeps everything up to the converted artifact. Handles are
+//! partitioned by harness session, and a session's graphs are dropped when its connection
+//! closes (for the shared `""` session, when the last connection without a session closes).
+//!
+//! A session holds at most `RUSTNNPT_MAX_SESSION_GRAPHS` graphs (default 256) whose prepared
+//! graph JSON takes at most `RUSTNNPT_MAX_SESSION_GRAPH_BYTES` together (default 1 GiB);
+//! building one past either limit fails with `PayloadLimitError`.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use serde_json::{Value, json};
+
+use crate::{
+    BuiltGraph, InputTensor, RunnerError, bf16_lowering, dispatch_check, int64_lowering,
+    payload_limits,
+};
+
+const DEFAULT_MAX_SESSION_GRAPHS: usize = 256;
+const DEFAULT_MAX_SESSION_GRAPH_BYTES: usize = 1 << 30;
+
+pub struct StoredGraph {
+    pub built: BuiltGraph,
+    /// The prepared graph's input declarations, which every compute is checked against.
+    declared: Value,
+    /// Serialized size of the prepared graph, counted against the session's limit.
+    bytes: usize,
+}
+
+impl StoredGraph {
+    /// The runtime input rewrites and dispatch checks `prepare_graph` applies to the inputs of
+    /// `execute_graph`.
+    pub fn prepare_inputs(
+        &self,
+        inputs: &mut BTreeMap<String, InputTensor>,
+    ) -> Result<(), RunnerError> {
+        bf16_lowering::lower_inputs(inputs)?;
+        if self.built.context_options.emulate_int64 {
+            int64_lowering::lower_inputs(inputs)?;
+        }
+        dispatch_check::check_inputs(&self.declared, inputs)
+    }
+}
+
+/// Per-session graphs; requests without a session share the `""` partition.
+fn store() -> &'static Mutex<HashMap<String, HashMap<String, Arc<StoredGraph>>>> {
+    static GRAPHS: OnceLock<Mutex<HashMap<String, HashMap<String, Arc<StoredGraph>>>>> =
+        OnceLock::new();
+    GRAPHS.get_or_init(Mutex::default)
+}
+
+fn unknown(handle: &str) -> RunnerError {
+    RunnerError::BadRequest(format!("unknown graph handle '{handle}'"))
+}
+
+/// Fail unless `graphs` has room for one more graph of `bytes` bytes.
+fn check_capacity(
+    graphs: &HashMap<String, Arc<StoredGraph>>,
+    bytes: usize,
+) -> Result<(), RunnerError> {
+    let max_graphs =
+        payload_limits::limit("RUSTNNPT_MAX_SESSION_GRAPHS", DEFAULT_MAX_SESSION_GRAPHS);
+    if graphs.len() >= max_graphs {
+        return Err(RunnerError::PayloadLimit(format!(
+            "session already holds {max_graphs} built graphs"
+        )));
+    }
+    let max_bytes = payload_limits::limit(
+        "RUSTNNPT_MAX_SESSION_GRAPH_BYTES",
+        DEFAULT_MAX_SESSION_GRAPH_BYTES,
+    );
+    let held = graphs.values().map(|g| g.bytes).sum::<usize>();
+    if held.saturating_add(bytes) > max_bytes {
+        return Err(RunnerError::PayloadLimit(format!(
+            "session graphs would take {} bytes, over the limit of {max_bytes}",
+            held.saturating_add(bytes)
+        )));
+    }
+    Ok(())
+}
+
+/// Keep `built` for later computes of `session` and return its handle.
+pub fn insert(session: &str, built: BuiltGraph) -> Result<String, RunnerError> {
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+    let serialized = serde_json::to_string(&built.prepared.graph)
+        .map_err(|e| RunnerError::BadRequest(format!("cannot serialize prepared graph: {e}")))?;
+    let bytes = serialized.len();
+    let prepared = serde_json::from_str::<Value>(&serialized)
+        .map_err(|e| RunnerError::BadRequest(format!("cannot serialize prepared graph: {e}")))?;
+    let declared = json!({ "inputs": prepared.get("inputs").cloned().unwrap_or_default() });
+    let mut sessions = store().lock().unwrap_or_else(|e| e.into_inner());
+    let graphs = sessions.entry(session.to_string()).or_default();
+    check_capacity(graphs, bytes)?;
+    let handle = format!("graph{}", NEXT.fetch_add(1, Ordering::Relaxed));
+    let stored = StoredGraph {
+        built,
+        declared,
+        bytes,
+    };
+    graphs.insert(handle.clone(), Arc::new(stored));
+    Ok(handle)
+}
+
+pub fn get(session: &str, handle: &str) -> Result<Arc<StoredGraph>, RunnerError> {
+    let sessions = store().lock().unwrap_or_else(|e| e.into_inner());
+    sessions
+        .get(session)
+        .and_then(|graphs| graphs.get(handle))
+        .cloned()
+        .ok_or_else(|| unknown(handle))
+}
+
+/// Forget `handle`; computes already running on it finish normally.
+pub fn destroy(session: &str, handle: &str) -> Result<(), RunnerError> {
+    let mut sessions = store().lock().unwrap_or_else(|e| e.into_inner());
+    sessions
+        .get_mut(session)
+        .and_then(|graphs| graphs.remove(handle))
+        .map(drop)
+        .ok_or_else(|| unknown(handle))
+}
+
+/// Drop every graph of `session`; called when its connection closes.
+pub fn end_session(session: &str) {
+    let mut sessions = store().lock().unwrap_or_else(|e| e.into_inner());
+    sessions.remove(session);
+}
+
+/// Graphs held across all sessions, for soak leak checks.
+pub fn graph_count() -> usize {
+    let sessions = store().lock().unwrap_or_else(|e| e.into_inner());
+    sessions.values().map(HashMap::len).sum()
+}
@@ -0,0 +1,400 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Inference-mode graph rewrites, enabled by the `inferenceRewrites` context option.
+//!
+//! - A `batchNormalization` over the channel axis of a `conv2d` output is folded into the
+//!   convolution: the filter is scaled per output channel and the normalization's shift becomes
+//!   the convolution bias.
+//! - `identity` nodes, which exporters leave behind for inference-mode dropout, are removed and
+//!   their consumers read the identity's input.
+//!
+//! Both rewrite results within float rounding of the original, so running a suite with and
+//! without them checks that the tolerances hold. Operand-valued options refer to operands by
+//! index (see [`operand_order`]), so folding never adds or removes declarations: the filter and
+//! the normalization mean (which becomes the bias) are rewritten in place, and constants left
+//! unused stay declared. Removing a node shifts the indices of later node outputs, so index-valued
+//! options are renumbered after every rewrite.
+
+use std::collections::{HashMap, HashSet};
+
+use serde_json::Value;
+
+use crate::graph_json::string_list;
+use crate::weight_prepack::{const_bytes, consumer_counts};
+
+/// Option keys whose value is an operand (by [`operand_order`] index, or by name).
+pub const OPERAND_OPTIONS: &[&str] = &[
+    "bias",
+    "scale",
+    "c",
+    "recurrentBias",
+    "initialHiddenState",
+    "initialCellState",
+    "peepholeWeight",
+];
+
+const DEFAULT_EPSILON: f64 = 1e-5;
+
+/// [`string_list`], owned so the graph can be rewritten while the names are held.
+fn owned_list(value: Option<&Value>) -> Vec<String> {
+    string_list(value).into_iter().map(str::to_string).collect()
+}
+
+fn nodes(graph: &Value) -> &[Value] {
+    graph
+        .get("nodes")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+fn option<'a>(node: &'a Value, key: &str) -> Option<&'a Value> {
+    node.get("options")?.get(key)
+}
+
+/// Inputs, then constants, each sorted by name, then node outputs in declaration order: the
+/// order index-valued options count in, as `build-graph-json.js` numbers them.
+pub fn operand_order(graph: &Value) -> Vec<String> {
+    let mut order = Vec::new();
+    for section in ["inputs", "consts"] {
+        if let Some(map) = graph.get(section).and_then(Value::as_object) {
+            let mut names = map.keys().cloned().collect::<Vec<_>>();
+            names.sort();
+            order.extend(names);
+        }
+    }
+    let mut seen = order.iter().cloned().collect::<HashSet<_>>();
+    for node in nodes(graph) {
+        for output in string_list(node.get("outputs")) {
+            if seen.insert(output.to_string()) {
+                order.push(output.to_string());
+            }
+        }
+    }
+    order
+}
+
+/// Point index-valued operand options back at their operands after nodes were removed.
+///
+/// `old_order` is the [`operand_order`] before the change, and `renamed` maps the outputs of
+/// removed nodes to the operand their consumers now read. Options whose operand is gone are left
+/// as they were for validation to report.
+pub fn renumber_options(
+    graph: &mut Value,
+    old_order: &[String],
+    renamed: &HashMap<String, String>,
+) {
+    let order = operand_order(graph);
+    let position = order
+        .iter()
+        .enumerate()
+        .map(|(index, name)| (name.as_str(), index))
+        .collect::<HashMap<_, _>>();
+    let Some(nodes) = graph.get_mut("nodes").and_then(Value::as_array_mut) else {
+        return;
+    };
+    for node in nodes {
+        let Some(options) = node.get_mut("options").and_then(Value::as_object_mut) else {
+            continue;
+        };
+        for key in OPERAND_OPTIONS {
+            let Some(operand) = options.get_mut(*key) else {
+                continue;
+            };
+            let Some(name) = operand
+                .as_u64()
+                .and_then(|index| old_order.get(usize::try_from(index).ok()?))
+            else {
+                continue;
+            };
+            let name = renamed.get(name).unwrap_or(name);
+            if let Some(&index) = position.get(name.as_str()) {
+                *operand = Value::from(index);
+            }
+        }
+    }
+}
+
+fn option_operand(node: &Value, key: &str, order: &[String]) -> Option<String> {
+    match option(node, key)? {
+        Value::String(name) => Some(name.clone()),
+        Value::Number(index) => order.get(usize::try_from(index.as_u64()?).ok()?).cloned(),
+        _ => None,
+    }
+}
+
+/// Operands `node` references through operand-valued options, with the option key.
+pub fn option_operands(node: &Value, order: &[String]) -> Vec<(&'static str, String)> {
+    OPERAND_OPTIONS
+        .iter()
+        .filter_map(|&key| Some((key, option_operand(node, key, order)?)))
+        .collect()
+}
+
+/// References to each operand from node inputs, operand-valued options and graph outputs.
+pub fn references(graph: &Value, order: &[String]) -> HashMap<String, usize> {
+    let mut counts = consumer_counts(graph);
+    for node in nodes(graph) {
+        for (_, name) in option_operands(node, order) {
+            *counts.entry(name).or_default() += 1;
+        }
+    }
+    counts
+}
+
+fn f32_const(graph: &Value, name: &str) -> Option<Vec<f32>> {
+    let decl = graph.get("consts")?.get(name)?;
+    if decl.get("dataType")?.as_str()? != "float32" {
+        return None;
+    }
+    let bytes = const_bytes(decl)?;
+    (bytes.len() % 4 == 0).then(|| {
+        bytes
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect()
+    })
+}
+
+fn set_f32_const(graph: &mut Value, name: &str, values: &[f32]) {
+    let bytes = values
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect::<Vec<u8>>();
+    graph["consts"][name]["init"]["bytes"] = Value::from(bytes);
+}
+
+fn const_shape(graph: &Value, name: &str) -> Option<Vec<usize>> {
+    graph
+        .get("consts")?
+        .get(name)?
+        .get("shape")?
+        .as_array()?
+        .iter()
+        .map(|d| d.as_u64().map(|d| d as usize))
+        .collect()
+}
+
+/// `values` of a per-channel operand option, `default` when the option is absent.
+fn channel_values(
+    graph: &Value,
+    node: &Value,
+    key: &str,
+    order: &[String],
+    channels: usize,
+    default: f32,
+) -> Option<Vec<f32>> {
+    if option(node, key).is_none() {
+        return Some(vec![default; channels]);
+    }
+    let values = f32_const(graph, &option_operand(node, key, order)?)?;
+    (values.len() == channels).then_some(values)
+}
+
+/// Fold the `batchNormalization` at `bn_index` into the `conv2d` producing its input.
+fn fold_batch_norm(graph: &mut Value, bn_index: usize) -> Option<String> {
+    let order = operand_order(graph);
+    let refs = references(graph, &order);
+    let count = |name: &str| refs.get(name).copied().unwrap_or(0);
+    let all = nodes(graph);
+    let bn = &all[bn_index];
+    if bn.get("op").and_then(Value::as_str) != Some("batchNormalization") {
+        return None;
+    }
+    let [x, mean, variance] = owned_list(bn.get("inputs")).try_into().ok()?;
+    let [bn_output] = owned_list(bn.get("outputs")).try_into().ok()?;
+    let conv_index = all[..bn_index]
+        .iter()
+        .position(|node| string_list(node.get("outputs")) == [x.as_str()])?;
+    let conv = &all[conv_index];
+    if conv.get("op").and_then(Value::as_str) != Some("conv2d") || count(&x) != 1 {
+        return None;
+    }
+
+    let input_layout = option(conv, "inputLayout")
+        .and_then(Value::as_str)
+        .unwrap_or("nchw")
+        .to_ascii_lowercase();
+    let channel_axis = match input_layout.as_str() {
+        "nchw" => 1,
+        "nhwc" => 3,
+        _ => return None,
+    };
+    let bn_axis = option(bn, "axis").map_or(Some(1), Value::as_u64)?;
+    if bn_axis != channel_axis {
+        return None;
+    }
+
+    let filter = conv.get("inputs")?.get(1)?.as_str()?.to_string();
+    let filter_layout = option(conv, "filterLayout")
+        .and_then(Value::as_str)
+        .unwrap_or("oihw")
+        .to_ascii_lowercase();
+    let shape = const_shape(graph, &filter)?;
+    let out_axis = filter_layout.find('o').filter(|_| shape.len() == 4)?;
+    let channels = shape[out_axis];
+    let mut weights = f32_const(graph, &filter)?;
+    if count(&filter) != 1 || count(&mean) != 1 || weights.len() != shape.iter().product() {
+        return None;
+    }
+    let means = f32_const(graph, &mean).filter(|v| v.len() == channels)?;
+    let variances = f32_const(graph, &variance).filter(|v| v.len() == channels)?;
+    let scales = channel_values(graph, bn, "scale", &order, channels, 1.0)?;
+    let shifts = channel_values(graph, bn, "bias", &order, channels, 0.0)?;
+    let conv_bias = channel_values(graph, conv, "bias", &order, channels, 0.0)?;
+    let epsilon = option(bn, "epsilon")
+        .and_then(Value::as_f64)
+        .unwrap_or(DEFAULT_EPSILON);
+
+    let factors = (0..channels)
+        .map(|c| f64::from(scales[c]) / (f64::from(variances[c]) + epsilon).sqrt())
+        .collect::<Vec<_>>();
+    let stride = shape[out_axis + 1..].iter().product::<usize>();
+    for (flat, weight) in weights.iter_mut().enumerate() {
+        *weight = (f64::from(*weight) * factors[(flat / stride) % channels]) as f32;
+    }
+    let bias = (0..channels)
+        .map(|c| {
+            let centered = f64::from(conv_bias[c]) - f64::from(means[c]);
+            (centered * factors[c] + f64::from(shifts[c])) as f32
+        })
+        .collect::<Vec<_>>();
+    // Keep the graph's spelling of operand options: a name if it already uses names.
+    let by_name = [(bn, "scale"), (bn, "bias"), (conv, "bias")]
+        .iter()
+        .any(|(node, key)| option(node, key).is_some_and(Value::is_string));
+    let bias_ref = if by_name {
+        Value::from(mean.as_str())
+    } else {
+        Value::from(order.iter().position(|name| *name == mean)?)
+    };
+    let conv_id = conv
+        .get("id")
+        .and_then(Value::as_str)
+        .map_or_else(|| x.clone(), str::to_string);
+
+    set_f32_const(graph, &filter, &weights);
+    set_f32_const(graph, &mean, &bias);
+    let conv = &mut graph["nodes"][conv_index];
+    conv["options"]["bias"] = bias_ref;
+    conv["outputs"] = Value::from(vec![bn_output]);
+    if let Some(nodes) = graph.get_mut("nodes").and_then(Value::as_array_mut) {
+        nodes.remove(bn_index);
+    }
+    renumber_options(graph, &order, &HashMap::new());
+    Some(format!("batchNormalization into {conv_id}"))
+}
+
+fn rename(value: &mut Value, from: &str, to: &str) {
+    match value {
+        Value::String(name) if name == from => *name = to.to_string(),
+        Value::Array(items) => items.iter_mut().for_each(|item| rename(item, from, to)),
+        _ => {}
+    }
+}
+
+/// Remove the `identity` node at `index`, pointing its consumers at its input, including those
+/// that read it through an operand-valued option.
+fn remove_identity(graph: &mut Value, index: usize) -> Option<String> {
+    let node = &nodes(graph)[index];
+    if node.get("op").and_then(Value::as_str) != Some("identity") {
+        return None;
+    }
+    let [input] = owned_list(node.get("inputs")).try_into().ok()?;
+    let [output] = owned_list(node.get("outputs")).try_into().ok()?;
+    let id = node
+        .get("id")
+        .and_then(Value::as_str)
+        .map_or_else(|| output.clone(), str::to_string);
+    let order = operand_order(graph);
+    let nodes = graph.get_mut("nodes").and_then(Value::as_array_mut)?;
+    nodes.remove(index);
+    for node in nodes.iter_mut().skip(index) {
+        if let Some(inputs) = node.get_mut("inputs") {
+            rename(inputs, &output, &input);
+        }
+        if let Some(options) = node.get_mut("options").and_then(Value::as_object_mut) {
+            for key in OPERAND_OPTIONS {
+                if let Some(operand) = options.get_mut(*key) {
+                    rename(operand, &output, &input);
+                }
+            }
+        }
+    }
+    if let Some(outputs) = graph.get_mut("outputs").and_then(Value::as_object_mut) {
+        outputs
+            .values_mut()
+            .for_each(|operand| rename(operand, &output, &input));
+    }
+    renumber_options(graph, &order, &HashMap::from([(output, input)]));
+    Some(format!("identity {id}"))
+}
+
+/// Apply the inference rewrites, returning a description of each one.
+pub fn rewrite(graph: &mut Value) -> Vec<String> {
+    let mut applied = Vec::new();
+    let mut index = 0;
+    while index < nodes(graph).len() {
+        match fold_batch_norm(graph, index).or_else(|| remove_identity(graph, index)) {
+            // The node at `index` was removed; look at its successor next.
+            Some(rewrite) => applied.push(rewrite),
+            None => index += 1,
+        }
+    }
+    applied
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn conv_with_bias_through_identity(bias: Value) -> Value {
+        // Operand order: x, b, w, b_id, y.
+        json!({
+            "inputs": {"x": {"dataType": "float32", "shape": [1, 1, 3, 3]}},
+            "consts": {
+                "b": {"dataType": "float32", "shape": [1]},
+                "w": {"dataType": "float32", "shape": [1, 1, 1, 1]},
+            },
+            "nodes": [
+                {"id": "dropout", "op": "identity", "inputs": ["b"], "outputs": ["b_id"]},
+                {
+                    "id": "conv",
+                    "op": "conv2d",
+                    "inputs": ["x", "w"],
+                    "options": {"bias": bias},
+                    "outputs": ["y"],
+                },
+            ],
+            "outputs": {"y": "y"},
+        })
+    }
+
+    #[test]
+    fn identity_read_as_conv_bias_is_removed() {
+        for (bias, expected) in [(json!("b_id"), json!("b")), (json!(3), json!(1))] {
+            let mut graph = conv_with_bias_through_identity(bias);
+            assert_eq!(rewrite(&mut graph), ["identity dropout"]);
+            assert_eq!(graph["nodes"].as_array().map(Vec::len), Some(1));
+            assert_eq!(graph["nodes"][0]["options"]["bias"], expected);
+        }
+    }
+}
@@ -0,0 +1,84 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Packed 4-bit integer tensors.
+//!
+//! WebNN lays out `int4`/`uint4` data two elements per byte, low nibble first. rustnn executors
+//! take 4-bit inputs widened (`Int32`/`Uint8`), so packing only happens at the byte boundaries
+//! of the protocol: base64 transport in both directions. Values are range checked instead of
+//! wrapping silently.
+
+pub fn is_4bit(data_type: &str) -> bool {
+    matches!(data_type, "int4" | "uint4")
+}
+
+pub fn range(data_type: &str) -> (i64, i64) {
+    if data_type == "int4" {
+        (-8, 7)
+    } else {
+        (0, 15)
+    }
+}
+
+/// Fail if `value` does not fit in `data_type`.
+pub fn check_range(data_type: &str, value: i64) -> Result<(), String> {
+    let (min, max) = range(data_type);
+    if (min..=max).contains(&value) {
+        Ok(())
+    } else {
+        Err(format!(
+            "{data_type} value {value} is out of range [{min}, {max}]"
+        ))
+    }
+}
+
+/// Pack nibbles two per byte, low nibble first; an odd count leaves the last high nibble zero.
+pub fn pack(values: &[i64]) -> Vec<u8> {
+    values
+        .chunks(2)
+        .map(|pair| {
+            let lo = (pair[0] & 0x0f) as u8;
+            let hi = pair.get(1).map_or(0, |v| (v & 0x0f) as u8);
+            lo | (hi << 4)
+        })
+        .collect()
+}
+
+/// Unpack `count` elements; `int4` nibbles are sign extended.
+pub fn unpack(data_type: &str, bytes: &[u8], count: usize) -> Result<Vec<i64>, String> {
+    let packed_len = count.div_ceil(2);
+    if bytes.len() != packed_len {
+        return Err(format!(
+            "{data_type} data of {count} elements needs {packed_len} packed bytes, got {}",
+            bytes.len()
+        ));
+    }
+    let signed = data_type == "int4";
+    Ok(bytes
+        .iter()
+        .flat_map(|b| [b & 0x0f, b >> 4])
+        .take(count)
+        .map(|nibble| {
+            if signed && nibble & 0x08 != 0 {
+                i64::from(nibble) - 16
+            } else {
+                i64::from(nibble)
+            }
+        })
+        .collect())
+}
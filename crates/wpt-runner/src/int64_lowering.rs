@@ -0,0 +1,178 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Optional int64 -> int32 (and uint64 -> uint32) lowering for backends without 64-bit integers.
+//!
+//! The rewrite happens on the webnn-graph-json before conversion: graph inputs, inline constants
+//! and dtype-valued node options are narrowed, and every value is range checked so a lowering
+//! that would change results fails loudly instead of wrapping. Outputs are widened back when
+//! they are cast to the expected int64 descriptor.
+
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+use crate::{InputTensor, RunnerError, WarningPayload, graph_json, parse_i64, parse_u64};
+
+/// Node option keys whose value is an `MLOperandDataType`.
+const DTYPE_OPTION_KEYS: &[&str] = &["to", "outputDataType", "dataType"];
+
+fn narrowed(data_type: &str) -> Option<&'static str> {
+    match data_type {
+        "int64" => Some("int32"),
+        "uint64" => Some("uint32"),
+        _ => None,
+    }
+}
+
+fn out_of_range(what: &str, value: impl std::fmt::Display, target: &str) -> RunnerError {
+    RunnerError::GraphConversion(format!(
+        "int64 emulation: {what} value {value} does not fit in {target}"
+    ))
+}
+
+fn narrow_const_bytes(name: &str, data_type: &str, raw: &[u8]) -> Result<Vec<u8>, RunnerError> {
+    let what = format!("constant '{name}'");
+    let mut out = Vec::with_capacity(raw.len() / 2);
+    for chunk in raw.chunks_exact(8) {
+        let mut arr = [0u8; 8];
+        arr.copy_from_slice(chunk);
+        let narrowed = if data_type == "int64" {
+            let v = i64::from_le_bytes(arr);
+            i32::try_from(v)
+                .map_err(|_| out_of_range(&what, v, "int32"))?
+                .to_le_bytes()
+        } else {
+            let v = u64::from_le_bytes(arr);
+            u32::try_from(v)
+                .map_err(|_| out_of_range(&what, v, "uint32"))?
+                .to_le_bytes()
+        };
+        out.extend(narrowed);
+    }
+    Ok(out)
+}
+
+/// Narrow 64-bit integer inputs, constants and dtype options in `graph`.
+pub fn lower_graph(
+    graph: &mut Value,
+    warnings: &mut Vec<WarningPayload>,
+) -> Result<(), RunnerError> {
+    let mut rewritten = Vec::new();
+
+    if let Some(inputs) = graph.get_mut("inputs").and_then(Value::as_object_mut) {
+        for (name, desc) in inputs.iter_mut() {
+            let Some(target) = desc
+                .get("dataType")
+                .and_then(Value::as_str)
+                .and_then(narrowed)
+            else {
+                continue;
+            };
+            desc["dataType"] = Value::from(target);
+            rewritten.push(format!("input '{name}'"));
+        }
+    }
+
+    if let Some(consts) = graph.get_mut("consts").and_then(Value::as_object_mut) {
+        for (name, decl) in consts.iter_mut() {
+            let Some(data_type) = decl
+                .get("dataType")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+            else {
+                continue;
+            };
+            let Some(target) = narrowed(&data_type) else {
+                continue;
+            };
+            graph_json::rewrite_const_bytes(name, decl, "int64 emulation", 8, |raw| {
+                narrow_const_bytes(name, &data_type, raw)
+            })?;
+            decl["dataType"] = Value::from(target);
+            rewritten.push(format!("constant '{name}'"));
+        }
+    }
+
+    if let Some(nodes) = graph.get_mut("nodes").and_then(Value::as_array_mut) {
+        for (index, node) in nodes.iter_mut().enumerate() {
+            let id = node
+                .get("id")
+                .and_then(Value::as_str)
+                .map_or_else(|| format!("#{index}"), str::to_string);
+            let Some(options) = node.get_mut("options").and_then(Value::as_object_mut) else {
+                continue;
+            };
+            for key in DTYPE_OPTION_KEYS {
+                let Some(target) = options.get(*key).and_then(Value::as_str).and_then(narrowed)
+                else {
+                    continue;
+                };
+                options.insert((*key).to_string(), Value::from(target));
+                rewritten.push(format!("node '{id}' option {key}"));
+            }
+        }
+    }
+
+    if !rewritten.is_empty() {
+        warnings.push(WarningPayload {
+            kind: "Int64Emulation".to_string(),
+            message: format!(
+                "rewrote 64-bit integer operands to 32-bit: {}",
+                rewritten.join(", ")
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// Narrow 64-bit integer runtime inputs to match the lowered graph, range checking each value.
+pub fn lower_inputs(inputs: &mut BTreeMap<String, InputTensor>) -> Result<(), RunnerError> {
+    for (name, input) in inputs.iter_mut() {
+        let Some(target) = narrowed(&input.descriptor.data_type) else {
+            continue;
+        };
+        let what = format!("input '{name}'");
+        let data = if target == "int32" {
+            input
+                .data
+                .iter()
+                .map(|v| {
+                    let n = parse_i64(v)?;
+                    i32::try_from(n)
+                        .map(Value::from)
+                        .map_err(|_| out_of_range(&what, n, target))
+                })
+                .collect::<Result<Vec<_>, _>>()?
+        } else {
+            input
+                .data
+                .iter()
+                .map(|v| {
+                    let n = parse_u64(v)?;
+                    u32::try_from(n)
+                        .map(Value::from)
+                        .map_err(|_| out_of_range(&what, n, target))
+                })
+                .collect::<Result<Vec<_>, _>>()?
+        };
+        input.data = data;
+        input.descriptor.data_type = target.to_string();
+    }
+    Ok(())
+}
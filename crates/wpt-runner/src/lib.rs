@@ -0,0 +1,3525 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::{self, BufRead, Write};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, mpsc};
+use std::time::{Duration, Instant};
+
+mod anonymize;
+mod benchmark;
+mod bf16_lowering;
+mod boolean;
+mod canonical;
+mod compile_cache;
+mod compiled;
+mod constant_fold;
+mod constants;
+mod deviation;
+mod dispatch_check;
+mod distribution;
+mod dynamic_dims;
+mod element_count;
+mod encoding;
+mod external;
+mod failure_dump;
+mod freeze;
+mod graph_diff;
+mod graph_dump;
+mod graph_json;
+mod graph_refs;
+mod graphs;
+mod inference_rewrite;
+mod int4;
+mod int64_lowering;
+mod nondeterminism;
+mod npz;
+mod one_shot;
+mod op_limits;
+mod operands;
+mod output_alias;
+mod output_dtype;
+mod payload_limits;
+mod plan;
+mod postprocess;
+mod progress;
+mod prune;
+mod quantize;
+mod reduction;
+mod remote;
+mod repro_js;
+mod soak;
+mod spill;
+mod stats;
+mod telemetry;
+mod tensors;
+mod tolerance;
+mod weight_prepack;
+
+use half::f16;
+#[cfg(all(target_os = "macos", feature = "backend-coreml"))]
+use rustnn::executors::coreml::{CoremlInput, CoremlOutput, run_coreml_with_inputs_with_weights};
+
+#[cfg(any(feature = "backend-trtx", feature = "backend-trtx-mock"))]
+use rustnn::converters::TrtxConverter;
+#[cfg(any(feature = "backend-trtx", feature = "backend-trtx-mock"))]
+use rustnn::executors::trtx::{TrtxInput, TrtxOutputWithData, run_trtx_with_inputs};
+
+use compiled::{CompiledGraph, EngineBinding, OperandDescriptor};
+use distribution::DistributionSpec;
+use element_count::ElementCount;
+use encoding::DataEncoding;
+#[cfg(feature = "backend-onnx")]
+use rustnn::executors::onnx::{
+    OnnxInput, OnnxOutputWithData, TensorData as OnnxTensorData, run_onnx_with_inputs,
+};
+use rustnn::{ContextProperties, ConverterRegistry, GraphInfo, GraphValidator};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use stats::ExecutionStats;
+use thiserror::Error;
+use tolerance::{Tolerance, Verdict};
+use webnn_graph::ast::GraphJson;
+
+pub use telemetry::{JsonlSink, NoopSink, TelemetryEvent, TelemetrySink};
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+enum Request {
+    ExecuteGraph {
+        id: String,
+        graph: Value,
+        #[serde(default)]
+        inputs: BTreeMap<String, InputTensor>,
+        /// Inputs read from tensors created with `create_tensor`, as input name to handle.
+        #[serde(default, rename = "inputTensors")]
+        input_tensors: BTreeMap<String, String>,
+        /// Outputs stored into tensors instead of being returned, as output name to handle.
+        #[serde(default, rename = "outputTensors")]
+        output_tensors: BTreeMap<String, String>,
+        /// Runtime inputs to bind as constants before validation.
+        #[serde(default)]
+        freeze_inputs: Vec<String>,
+        #[serde(default)]
+        expected_outputs: BTreeMap<String, ExpectedOutput>,
+        #[serde(default)]
+        context_options: ContextOptions,
+        /// Converter/executor for this request; overrides `context_options.backend`.
+        #[serde(default)]
+        target: Option<String>,
+        /// Wall-clock budget for the whole request; exceeding it answers `TimeoutError`.
+        #[serde(default, rename = "timeoutMs")]
+        timeout_ms: Option<u64>,
+    },
+    /// Prepare, validate and compile a graph once; `compute` then runs it by the returned handle.
+    BuildGraph {
+        id: String,
+        graph: Value,
+        #[serde(default)]
+        context_options: ContextOptions,
+        /// Converter/executor for this graph; overrides `context_options.backend`.
+        #[serde(default)]
+        target: Option<String>,
+    },
+    /// Execute a graph built with `build_graph` on new inputs.
+    Compute {
+        id: String,
+        /// Handle returned by `build_graph`.
+        graph: String,
+        #[serde(default)]
+        inputs: BTreeMap<String, InputTensor>,
+        #[serde(default, rename = "inputTensors")]
+        input_tensors: BTreeMap<String, String>,
+        #[serde(default, rename = "outputTensors")]
+        output_tensors: BTreeMap<String, String>,
+        #[serde(default)]
+        expected_outputs: BTreeMap<String, ExpectedOutput>,
+        #[serde(default, rename = "timeoutMs")]
+        timeout_ms: Option<u64>,
+    },
+    /// Run a graph built with `build_graph` from tensors into tensors, like
+    /// `MLContext.dispatch()`: outputs are written into their preallocated tensors and nothing
+    /// but the status is returned.
+    Dispatch {
+        id: String,
+        /// Handle returned by `build_graph`.
+        graph: String,
+        /// Graph input name to tensor handle.
+        inputs: BTreeMap<String, String>,
+        /// Graph output name to tensor handle.
+        outputs: BTreeMap<String, String>,
+        #[serde(default, rename = "timeoutMs")]
+        timeout_ms: Option<u64>,
+    },
+    /// Free a graph built with `build_graph`.
+    DestroyGraph { id: String, graph: String },
+    /// Execute a single operation; the one-node graph is synthesized by the runner.
+    RunOp(RunOpRequest),
+    /// Run several requests in one round trip; each item gets its own response. Items are kept
+    /// as JSON and parsed one by one, so a malformed item only fails its own response.
+    ExecuteBatch { id: String, requests: Vec<Value> },
+    /// Execute an already compiled graph; sent by the remote executor.
+    RunCompiled {
+        id: String,
+        compiled: Box<CompiledGraph>,
+        inputs: BTreeMap<String, InputTensor>,
+    },
+    /// Validate a graph without inputs and report its output descriptors; nothing is converted
+    /// or executed.
+    ValidateGraph {
+        id: String,
+        graph: Value,
+        #[serde(default)]
+        context_options: ContextOptions,
+    },
+    /// Validate and convert a graph, returning the converter artifact instead of executing it.
+    ConvertGraph {
+        id: String,
+        graph: Value,
+        #[serde(default)]
+        context_options: ContextOptions,
+        /// Converter to run; overrides `context_options.backend`.
+        #[serde(default)]
+        target: Option<String>,
+    },
+    /// Render the graph the selected backend would run as Graphviz DOT, without executing it.
+    PlanGraph {
+        id: String,
+        graph: Value,
+        #[serde(default)]
+        context_options: ContextOptions,
+        /// Backend to plan for; overrides `context_options.backend`.
+        #[serde(default)]
+        target: Option<String>,
+    },
+    /// Render the prepared graph as DOT or Mermaid, with operand types and shapes on its edges.
+    DumpGraph {
+        id: String,
+        graph: Value,
+        #[serde(default)]
+        context_options: ContextOptions,
+        #[serde(default)]
+        format: graph_dump::DumpFormat,
+    },
+    /// Structural diff of `graph` against `other`, or against `graph` as prepared for
+    /// `context_options` when `other` is omitted.
+    DiffGraphs {
+        id: String,
+        graph: Value,
+        #[serde(default)]
+        other: Option<Value>,
+        #[serde(default)]
+        context_options: ContextOptions,
+        /// Absolute tolerance for float constant data.
+        #[serde(default)]
+        tolerance: f64,
+    },
+    /// Calibrate a float graph on `calibration` input sets and return its int8 QDQ form.
+    QuantizeGraph {
+        id: String,
+        graph: Value,
+        calibration: Vec<BTreeMap<String, InputTensor>>,
+        #[serde(default)]
+        context_options: ContextOptions,
+    },
+    /// Run a graph on `samples` generated input sets on two configurations and report the ULP
+    /// distances between their outputs.
+    DeviationStats {
+        id: String,
+        graph: Value,
+        inputs: BTreeMap<String, InputTensor>,
+        #[serde(default = "deviation::default_samples")]
+        samples: usize,
+        #[serde(default)]
+        reference: deviation::Side,
+        candidate: deviation::Side,
+    },
+    /// Build a graph once and report per-stage latency statistics over `iterations` timed runs.
+    Benchmark {
+        id: String,
+        graph: Value,
+        inputs: BTreeMap<String, InputTensor>,
+        #[serde(default)]
+        context_options: ContextOptions,
+        /// Converter/executor to benchmark; overrides `context_options.backend`.
+        #[serde(default)]
+        target: Option<String>,
+        #[serde(default = "benchmark::default_warmups")]
+        warmups: usize,
+        #[serde(default = "benchmark::default_iterations")]
+        iterations: usize,
+    },
+    /// Describe the converters and executors compiled into this runner.
+    ListBackends { id: String },
+    /// Report `opSupportLimits` and the available backends, so harnesses can skip what this
+    /// runner rejects.
+    GetCapabilities { id: String },
+    /// Override entries of the reported `opSupportLimits` for the rest of the session; `null`
+    /// removes an operator, which later validation then rejects.
+    SetCapabilities {
+        id: String,
+        #[serde(default)]
+        overrides: serde_json::Map<String, Value>,
+    },
+    /// Bind the connection to `session_id`; later requests only see that session's caches.
+    Handshake {
+        id: String,
+        #[serde(default)]
+        session_id: Option<String>,
+        /// Write compile progress frames for later requests at this interval.
+        #[serde(default)]
+        progress_interval_ms: Option<u64>,
+    },
+    /// Allocate a zero-filled tensor under `handle` for later requests of this session.
+    CreateTensor {
+        id: String,
+        handle: String,
+        descriptor: TensorDescriptor,
+    },
+    /// Replace the contents of a tensor created with `create_tensor`.
+    WriteTensor {
+        id: String,
+        handle: String,
+        data: Value,
+        #[serde(rename = "dataEncoding", default)]
+        data_encoding: DataEncoding,
+    },
+    /// Return the contents of a tensor in `outputs`, keyed by its handle.
+    ReadTensor {
+        id: String,
+        handle: String,
+        #[serde(rename = "dataEncoding", default)]
+        data_encoding: DataEncoding,
+        /// Also return the tensor's storage bytes as base64 `rawBytes`.
+        #[serde(rename = "rawBytes", default)]
+        raw_bytes: bool,
+    },
+    /// Free a tensor created with `create_tensor`.
+    DestroyTensor { id: String, handle: String },
+}
+
+#[derive(Debug, Deserialize)]
+struct RunOpRequest {
+    id: String,
+    op: String,
+    inputs: BTreeMap<String, InputTensor>,
+    /// Positional order of `inputs` for the node; defaults to sorted input names.
+    #[serde(default)]
+    input_order: Option<Vec<String>>,
+    #[serde(default)]
+    options: serde_json::Map<String, Value>,
+    /// Node output operand names; defaults to a single `output`.
+    #[serde(default)]
+    outputs: Option<Vec<String>>,
+    #[serde(default)]
+    expected_outputs: BTreeMap<String, ExpectedOutput>,
+    #[serde(default)]
+    context_options: ContextOptions,
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+struct ContextOptions {
+    #[serde(default)]
+    backend: Option<String>,
+    #[serde(rename = "deviceType", default)]
+    device_type: Option<String>,
+    #[serde(rename = "powerPreference", default)]
+    power_preference: Option<String>,
+    /// Lower int64/uint64 operands to 32-bit for backends without 64-bit integer support.
+    #[serde(rename = "emulateInt64", default)]
+    emulate_int64: bool,
+    /// Pre-transpose constant conv filters into the converter's native layout before conversion.
+    #[serde(rename = "prepackWeights", default)]
+    prepack_weights: bool,
+    /// Fold batch normalization into convolutions and drop identity nodes before conversion.
+    #[serde(rename = "inferenceRewrites", default)]
+    inference_rewrites: bool,
+    /// Fold `reshape`/`transpose`/`cast` chains over inline constants before conversion. Off by
+    /// default, since it runs before rustnn validation and would hide errors in folded nodes.
+    #[serde(rename = "foldConstants", default)]
+    fold_constants: bool,
+    /// Drop nodes no graph output depends on before conversion. Off by default for the same
+    /// reason as `foldConstants`: invalid but unused nodes would never reach the validator.
+    #[serde(rename = "eliminateDeadNodes", default)]
+    eliminate_dead_nodes: bool,
+    /// Attach `stats` (effective CPU affinity, priority, ...) to the response.
+    #[serde(rename = "collectStats", default)]
+    collect_stats: bool,
+    /// `host:port` of a runner started with `--listen`; the graph is compiled here for `backend`
+    /// and executed there.
+    #[serde(rename = "remoteAddress", default)]
+    remote_address: Option<String>,
+    /// Optimization passes to skip, for attributing numeric differences during triage.
+    #[serde(rename = "disablePasses", default)]
+    disable_passes: Vec<String>,
+    /// Command line of an oracle process that also runs the compiled graph; its outputs are
+    /// compared with the executor's.
+    #[serde(rename = "compareExternal", default)]
+    compare_external: Option<String>,
+    /// Outputs whose storage exceeds this many bytes are written to a file instead of `data`.
+    #[serde(rename = "spillThresholdBytes", default)]
+    spill_threshold_bytes: Option<usize>,
+    /// Reject graphs with ops that are nondeterministic on the selected device.
+    #[serde(rename = "requireDeterminism", default)]
+    require_determinism: bool,
+    /// Keys not listed above; rejected by [`ContextOptions::validate`].
+    #[serde(flatten)]
+    unknown: BTreeMap<String, Value>,
+}
+
+/// Passes `disablePasses` can name.
+const ABLATABLE_PASSES: &[&str] = &[
+    "fusion",
+    "constantFolding",
+    "layoutRewrites",
+    "deadNodeElimination",
+];
+/// Passes that run inside rustnn converters/executors, which expose no toggle for them.
+const UNCONTROLLED_PASSES: &[&str] = &["fusion"];
+
+impl ContextOptions {
+    fn pass_disabled(&self, pass: &str) -> bool {
+        self.disable_passes.iter().any(|p| p == pass)
+    }
+
+    fn check_disabled_passes(&self, warnings: &mut Vec<WarningPayload>) -> Result<(), RunnerError> {
+        for pass in &self.disable_passes {
+            if !ABLATABLE_PASSES.contains(&pass.as_str()) {
+                return Err(RunnerError::BadRequest(format!(
+                    "unknown pass '{pass}' in disablePasses. Supported: {}",
+                    ABLATABLE_PASSES.join(", ")
+                )));
+            }
+            if UNCONTROLLED_PASSES.contains(&pass.as_str()) {
+                warnings.push(WarningPayload {
+                    kind: "PassNotDisabled".to_string(),
+                    message: format!(
+                        "pass '{pass}' runs inside the rustnn backend and cannot be disabled by the runner"
+                    ),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `MLContextOptions.deviceType`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DeviceType {
+    Cpu,
+    Gpu,
+    Npu,
+}
+
+/// `MLContextOptions.powerPreference`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PowerPreference {
+    Default,
+    HighPerformance,
+    LowPower,
+}
+
+impl DeviceType {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Cpu => "cpu",
+            Self::Gpu => "gpu",
+            Self::Npu => "npu",
+        }
+    }
+}
+
+impl ContextOptions {
+    fn device(&self) -> Result<(DeviceType, PowerPreference), RunnerError> {
+        let device = match self.device_type.as_deref().unwrap_or("cpu") {
+            "cpu" => DeviceType::Cpu,
+            "gpu" => DeviceType::Gpu,
+            "npu" => DeviceType::Npu,
+            other => {
+                return Err(RunnerError::ContextOptions(format!(
+                    "'{other}' is not a valid value for enumeration MLDeviceType (cpu, gpu, npu)"
+                )));
+            }
+        };
+        let power = match self.power_preference.as_deref().unwrap_or("default") {
+            "default" => PowerPreference::Default,
+            "high-performance" => PowerPreference::HighPerformance,
+            "low-power" => PowerPreference::LowPower,
+            other => {
+                return Err(RunnerError::ContextOptions(format!(
+                    "'{other}' is not a valid value for enumeration MLPowerPreference (default, high-performance, low-power)"
+                )));
+            }
+        };
+        Ok((device, power))
+    }
+
+    /// Context creation checks, run before any graph work so they fail like `createContext()`.
+    fn validate(&self) -> Result<(), RunnerError> {
+        if let Some(key) = self.unknown.keys().next() {
+            return Err(RunnerError::ContextOptions(format!(
+                "unknown context option '{key}'"
+            )));
+        }
+        self.device()?;
+        Backend::from_context(self).map(drop)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Backend {
+    Onnx,
+    Coreml,
+    Trtx,
+}
+
+/// One converter/executor pair as reported by `list_backends` and `--list-backends`.
+#[derive(Debug, Serialize)]
+struct BackendInfo {
+    name: &'static str,
+    /// Whether the executor is compiled in; the converter is always present.
+    available: bool,
+    devices: Vec<&'static str>,
+    /// Enabled cargo features that provide the executor.
+    features: Vec<&'static str>,
+    fingerprint: String,
+}
+
+impl Backend {
+    const ALL: [Self; 3] = [Self::Onnx, Self::Coreml, Self::Trtx];
+
+    fn from_context(options: &ContextOptions) -> Result<Self, RunnerError> {
+        let Some(selected) = options.backend.as_deref() else {
+            let (device, power) = options.device()?;
+            return Ok(Self::for_device(device, power));
+        };
+        match selected.trim().to_ascii_lowercase().as_str() {
+            "" | "onnx" | "ort" => Ok(Self::Onnx),
+            "coreml" => Ok(Self::Coreml),
+            "trtx" | "trt" | "tensorrt" => Ok(Self::Trtx),
+            other @ ("tflite" | "reference") => Err(RunnerError::BadRequest(format!(
+                "no '{other}' converter is registered in this runner. Supported: onnx, coreml, trtx"
+            ))),
+            other => Err(RunnerError::BadRequest(format!(
+                "unknown backend '{other}'. Supported: onnx, coreml, trtx"
+            ))),
+        }
+    }
+
+    /// Executor picked when the request names a device but no backend.
+    fn for_device(device: DeviceType, power: PowerPreference) -> Self {
+        let coreml = Self::Coreml.is_available();
+        let trtx = Self::Trtx.is_available();
+        let low_power = power == PowerPreference::LowPower;
+        match device {
+            DeviceType::Cpu => Self::Onnx,
+            DeviceType::Npu if coreml => Self::Coreml,
+            DeviceType::Gpu if coreml && (low_power || !trtx) => Self::Coreml,
+            DeviceType::Gpu if trtx => Self::Trtx,
+            _ => Self::Onnx,
+        }
+    }
+
+    /// Devices this executor can place work on.
+    fn devices(self) -> &'static [DeviceType] {
+        match self {
+            Self::Onnx => &[DeviceType::Cpu],
+            Self::Coreml => &[DeviceType::Cpu, DeviceType::Gpu, DeviceType::Npu],
+            Self::Trtx => &[DeviceType::Gpu],
+        }
+    }
+
+    /// Device the graph runs on when `requested` is asked for.
+    fn placement(self, requested: DeviceType) -> DeviceType {
+        if self.devices().contains(&requested) {
+            requested
+        } else {
+            self.devices()[0]
+        }
+    }
+
+    /// Converter and executor id, as recorded in [`CompiledGraph::target`].
+    fn target(self) -> &'static str {
+        match self {
+            Self::Onnx => "onnx",
+            Self::Coreml => "coreml",
+            Self::Trtx => "trtx",
+        }
+    }
+
+    fn from_target(target: &str) -> Result<Self, RunnerError> {
+        match target {
+            "onnx" => Ok(Self::Onnx),
+            "coreml" => Ok(Self::Coreml),
+            "trtx" => Ok(Self::Trtx),
+            other => Err(RunnerError::BadRequest(format!(
+                "compiled graph has unknown target '{other}'"
+            ))),
+        }
+    }
+
+    fn is_available(self) -> bool {
+        match self {
+            Self::Onnx => cfg!(feature = "backend-onnx"),
+            Self::Coreml => cfg!(all(target_os = "macos", feature = "backend-coreml")),
+            Self::Trtx => cfg!(any(feature = "backend-trtx", feature = "backend-trtx-mock")),
+        }
+    }
+
+    fn unavailable(self) -> RunnerError {
+        let hint = match self {
+            Self::Onnx => "feature backend-onnx",
+            Self::Coreml => "feature backend-coreml on macOS",
+            Self::Trtx => "feature backend-trtx or backend-trtx-mock",
+        };
+        if cfg!(feature = "validation-only") {
+            return RunnerError::NotSupported(format!(
+                "backend '{}' is unavailable; runner was built validation-only",
+                self.target()
+            ));
+        }
+        RunnerError::NotSupported(format!(
+            "backend '{}' is unavailable; rebuild runner with {hint}",
+            self.target()
+        ))
+    }
+
+    fn enabled_features(self) -> Vec<&'static str> {
+        let features: &[(&'static str, bool)] = match self {
+            Self::Onnx => &[("backend-onnx", cfg!(feature = "backend-onnx"))],
+            Self::Coreml => &[(
+                "backend-coreml",
+                cfg!(all(target_os = "macos", feature = "backend-coreml")),
+            )],
+            Self::Trtx => &[
+                ("backend-trtx", cfg!(feature = "backend-trtx")),
+                ("backend-trtx-mock", cfg!(feature = "backend-trtx-mock")),
+            ],
+        };
+        features
+            .iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(name, _)| *name)
+            .collect()
+    }
+
+    fn info(self) -> BackendInfo {
+        BackendInfo {
+            name: self.target(),
+            available: self.is_available(),
+            devices: self.devices().iter().map(|d| d.name()).collect(),
+            features: self.enabled_features(),
+            fingerprint: compiled::capability_fingerprint(self.target()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct TensorDescriptor {
+    #[serde(rename = "dataType")]
+    data_type: String,
+    shape: Vec<usize>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(try_from = "WireInputTensor")]
+struct InputTensor {
+    descriptor: TensorDescriptor,
+    data: Vec<Value>,
+    /// Generator for `data`, consumed by `distribution::generate_inputs`.
+    #[serde(skip)]
+    distribution: Option<DistributionSpec>,
+}
+
+/// `InputTensor` as sent on the wire, before `dataEncoding` is applied.
+#[derive(Deserialize)]
+struct WireInputTensor {
+    descriptor: TensorDescriptor,
+    #[serde(rename = "dataEncoding", default)]
+    data_encoding: DataEncoding,
+    #[serde(default)]
+    data: Value,
+    #[serde(default)]
+    distribution: Option<DistributionSpec>,
+}
+
+impl TryFrom<WireInputTensor> for InputTensor {
+    type Error = String;
+
+    fn try_from(wire: WireInputTensor) -> Result<Self, Self::Error> {
+        if wire.distribution.is_some() {
+            if !wire.data.is_null() {
+                return Err("tensor data and distribution are mutually exclusive".to_string());
+            }
+            return Ok(Self {
+                descriptor: wire.descriptor,
+                data: Vec::new(),
+                distribution: wire.distribution,
+            });
+        }
+        let data = match (wire.data_encoding, wire.data) {
+            (DataEncoding::Json, Value::Array(values)) => values,
+            (DataEncoding::Base64, Value::String(encoded)) => {
+                let count = ElementCount::of(&wire.descriptor.shape)
+                    .map_err(|e| e.to_string())?
+                    .get();
+                encoding::decode_values(&wire.descriptor.data_type, &encoded, count)?
+            }
+            (DataEncoding::Npy, Value::String(encoded)) => {
+                encoding::decode_npy(&wire.descriptor.data_type, &wire.descriptor.shape, &encoded)?
+            }
+            (DataEncoding::Json, _) => return Err("tensor data must be an array".to_string()),
+            (DataEncoding::Base64, _) => {
+                return Err("base64 tensor data must be a string".to_string());
+            }
+            (DataEncoding::Npy, _) => {
+                return Err("npy tensor data must be a base64 string".to_string());
+            }
+        };
+        Ok(Self {
+            descriptor: wire.descriptor,
+            data,
+            distribution: None,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ExpectedOutput {
+    descriptor: TensorDescriptor,
+    #[serde(default)]
+    data: Vec<Value>,
+    /// Encoding of the returned `data`.
+    #[serde(rename = "dataEncoding", default)]
+    data_encoding: DataEncoding,
+    /// Return only the first element of the output (reported with an `OutputTruncated` warning).
+    #[serde(default)]
+    summarize: bool,
+    /// Compare against `data` in the runner and return a verdict with the output.
+    #[serde(default)]
+    tolerance: Option<Tolerance>,
+    /// Also return the output's storage bytes as base64 `rawBytes`, next to the decoded `data`.
+    #[serde(rename = "rawBytes", default)]
+    raw_bytes: bool,
+    /// Summarize the output (top-k classes, decoded boxes) into `postprocessed`.
+    #[serde(default)]
+    postprocess: Option<postprocess::PostProcess>,
+    /// Return float elements as hex bit patterns, keeping NaN payloads and signed zeros.
+    #[serde(rename = "bitExact", default)]
+    bit_exact: bool,
+}
+
+/// Protocol features of this runner, reported in the `handshake` response.
+#[derive(Debug, Serialize)]
+struct Capabilities {
+    /// int64/uint64 data round-trips exactly over the whole 64-bit range, as integer literals or
+    /// decimal strings.
+    #[serde(rename = "losslessInt64")]
+    lossless_int64: bool,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct Response {
+    id: String,
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    outputs: Option<BTreeMap<String, OutputTensor>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<ErrorPayload>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<WarningPayload>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stats: Option<ExecutionStats>,
+    /// Per-item responses of an `execute_batch` request, in request order.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    responses: Option<Vec<Response>>,
+    /// Handle of the graph built by a `build_graph` request.
+    #[serde(rename = "graph", skip_serializing_if = "Option::is_none")]
+    graph_handle: Option<String>,
+    /// Unconverted executor outputs of a `run_compiled` request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    raw_outputs: Option<Vec<RuntimeOutput>>,
+    /// Graphviz DOT source of a `plan_graph` request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    plan: Option<String>,
+    /// DOT or Mermaid source of a `dump_graph` request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dump: Option<String>,
+    /// Result of a `diff_graphs` request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    diff: Option<graph_diff::GraphDiff>,
+    /// Stable operand ids of the submitted graph, on `validate_graph` and `plan_graph`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    operands: Option<Vec<operands::Operand>>,
+    /// Converter output of a `convert_graph` request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    compiled: Option<CompiledGraph>,
+    /// QDQ graph and per-tensor report of a `quantize_graph` request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    quantized: Option<quantize::QuantizedGraph>,
+    /// Output descriptors of a `validate_graph` request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output_descriptors: Option<BTreeMap<String, TensorDescriptorOut>>,
+    /// Per-output ULP distance statistics of a `deviation_stats` request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deviation: Option<BTreeMap<String, deviation::OutputDeviation>>,
+    /// Latency statistics of a `benchmark` request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    benchmark: Option<benchmark::BenchmarkReport>,
+    /// Converters and executors of a `list_backends` request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    backends: Option<Vec<BackendInfo>>,
+    /// `MLContext.opSupportLimits()` of a `get_capabilities` request.
+    #[serde(rename = "opSupportLimits", skip_serializing_if = "Option::is_none")]
+    op_support_limits: Option<Value>,
+    /// Protocol features, on a `handshake` response.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    capabilities: Option<Capabilities>,
+}
+
+#[derive(Debug, Serialize)]
+struct OutputTensor {
+    descriptor: TensorDescriptorOut,
+    /// An array of values, or a base64 string when `dataEncoding` is `base64`; null when spilled.
+    #[serde(skip_serializing_if = "Value::is_null")]
+    data: Value,
+    /// Where the output was written instead of `data`, above `spillThresholdBytes`.
+    #[serde(flatten)]
+    spilled: Option<spill::SpilledData>,
+    #[serde(rename = "dataEncoding", skip_serializing_if = "DataEncoding::is_json")]
+    data_encoding: DataEncoding,
+    /// Result of the expected output's `tolerance` check.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    verdict: Option<Verdict>,
+    /// Comparison with the `compareExternal` oracle's output.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    external: Option<Verdict>,
+    /// Base64 of the exact bytes of the returned elements in `dataType` storage (`int4`/`uint4`
+    /// packed), for bit-exact readback checks.
+    #[serde(rename = "rawBytes", skip_serializing_if = "Option::is_none")]
+    raw_bytes: Option<Value>,
+    /// Result of the expected output's `postprocess` step.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    postprocessed: Option<Value>,
+}
+
+#[derive(Debug, Serialize)]
+struct TensorDescriptorOut {
+    #[serde(rename = "dataType")]
+    data_type: String,
+    shape: Vec<usize>,
+}
+
+#[derive(Debug, Serialize)]
+struct ErrorPayload {
+    kind: String,
+    message: String,
+    /// DOMException name the WebNN spec mandates for this failure, when there is one.
+    #[serde(rename = "domException", skip_serializing_if = "Option::is_none")]
+    dom_exception: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct WarningPayload {
+    kind: String,
+    message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RuntimeOutput {
+    name: String,
+    shape: Vec<usize>,
+    #[serde(with = "remote::f64_bytes")]
+    data: Vec<f64>,
+    #[serde(default)]
+    int64_data: Option<Vec<i64>>,
+    #[serde(default)]
+    uint64_data: Option<Vec<u64>>,
+}
+
+#[derive(Debug, Error)]
+enum RunnerError {
+    #[error("bad request: {0}")]
+    BadRequest(String),
+    #[error("graph validation failed: {0}")]
+    GraphValidation(String),
+    #[error("input validation failed: {0}")]
+    InputValidation(String),
+    #[error("input data invalid: {0}")]
+    InputData(String),
+    #[error("graph conversion failed: {0}")]
+    GraphConversion(String),
+    #[error("output shape mismatch: {0}")]
+    ShapeMismatch(String),
+    #[error("runtime execution failed: {0}")]
+    RuntimeExecution(String),
+    #[error("not supported: {0}")]
+    NotSupported(String),
+    #[error("invalid context options: {0}")]
+    ContextOptions(String),
+    #[error("timed out: {0}")]
+    Timeout(String),
+    #[error("payload limit exceeded: {0}")]
+    PayloadLimit(String),
+}
+
+/// 2^53: every integer up to this magnitude has an exact `f64`.
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_992.0;
+
+/// An integer sent in float notation (`3.0`, `1e3`), accepted only when `f64` holds it exactly.
+///
+/// serde_json parses plain integer literals losslessly over the whole 64-bit range, while float
+/// notation goes through `f64`, so a large value there has already been rounded.
+fn exact_integer(v: f64, data_type: &str) -> Result<f64, RunnerError> {
+    if v.fract() == 0.0 && v.abs() <= MAX_SAFE_INTEGER {
+        return Ok(v);
+    }
+    Err(RunnerError::BadRequest(format!(
+        "invalid {data_type} value {v}: integers in float notation must be exact and within 2^53; \
+         send larger values as integer literals or decimal strings"
+    )))
+}
+
+fn parse_i64(v: &Value) -> Result<i64, RunnerError> {
+    if let Some(n) = v.as_i64() {
+        return Ok(n);
+    }
+    if let Some(n) = v.as_f64().filter(|_| v.is_f64()) {
+        return exact_integer(n, "int64").map(|n| n as i64);
+    }
+    if let Some(s) = v.as_str() {
+        return s
+            .parse::<i64>()
+            .map_err(|_| RunnerError::BadRequest(format!("invalid int64 value: {s}")));
+    }
+    Err(RunnerError::BadRequest(format!("invalid int64 value: {v}")))
+}
+
+fn parse_u64(v: &Value) -> Result<u64, RunnerError> {
+    if let Some(n) = v.as_u64() {
+        return Ok(n);
+    }
+    if let Some(n) = v.as_f64().filter(|n| v.is_f64() && *n >= 0.0) {
+        return exact_integer(n, "uint64").map(|n| n as u64);
+    }
+    if let Some(s) = v.as_str() {
+        return s
+            .parse::<u64>()
+            .map_err(|_| RunnerError::BadRequest(format!("invalid uint64 value: {s}")));
+    }
+    Err(RunnerError::BadRequest(format!(
+        "invalid uint64 value: {v}"
+    )))
+}
+
+fn parse_f32(v: &Value) -> Result<f32, RunnerError> {
+    if let Some(bits) = encoding::hex_bits(v, 32) {
+        return bits
+            .map(|bits| f32::from_bits(bits as u32))
+            .map_err(RunnerError::BadRequest);
+    }
+    if let Some(n) = v.as_f64() {
+        return Ok(n as f32);
+    }
+    if let Some(n) = v.as_i64() {
+        return Ok(n as f32);
+    }
+    if let Some(n) = v.as_u64() {
+        return Ok(n as f32);
+    }
+    if let Some(s) = v.as_str() {
+        return s
+            .parse::<f32>()
+            .map_err(|_| RunnerError::BadRequest(format!("invalid float value: {s}")));
+    }
+    Err(RunnerError::BadRequest(format!("invalid float value: {v}")))
+}
+
+fn parse_f64(v: &Value) -> Result<f64, RunnerError> {
+    if let Some(bits) = encoding::hex_bits(v, 64) {
+        return bits.map(f64::from_bits).map_err(RunnerError::BadRequest);
+    }
+    if let Some(n) = v.as_f64() {
+        return Ok(n);
+    }
+    if let Some(s) = v.as_str() {
+        return s
+            .parse::<f64>()
+            .map_err(|_| RunnerError::BadRequest(format!("invalid float64 value: {s}")));
+    }
+    Err(RunnerError::BadRequest(format!(
+        "invalid float64 value: {v}"
+    )))
+}
+
+fn normalize_input_values(
+    descriptor: &TensorDescriptor,
+    data: &[Value],
+) -> Result<Vec<Value>, RunnerError> {
+    let count = ElementCount::of(&descriptor.shape)?;
+    let expected = count.get();
+    let actual = data.len();
+
+    if actual == expected {
+        return Ok(data.to_vec());
+    }
+    if actual == 1 && expected > 1 {
+        // Shapes come straight from the request, so a huge broadcast must fail, not abort.
+        count.check_allocation(&descriptor.data_type)?;
+        let mut expanded = Vec::new();
+        expanded.try_reserve_exact(expected).map_err(|_| {
+            RunnerError::BadRequest(format!(
+                "cannot broadcast input to {expected} values for shape {:?}",
+                descriptor.shape
+            ))
+        })?;
+        expanded.resize(expected, data[0].clone());
+        return Ok(expanded);
+    }
+
+    Err(RunnerError::BadRequest(format!(
+        "input data length mismatch: expected {} values for shape {:?}, got {}",
+        expected, descriptor.shape, actual
+    )))
+}
+
+/// Parse and range check int4/uint4 values.
+fn parse_4bit(data_type: &str, values: &[Value]) -> Result<Vec<i64>, RunnerError> {
+    values
+        .iter()
+        .map(|v| {
+            let n = parse_i64(v)?;
+            int4::check_range(data_type, n).map_err(RunnerError::BadRequest)?;
+            Ok(n)
+        })
+        .collect()
+}
+
+/// Typed element buffer of one tensor.
+///
+/// Mirrors rustnn's ONNX `TensorData` so freezing, TensorRT bindings and base64 outputs keep
+/// working in builds without the ONNX runtime. `Float64` has no rustnn counterpart: float64
+/// tensors parse and encode, so rustnn's validator decides whether a graph may use them, but no
+/// executor takes them as inputs.
+enum TensorData {
+    Float32(Vec<f32>),
+    Float64(Vec<f64>),
+    Float16(Vec<u16>),
+    Int8(Vec<i8>),
+    Uint8(Vec<u8>),
+    Int32(Vec<i32>),
+    Uint32(Vec<u32>),
+    Int64(Vec<i64>),
+    Uint64(Vec<u64>),
+}
+
+#[cfg(feature = "backend-onnx")]
+impl TryFrom<TensorData> for OnnxTensorData {
+    type Error = RunnerError;
+
+    fn try_from(data: TensorData) -> Result<Self, Self::Error> {
+        Ok(match data {
+            TensorData::Float32(values) => Self::Float32(values),
+            TensorData::Float64(_) => {
+                return Err(RunnerError::NotSupported(
+                    "rustnn's ONNX executor has no float64 inputs".to_string(),
+                ));
+            }
+            TensorData::Float16(values) => Self::Float16(values),
+            TensorData::Int8(values) => Self::Int8(values),
+            TensorData::Uint8(values) => Self::Uint8(values),
+            TensorData::Int32(values) => Self::Int32(values),
+            TensorData::Uint32(values) => Self::Uint32(values),
+            TensorData::Int64(values) => Self::Int64(values),
+            TensorData::Uint64(values) => Self::Uint64(values),
+        })
+    }
+}
+
+fn to_tensor_data(
+    descriptor: &TensorDescriptor,
+    data: &[Value],
+) -> Result<TensorData, RunnerError> {
+    let normalized = normalize_input_values(descriptor, data)?;
+    match descriptor.data_type.as_str() {
+        "float32" => Ok(TensorData::Float32(
+            normalized
+                .iter()
+                .map(parse_f32)
+                .collect::<Result<Vec<_>, _>>()?,
+        )),
+        "float64" => Ok(TensorData::Float64(
+            normalized
+                .iter()
+                .map(parse_f64)
+                .collect::<Result<Vec<_>, _>>()?,
+        )),
+        "float16" => {
+            let bits = normalized
+                .iter()
+                .map(|v| match encoding::hex_bits(v, 16) {
+                    Some(bits) => bits
+                        .map(|bits| bits as u16)
+                        .map_err(RunnerError::BadRequest),
+                    None => Ok(f16::from_f32(parse_f32(v)?).to_bits()),
+                })
+                .collect::<Result<Vec<u16>, RunnerError>>()?;
+            Ok(TensorData::Float16(bits))
+        }
+        "int8" => Ok(TensorData::Int8(
+            normalized
+                .iter()
+                .map(|v| parse_i64(v).map(|x| x as i8))
+                .collect::<Result<Vec<_>, _>>()?,
+        )),
+        "uint8" => Ok(TensorData::Uint8(
+            normalized
+                .iter()
+                .map(|v| parse_u64(v).map(|x| x as u8))
+                .collect::<Result<Vec<_>, _>>()?,
+        )),
+        // rustnn executors take 4-bit inputs widened: uint4 as Uint8, int4 as Int32.
+        "uint4" => Ok(TensorData::Uint8(
+            parse_4bit(&descriptor.data_type, &normalized)?
+                .into_iter()
+                .map(|x| x as u8)
+                .collect(),
+        )),
+        "int4" => Ok(TensorData::Int32(
+            parse_4bit(&descriptor.data_type, &normalized)?
+                .into_iter()
+                .map(|x| x as i32)
+                .collect(),
+        )),
+        "int32" => Ok(TensorData::Int32(
+            normalized
+                .iter()
+                .map(|v| parse_i64(v).map(|x| x as i32))
+                .collect::<Result<Vec<_>, _>>()?,
+        )),
+        "uint32" => Ok(TensorData::Uint32(
+            normalized
+                .iter()
+                .map(|v| parse_u64(v).map(|x| x as u32))
+                .collect::<Result<Vec<_>, _>>()?,
+        )),
+        "int64" => Ok(TensorData::Int64(
+            normalized
+                .iter()
+                .map(parse_i64)
+                .collect::<Result<Vec<_>, _>>()?,
+        )),
+        "uint64" => Ok(TensorData::Uint64(
+            normalized
+                .iter()
+                .map(parse_u64)
+                .collect::<Result<Vec<_>, _>>()?,
+        )),
+        other => Err(RunnerError::BadRequest(format!(
+            "unsupported input dataType: {other}"
+        ))),
+    }
+}
+
+fn tensor_data_to_le_bytes(data: TensorData) -> Vec<u8> {
+    match data {
+        TensorData::Float32(values) => values.into_iter().flat_map(f32::to_le_bytes).collect(),
+        TensorData::Float64(values) => values.into_iter().flat_map(f64::to_le_bytes).collect(),
+        TensorData::Float16(values) => values.into_iter().flat_map(u16::to_le_bytes).collect(),
+        TensorData::Int8(values) => values.into_iter().map(|v| v as u8).collect(),
+        TensorData::Uint8(values) => values,
+        TensorData::Int32(values) => values.into_iter().flat_map(i32::to_le_bytes).collect(),
+        TensorData::Uint32(values) => values.into_iter().flat_map(u32::to_le_bytes).collect(),
+        TensorData::Int64(values) => values.into_iter().flat_map(i64::to_le_bytes).collect(),
+        TensorData::Uint64(values) => values.into_iter().flat_map(u64::to_le_bytes).collect(),
+    }
+}
+
+/// `.npy` file of a tensor's values, with the dtype `npz::numpy_type` gives its `dataType`.
+fn npy_file(descriptor: &TensorDescriptor, data: &[Value]) -> Result<Vec<u8>, RunnerError> {
+    let stored = TensorDescriptor {
+        data_type: npz::numpy_type(&descriptor.data_type).to_string(),
+        shape: descriptor.shape.clone(),
+    };
+    Ok(npz::npy(&stored.shape, to_tensor_data(&stored, data)?))
+}
+
+#[cfg(all(target_os = "macos", feature = "backend-coreml"))]
+fn to_f32_values(descriptor: &TensorDescriptor, data: &[Value]) -> Result<Vec<f32>, RunnerError> {
+    let normalized = normalize_input_values(descriptor, data)?;
+    normalized.iter().map(parse_f32).collect()
+}
+
+#[cfg(feature = "backend-onnx")]
+fn onnx_outputs_to_runtime(outputs: Vec<OnnxOutputWithData>) -> Vec<RuntimeOutput> {
+    outputs
+        .into_iter()
+        .map(|output| RuntimeOutput {
+            name: output.name,
+            shape: output.shape,
+            data: output.data,
+            int64_data: output.int64_data,
+            uint64_data: output.uint64_data,
+        })
+        .collect()
+}
+
+#[cfg(all(target_os = "macos", feature = "backend-coreml"))]
+fn coreml_output_shape_to_usize(shape: &[i64]) -> Result<Vec<usize>, RunnerError> {
+    shape
+        .iter()
+        .map(|&dim| {
+            if dim < 0 {
+                return Err(RunnerError::RuntimeExecution(format!(
+                    "coreml output shape contains negative dimension: {shape:?}"
+                )));
+            }
+            Ok(dim as usize)
+        })
+        .collect()
+}
+
+#[cfg(all(target_os = "macos", feature = "backend-coreml"))]
+fn coreml_outputs_to_runtime(
+    outputs: Vec<CoremlOutput>,
+) -> Result<Vec<RuntimeOutput>, RunnerError> {
+    outputs
+        .into_iter()
+        .map(|output| {
+            let shape = coreml_output_shape_to_usize(&output.shape)?;
+            let data = output
+                .data
+                .into_iter()
+                .map(|v| v as f64)
+                .collect::<Vec<_>>();
+            Ok(RuntimeOutput {
+                name: output.name,
+                shape,
+                data,
+                int64_data: None,
+                uint64_data: None,
+            })
+        })
+        .collect()
+}
+
+#[cfg(any(feature = "backend-trtx", feature = "backend-trtx-mock"))]
+fn read_le_chunks<const N: usize>(bytes: &[u8]) -> Result<Vec<[u8; N]>, RunnerError> {
+    if bytes.len() % N != 0 {
+        return Err(RunnerError::RuntimeExecution(format!(
+            "invalid tensor byte length {} for element width {}",
+            bytes.len(),
+            N
+        )));
+    }
+
+    Ok(bytes
+        .chunks_exact(N)
+        .map(|chunk| {
+            let mut arr = [0u8; N];
+            arr.copy_from_slice(chunk);
+            arr
+        })
+        .collect())
+}
+
+#[cfg(any(feature = "backend-trtx", feature = "backend-trtx-mock"))]
+fn trtx_output_to_runtime(
+    output: TrtxOutputWithData,
+    logical_name: String,
+) -> Result<RuntimeOutput, RunnerError> {
+    let data_type = output.data_type.to_ascii_lowercase();
+    let (data, int64_data, uint64_data) = match data_type.as_str() {
+        "float32" => {
+            let values = read_le_chunks::<4>(&output.data)?
+                .into_iter()
+                .map(|b| f32::from_le_bytes(b) as f64)
+                .collect::<Vec<_>>();
+            (values, None, None)
+        }
+        "float16" => {
+            let values = read_le_chunks::<2>(&output.data)?
+                .into_iter()
+                .map(|b| f16::from_bits(u16::from_le_bytes(b)).to_f32() as f64)
+                .collect::<Vec<_>>();
+            (values, None, None)
+        }
+        "int8" => {
+            let values = output
+                .data
+                .iter()
+                .map(|b| (*b as i8) as f64)
+                .collect::<Vec<_>>();
+            (values, None, None)
+        }
+        "uint8" | "bool" => {
+            let values = output.data.iter().map(|b| *b as f64).collect::<Vec<_>>();
+            (values, None, None)
+        }
+        "int32" => {
+            let values = read_le_chunks::<4>(&output.data)?
+                .into_iter()
+                .map(|b| i32::from_le_bytes(b) as f64)
+                .collect::<Vec<_>>();
+            (values, None, None)
+        }
+        "uint32" => {
+            let values = read_le_chunks::<4>(&output.data)?
+                .into_iter()
+                .map(|b| u32::from_le_bytes(b) as f64)
+                .collect::<Vec<_>>();
+            (values, None, None)
+        }
+        "int64" => {
+            let values = read_le_chunks::<8>(&output.data)?
+                .into_iter()
+                .map(i64::from_le_bytes)
+                .collect::<Vec<_>>();
+            (
+                values.iter().map(|v| *v as f64).collect::<Vec<_>>(),
+                Some(values),
+                None,
+            )
+        }
+        "uint64" => {
+            let values = read_le_chunks::<8>(&output.data)?
+                .into_iter()
+                .map(u64::from_le_bytes)
+                .collect::<Vec<_>>();
+            (
+                values.iter().map(|v| *v as f64).collect::<Vec<_>>(),
+                None,
+                Some(values),
+            )
+        }
+        other => {
+            return Err(RunnerError::RuntimeExecution(format!(
+                "unsupported TensorRT output data type: {other}"
+            )));
+        }
+    };
+
+    Ok(RuntimeOutput {
+        name: logical_name,
+        shape: output.shape,
+        data,
+        int64_data,
+        uint64_data,
+    })
+}
+
+fn cast_output_data(
+    data: &[f64],
+    int64_data: Option<&[i64]>,
+    uint64_data: Option<&[u64]>,
+    dtype: &str,
+) -> Vec<Value> {
+    fn float_value(x: f64) -> Value {
+        if x.is_nan() {
+            Value::String("NaN".to_string())
+        } else if x.is_infinite() {
+            if x.is_sign_positive() {
+                Value::String("Infinity".to_string())
+            } else {
+                Value::String("-Infinity".to_string())
+            }
+        } else {
+            Value::from(canonical::float(x))
+        }
+    }
+
+    match dtype {
+        "float32" => data.iter().map(|x| float_value(*x)).collect(),
+        // Full precision: the canonical float path narrows to f32.
+        "float64" => data.iter().map(|x| encoding::float64_value(*x)).collect(),
+        "float16" => data
+            .iter()
+            .map(|x| float_value(f16::from_f32(*x as f32).to_f32() as f64))
+            .collect(),
+        "bfloat16" => data
+            .iter()
+            .map(|x| float_value(bf16_lowering::round(*x as f32) as f64))
+            .collect(),
+        "int8" => data
+            .iter()
+            .map(|x| Value::from((*x as i8) as i64))
+            .collect(),
+        "uint8" => data
+            .iter()
+            .map(|x| Value::from((*x as u8) as u64))
+            .collect(),
+        "uint4" => data
+            .iter()
+            .map(|x| Value::from(((*x as u8).min(15)) as u64))
+            .collect(),
+        "int4" => data
+            .iter()
+            .map(|x| Value::from(((*x as i8).clamp(-8, 7)) as i64))
+            .collect(),
+        "int32" => data
+            .iter()
+            .map(|x| Value::from((*x as i32) as i64))
+            .collect(),
+        "uint32" => data
+            .iter()
+            .map(|x| Value::from((*x as u32) as u64))
+            .collect(),
+        "int64" => {
+            if let Some(values) = int64_data {
+                values
+                    .iter()
+                    .map(|x| Value::String(x.to_string()))
+                    .collect()
+            } else {
+                data.iter()
+                    .map(|x| Value::String((*x as i64).to_string()))
+                    .collect()
+            }
+        }
+        "uint64" => {
+            if let Some(values) = uint64_data {
+                values
+                    .iter()
+                    .map(|x| Value::String(x.to_string()))
+                    .collect()
+            } else {
+                data.iter()
+                    .map(|x| Value::String((*x as u64).to_string()))
+                    .collect()
+            }
+        }
+        _ => data.iter().map(|x| float_value(*x)).collect(),
+    }
+}
+
+/// Output values cast to `dtype` in the executor's element representation.
+fn output_tensor_data(output: &RuntimeOutput, dtype: &str, len: usize) -> TensorData {
+    let data = &output.data[..len];
+    match dtype {
+        "float64" => TensorData::Float64(data.to_vec()),
+        "float16" => {
+            TensorData::Float16(data.iter().map(|x| f16::from_f64(*x).to_bits()).collect())
+        }
+        // No bfloat16 variant; Float16 only carries the 16-bit patterns to the byte encoder.
+        "bfloat16" => TensorData::Float16(
+            data.iter()
+                .map(|x| bf16_lowering::to_bits(*x as f32))
+                .collect(),
+        ),
+        "int8" => TensorData::Int8(data.iter().map(|x| *x as i8).collect()),
+        "int4" => TensorData::Int8(data.iter().map(|x| (*x as i8).clamp(-8, 7)).collect()),
+        "uint8" => TensorData::Uint8(data.iter().map(|x| *x as u8).collect()),
+        "uint4" => TensorData::Uint8(data.iter().map(|x| (*x as u8).min(15)).collect()),
+        "int32" => TensorData::Int32(data.iter().map(|x| *x as i32).collect()),
+        "uint32" => TensorData::Uint32(data.iter().map(|x| *x as u32).collect()),
+        "int64" => TensorData::Int64(match &output.int64_data {
+            Some(values) => values[..len].to_vec(),
+            None => data.iter().map(|x| *x as i64).collect(),
+        }),
+        "uint64" => TensorData::Uint64(match &output.uint64_data {
+            Some(values) => values[..len].to_vec(),
+            None => data.iter().map(|x| *x as u64).collect(),
+        }),
+        _ => TensorData::Float32(data.iter().map(|x| *x as f32).collect()),
+    }
+}
+
+/// Encode the first `len` elements of `output` as `dtype`.
+fn encode_output_data(
+    output: &RuntimeOutput,
+    dtype: &str,
+    len: usize,
+    data_encoding: DataEncoding,
+) -> Value {
+    match data_encoding {
+        DataEncoding::Json => Value::Array(cast_output_data(
+            &output.data[..len],
+            output.int64_data.as_deref().map(|v| &v[..len]),
+            output.uint64_data.as_deref().map(|v| &v[..len]),
+            dtype,
+        )),
+        DataEncoding::Base64 => encoding::encode_bytes(&output_bytes(output, dtype, len)),
+        DataEncoding::Npy => {
+            // A summarized output is no longer the declared shape.
+            let shape = if len == output.data.len() {
+                output.shape.clone()
+            } else {
+                vec![len]
+            };
+            let data = output_tensor_data(output, npz::numpy_type(dtype), len);
+            encoding::encode_bytes(&npz::npy(&shape, data))
+        }
+    }
+}
+
+/// Storage bytes of the first `len` elements in `dtype` layout (`int4`/`uint4` packed).
+fn output_bytes(output: &RuntimeOutput, dtype: &str, len: usize) -> Vec<u8> {
+    if int4::is_4bit(dtype) {
+        let (min, max) = int4::range(dtype);
+        let values = output.data[..len]
+            .iter()
+            .map(|x| (*x as i64).clamp(min, max))
+            .collect::<Vec<_>>();
+        int4::pack(&values)
+    } else {
+        tensor_data_to_le_bytes(output_tensor_data(output, dtype, len))
+    }
+}
+
+/// Write the first `len` elements of `output` to a file when they exceed `spillThresholdBytes`.
+fn spill_output(
+    name: &str,
+    output: &RuntimeOutput,
+    dtype: &str,
+    len: usize,
+    context_options: &ContextOptions,
+) -> Result<Option<spill::SpilledData>, RunnerError> {
+    match context_options.spill_threshold_bytes {
+        Some(threshold) if spill::storage_len(dtype, len) > threshold => {
+            spill::write(name, &output_bytes(output, dtype, len)).map(Some)
+        }
+        _ => Ok(None),
+    }
+}
+
+fn compile_graph(
+    backend: Backend,
+    graph_info: &GraphInfo,
+    prepared: &PreparedGraph,
+) -> Result<CompiledGraph, RunnerError> {
+    let target = backend.target();
+    let converted = ConverterRegistry::with_defaults()
+        .convert(target, graph_info)
+        .map_err(|e| RunnerError::GraphConversion(e.to_string()))?;
+    let (input_bindings, output_bindings) = engine_bindings(backend, graph_info)?;
+    Ok(CompiledGraph {
+        format_version: compiled::FORMAT_VERSION,
+        target: target.to_string(),
+        artifact: converted.data,
+        weights: converted.weights_data,
+        inputs: prepared.inputs.clone(),
+        outputs: prepared.outputs.clone(),
+        input_bindings,
+        output_bindings,
+        fingerprint: compiled::capability_fingerprint(target),
+    })
+}
+
+#[cfg(any(feature = "backend-trtx", feature = "backend-trtx-mock"))]
+fn engine_bindings(
+    backend: Backend,
+    graph_info: &GraphInfo,
+) -> Result<(Vec<EngineBinding>, BTreeMap<String, String>), RunnerError> {
+    if !matches!(backend, Backend::Trtx) {
+        return Ok((Vec::new(), BTreeMap::new()));
+    }
+
+    // Native WebNN->TRT engines bind tensors as `webnn_operand_{operand_id}` (see TrtxConverter), not
+    // WPT logical names like `quantizeLinearInput`.
+    let mut inputs = Vec::with_capacity(graph_info.input_operands.len());
+    for &op_id in &graph_info.input_operands {
+        let operand = graph_info.operand(op_id).ok_or_else(|| {
+            RunnerError::RuntimeExecution(format!(
+                "graph declares input operand id {op_id} but it is missing"
+            ))
+        })?;
+        let logical = operand.name.clone().ok_or_else(|| {
+            RunnerError::RuntimeExecution(format!(
+                "graph input operand {op_id} has no logical name for TRT binding"
+            ))
+        })?;
+        inputs.push(EngineBinding {
+            binding: TrtxConverter::engine_binding_name(op_id),
+            name: logical,
+        });
+    }
+
+    let outputs = (0u32..)
+        .map_while(|op_id| graph_info.operand(op_id).map(|op| (op_id, op)))
+        .filter_map(|(op_id, op)| {
+            op.name
+                .clone()
+                .map(|name| (TrtxConverter::engine_binding_name(op_id), name))
+        })
+        .collect();
+    Ok((inputs, outputs))
+}
+
+#[cfg(not(any(feature = "backend-trtx", feature = "backend-trtx-mock")))]
+fn engine_bindings(
+    _backend: Backend,
+    _graph_info: &GraphInfo,
+) -> Result<(Vec<EngineBinding>, BTreeMap<String, String>), RunnerError> {
+    Ok((Vec::new(), BTreeMap::new()))
+}
+
+#[cfg(feature = "backend-onnx")]
+fn run_onnx_compiled(
+    compiled: &CompiledGraph,
+    inputs: &BTreeMap<String, InputTensor>,
+) -> Result<Vec<RuntimeOutput>, RunnerError> {
+    let mut onnx_inputs = Vec::with_capacity(inputs.len());
+    for (name, input) in inputs {
+        onnx_inputs.push(OnnxInput {
+            name: name.clone(),
+            shape: input.descriptor.shape.clone(),
+            data: to_tensor_data(&input.descriptor, &input.data)?.try_into()?,
+        });
+    }
+
+    let outputs =
+        run_onnx_with_inputs(&compiled.artifact, compiled.weights.as_deref(), onnx_inputs)
+            .map_err(|e| RunnerError::RuntimeExecution(e.to_string()))?;
+    Ok(onnx_outputs_to_runtime(outputs))
+}
+
+#[cfg(not(feature = "backend-onnx"))]
+fn run_onnx_compiled(
+    _compiled: &CompiledGraph,
+    _inputs: &BTreeMap<String, InputTensor>,
+) -> Result<Vec<RuntimeOutput>, RunnerError> {
+    Err(Backend::Onnx.unavailable())
+}
+
+#[cfg(all(target_os = "macos", feature = "backend-coreml"))]
+fn run_coreml_compiled(
+    compiled: &CompiledGraph,
+    inputs: &BTreeMap<String, InputTensor>,
+) -> Result<Vec<RuntimeOutput>, RunnerError> {
+    let mut coreml_inputs = Vec::with_capacity(inputs.len());
+    for (name, input) in inputs {
+        coreml_inputs.push(CoremlInput {
+            name: name.clone(),
+            shape: input.descriptor.shape.clone(),
+            data: to_f32_values(&input.descriptor, &input.data)?,
+        });
+    }
+
+    let attempts = run_coreml_with_inputs_with_weights(
+        &compiled.artifact,
+        compiled.weights.as_deref(),
+        coreml_inputs,
+    )
+    .map_err(|e| RunnerError::RuntimeExecution(e.to_string()))?;
+
+    let outputs = attempts
+        .into_iter()
+        .find_map(|attempt| attempt.result.ok())
+        .ok_or_else(|| {
+            RunnerError::RuntimeExecution(
+                "coreml runtime failed: all compute unit attempts failed".to_string(),
+            )
+        })?;
+
+    coreml_outputs_to_runtime(outputs)
+}
+
+#[cfg(not(all(target_os = "macos", feature = "backend-coreml")))]
+fn run_coreml_compiled(
+    _compiled: &CompiledGraph,
+    _inputs: &BTreeMap<String, InputTensor>,
+) -> Result<Vec<RuntimeOutput>, RunnerError> {
+    Err(Backend::Coreml.unavailable())
+}
+
+#[cfg(any(feature = "backend-trtx", feature = "backend-trtx-mock"))]
+fn run_trtx_compiled(
+    compiled: &CompiledGraph,
+    inputs: &BTreeMap<String, InputTensor>,
+) -> Result<Vec<RuntimeOutput>, RunnerError> {
+    let mut trtx_inputs = Vec::with_capacity(compiled.input_bindings.len());
+    for binding in &compiled.input_bindings {
+        let input = inputs.get(&binding.name).ok_or_else(|| {
+            RunnerError::RuntimeExecution(format!(
+                "missing runtime input `{}` required for TensorRT binding {}",
+                binding.name, binding.binding
+            ))
+        })?;
+        trtx_inputs.push(TrtxInput {
+            name: binding.binding.clone(),
+            data: tensor_data_to_le_bytes(to_tensor_data(&input.descriptor, &input.data)?),
+        });
+    }
+
+    let outputs = run_trtx_with_inputs(&compiled.artifact, trtx_inputs)
+        .map_err(|e| RunnerError::RuntimeExecution(e.to_string()))?;
+    outputs
+        .into_iter()
+        .map(|o| {
+            let logical = compiled
+                .output_bindings
+                .get(&o.name)
+                .cloned()
+                .unwrap_or_else(|| o.name.clone());
+            trtx_output_to_runtime(o, logical)
+        })
+        .collect()
+}
+
+#[cfg(not(any(feature = "backend-trtx", feature = "backend-trtx-mock")))]
+fn run_trtx_compiled(
+    _compiled: &CompiledGraph,
+    _inputs: &BTreeMap<String, InputTensor>,
+) -> Result<Vec<RuntimeOutput>, RunnerError> {
+    Err(Backend::Trtx.unavailable())
+}
+
+fn run_compiled(
+    compiled: &CompiledGraph,
+    inputs: &BTreeMap<String, InputTensor>,
+) -> Result<Vec<RuntimeOutput>, RunnerError> {
+    compiled
+        .check_fingerprint()
+        .map_err(RunnerError::RuntimeExecution)?;
+    match Backend::from_target(&compiled.target)? {
+        Backend::Onnx => run_onnx_compiled(compiled, inputs),
+        Backend::Coreml => run_coreml_compiled(compiled, inputs),
+        Backend::Trtx => run_trtx_compiled(compiled, inputs),
+    }
+}
+
+struct PreparedGraph {
+    graph: GraphJson,
+    inputs: Vec<OperandDescriptor>,
+    outputs: Vec<String>,
+    /// Content digest for the compile cache; `None` when the cache is disabled.
+    graph_digest: Option<compile_cache::GraphDigest>,
+    /// Output names dropped from the graph because they export an already exported operand,
+    /// mapped to the output name that was kept.
+    output_aliases: BTreeMap<String, String>,
+    /// Output names produced by logical operators, reported as 0/1 `uint8`.
+    boolean_outputs: BTreeSet<String>,
+    /// Output `dataType`s inferred from the graph as submitted, before any lowering.
+    output_types: BTreeMap<String, String>,
+    /// Constants repacked into the backend-native filter layout.
+    prepacked: Vec<String>,
+}
+
+/// Request-level checks and graph-json rewrites that run before rustnn sees the graph.
+///
+/// Without `inputs` (graph validation only), the runtime input rewrites and dispatch checks are
+/// skipped.
+fn prepare_graph(
+    mut graph: Value,
+    mut inputs: Option<&mut BTreeMap<String, InputTensor>>,
+    context_options: &ContextOptions,
+    warnings: &mut Vec<WarningPayload>,
+) -> Result<PreparedGraph, RunnerError> {
+    context_options.validate()?;
+    context_options.check_disabled_passes(warnings)?;
+    graph_refs::check_operand_references(&graph).map_err(RunnerError::GraphValidation)?;
+    boolean::check_where_conditions(&graph)?;
+    match inputs.as_deref() {
+        Some(inputs) => dynamic_dims::resolve(&mut graph, inputs)?,
+        None => dynamic_dims::check_static(&graph)?,
+    }
+    let output_types = output_dtype::infer(&graph);
+    let lowered = reduction::lower_empty_axes(&mut graph);
+    if std::env::var("RUSTNNPT_DEBUG").as_deref() == Ok("1") && !lowered.is_empty() {
+        eprintln!("[RUNNER] reductions over empty axes made elementwise: {lowered:?}");
+    }
+    if context_options.eliminate_dead_nodes && !context_options.pass_disabled("deadNodeElimination")
+    {
+        let removed = prune::prune_dead_nodes(&mut graph);
+        if std::env::var("RUSTNNPT_DEBUG").as_deref() == Ok("1") && !removed.is_empty() {
+            eprintln!("[RUNNER] removed dead nodes: {removed:?}");
+        }
+    }
+    bf16_lowering::lower_graph(&mut graph, warnings)?;
+    if let Some(inputs) = inputs.as_deref_mut() {
+        bf16_lowering::lower_inputs(inputs)?;
+    }
+    if context_options.emulate_int64 {
+        int64_lowering::lower_graph(&mut graph, warnings)?;
+        if let Some(inputs) = inputs.as_deref_mut() {
+            int64_lowering::lower_inputs(inputs)?;
+        }
+    }
+    if context_options.fold_constants && !context_options.pass_disabled("constantFolding") {
+        let folded = constant_fold::fold_constants(&mut graph);
+        if std::env::var("RUSTNNPT_DEBUG").as_deref() == Ok("1") && !folded.is_empty() {
+            eprintln!("[RUNNER] folded constant nodes: {folded:?}");
+        }
+    }
+    if context_options.inference_rewrites {
+        let applied = inference_rewrite::rewrite(&mut graph);
+        if std::env::var("RUSTNNPT_DEBUG").as_deref() == Ok("1") && !applied.is_empty() {
+            eprintln!("[RUNNER] inference rewrites: {applied:?}");
+        }
+    }
+    let mut prepacked = Vec::new();
+    if context_options.prepack_weights && !context_options.pass_disabled("layoutRewrites") {
+        prepacked = weight_prepack::prepack_filters(&mut graph)?;
+        if std::env::var("RUSTNNPT_DEBUG").as_deref() == Ok("1") && !prepacked.is_empty() {
+            eprintln!("[RUNNER] prepacked filter constants: {prepacked:?}");
+        }
+    }
+    if let Some(inputs) = inputs {
+        dispatch_check::check_inputs(&graph, inputs)?;
+    }
+    let boolean_outputs = boolean::boolean_outputs(&graph);
+    let output_aliases = output_alias::dedup_outputs(&mut graph);
+    output_alias::materialize_passthrough_outputs(&mut graph);
+    let inputs = compiled::input_descriptors(&graph);
+    let outputs = compiled::output_names(&graph);
+    let graph_digest = compile_cache::enabled().then(|| compile_cache::graph_digest(&graph));
+    let graph = serde_json::from_value(graph)
+        .map_err(|e| RunnerError::BadRequest(format!("invalid graph: {e}")))?;
+    Ok(PreparedGraph {
+        graph,
+        inputs,
+        outputs,
+        graph_digest,
+        output_aliases,
+        boolean_outputs,
+        output_types,
+        prepacked,
+    })
+}
+
+fn validated_graph_info(prepared: &PreparedGraph) -> Result<GraphInfo, RunnerError> {
+    let started = Instant::now();
+    let result = rustnn::webnn_json::from_graph_json(&prepared.graph)
+        .map_err(|e| RunnerError::GraphValidation(e.to_string()))
+        .and_then(|graph_info| {
+            let validator = GraphValidator::new(&graph_info, ContextProperties::default());
+            let _artifacts = validator
+                .validate()
+                .map_err(|e| RunnerError::GraphValidation(e.to_string()))?;
+            Ok(graph_info)
+        });
+    telemetry::record(TelemetryEvent::ValidationFinished {
+        elapsed: started.elapsed(),
+        ok: result.is_ok(),
+    });
+    result
+}
+
+fn validate_and_compile(
+    backend: Backend,
+    prepared: &PreparedGraph,
+) -> Result<CompiledGraph, RunnerError> {
+    progress::stage("validate", 25);
+    let graph_info = validated_graph_info(prepared)?;
+    progress::stage("convert", 50);
+    let started = Instant::now();
+    let compiled = compile_graph(backend, &graph_info, prepared);
+    telemetry::record(TelemetryEvent::ConversionFinished {
+        target: backend.target(),
+        elapsed: started.elapsed(),
+        ok: compiled.is_ok(),
+    });
+    compiled
+}
+
+/// Validate and convert `graph` for the selected backend. Only the converter has to be present;
+/// the executor need not be compiled in.
+fn convert_graph(
+    graph: Value,
+    context_options: &ContextOptions,
+    warnings: &mut Vec<WarningPayload>,
+) -> Result<CompiledGraph, RunnerError> {
+    let prepared = prepare_graph(graph, None, context_options, warnings)?;
+    let backend = Backend::from_context(context_options)?;
+    validate_and_compile(backend, &prepared)
+}
+
+/// DOT plan of `graph` as prepared for the selected backend and device.
+fn plan_graph(
+    graph: Value,
+    context_options: &ContextOptions,
+    warnings: &mut Vec<WarningPayload>,
+) -> Result<String, RunnerError> {
+    let original = graph.clone();
+    let prepared = prepare_graph(graph, None, context_options, warnings)?;
+    let (device, _power) = context_options.device()?;
+    let backend = Backend::from_context(context_options)?;
+    let device = backend.placement(device);
+    let flagged = nondeterminism::on_device(nondeterminism::find(&original), device);
+    nondeterminism::check(&flagged, device, false, warnings)?;
+    let rewritten = serde_json::to_value(&prepared.graph)
+        .map_err(|e| RunnerError::BadRequest(format!("cannot serialize prepared graph: {e}")))?;
+    let placement = plan::Placement {
+        backend: backend.target(),
+        device: device.name(),
+    };
+    Ok(plan::to_dot(
+        &original,
+        &rewritten,
+        &placement,
+        &prepared.prepacked,
+        &flagged,
+    ))
+}
+
+/// Shapes inferred by rustnn for every named operand.
+fn operand_shapes(graph_info: &GraphInfo) -> HashMap<String, Vec<usize>> {
+    (0u32..)
+        .map_while(|op_id| graph_info.operand(op_id))
+        .filter_map(|op| {
+            let name = op.name.clone()?;
+            let shape = op.descriptor.shape.iter().map(|&d| d as usize).collect();
+            Some((name, shape))
+        })
+        .collect()
+}
+
+/// `graph` as prepared and validated, rendered in `format`.
+fn dump_graph(
+    graph: Value,
+    context_options: &ContextOptions,
+    format: graph_dump::DumpFormat,
+    warnings: &mut Vec<WarningPayload>,
+) -> Result<String, RunnerError> {
+    let prepared = prepare_graph(graph, None, context_options, warnings)?;
+    let graph_info = validated_graph_info(&prepared)?;
+    let shapes = operand_shapes(&graph_info);
+    let rewritten = serde_json::to_value(&prepared.graph)
+        .map_err(|e| RunnerError::BadRequest(format!("cannot serialize prepared graph: {e}")))?;
+    let types = output_dtype::operand_types(&rewritten);
+    Ok(graph_dump::render(&rewritten, &types, &shapes, format))
+}
+
+/// Diff of `graph` against `other`, or against its prepared form.
+fn diff_graphs(
+    graph: Value,
+    other: Option<Value>,
+    context_options: &ContextOptions,
+    tolerance: f64,
+    warnings: &mut Vec<WarningPayload>,
+) -> Result<graph_diff::GraphDiff, RunnerError> {
+    let other = match other {
+        Some(other) => other,
+        None => {
+            let prepared = prepare_graph(graph.clone(), None, context_options, warnings)?;
+            serde_json::to_value(&prepared.graph).map_err(|e| {
+                RunnerError::BadRequest(format!("cannot serialize prepared graph: {e}"))
+            })?
+        }
+    };
+    Ok(graph_diff::diff(&graph, &other, tolerance))
+}
+
+/// Output descriptors of a validated graph: shapes from rustnn's type inference, data types as
+/// submitted (before any lowering).
+fn validate_graph(
+    graph: Value,
+    context_options: &ContextOptions,
+    warnings: &mut Vec<WarningPayload>,
+) -> Result<BTreeMap<String, TensorDescriptorOut>, RunnerError> {
+    let prepared = prepare_graph(graph, None, context_options, warnings)?;
+    let graph_info = validated_graph_info(&prepared)?;
+    let shapes = operand_shapes(&graph_info);
+    let kept_name = |name: &str| -> String {
+        prepared
+            .output_aliases
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| name.to_string())
+    };
+    let output_names = prepared
+        .outputs
+        .iter()
+        .chain(prepared.output_aliases.keys())
+        .cloned()
+        .collect::<BTreeSet<_>>();
+
+    output_names
+        .into_iter()
+        .map(|name| {
+            let kept = kept_name(&name);
+            let shape = shapes.get(kept.as_str()).cloned().ok_or_else(|| {
+                RunnerError::GraphValidation(format!("output {name} is missing from the graph"))
+            })?;
+            let data_type = prepared
+                .output_types
+                .get(&name)
+                .cloned()
+                .unwrap_or_else(|| "float32".to_string());
+            Ok((name, TensorDescriptorOut { data_type, shape }))
+        })
+        .collect()
+}
+
+/// A graph prepared and compiled for one backend, ready to run on any number of input sets.
+struct BuiltGraph {
+    prepared: PreparedGraph,
+    compiled: Arc<CompiledGraph>,
+    context_options: ContextOptions,
+}
+
+fn execute_graph(
+    graph: Value,
+    mut inputs: BTreeMap<String, InputTensor>,
+    expected_outputs: BTreeMap<String, ExpectedOutput>,
+    context_options: ContextOptions,
+    session: &str,
+    warnings: &mut Vec<WarningPayload>,
+) -> Result<BTreeMap<String, OutputTensor>, RunnerError> {
+    let built = build_graph(graph, Some(&mut inputs), context_options, session, warnings)?;
+    compute(&built, &inputs, expected_outputs, warnings)
+}
+
+/// Prepare `graph` and compile it for the selected backend, unless the compile cache has it.
+fn build_graph(
+    graph: Value,
+    inputs: Option<&mut BTreeMap<String, InputTensor>>,
+    context_options: ContextOptions,
+    session: &str,
+    warnings: &mut Vec<WarningPayload>,
+) -> Result<BuiltGraph, RunnerError> {
+    let _heartbeat = progress::heartbeat();
+    progress::stage("prepare", 0);
+    op_limits::check_graph(session, &graph)?;
+    let flagged = nondeterminism::find(&graph);
+    let prepared = prepare_graph(graph, inputs, &context_options, warnings)?;
+
+    let (device, _power) = context_options.device()?;
+    let backend = Backend::from_context(&context_options)?;
+    if !backend.devices().contains(&device) {
+        warnings.push(WarningPayload {
+            kind: "DeviceFallback".to_string(),
+            message: format!(
+                "backend '{}' cannot target deviceType '{}'; running on {}",
+                backend.target(),
+                device.name(),
+                backend.devices()[0].name()
+            ),
+        });
+    }
+    let device = backend.placement(device);
+    nondeterminism::check(
+        &nondeterminism::on_device(flagged, device),
+        device,
+        context_options.require_determinism,
+        warnings,
+    )?;
+    let remote_address = context_options.remote_address.as_deref();
+    if remote_address.is_none() && !backend.is_available() {
+        // Validate first so builder error tests still see their TypeError in builds without
+        // this executor, e.g. `validation-only`.
+        validated_graph_info(&prepared)?;
+        return Err(backend.unavailable());
+    }
+    let target = backend.target();
+    let cache_key = prepared
+        .graph_digest
+        .map(|digest| (compile_cache::key(&digest, target, session), digest));
+    let cached =
+        cache_key.and_then(|(key, digest)| compile_cache::get(session, key, &digest, target));
+    let compiled = match cached {
+        Some(compiled) => {
+            telemetry::record(TelemetryEvent::CacheHit { target });
+            compiled
+        }
+        None => {
+            let compiled = validate_and_compile(backend, &prepared)?;
+            match cache_key {
+                Some((key, digest)) => compile_cache::insert(session, key, &digest, compiled),
+                None => Arc::new(compiled),
+            }
+        }
+    };
+    progress::stage("compiled", 100);
+    Ok(BuiltGraph {
+        prepared,
+        compiled,
+        context_options,
+    })
+}
+
+/// Run `built` on `inputs`, keyed by output name with aliased outputs expanded.
+fn runtime_outputs(
+    built: &BuiltGraph,
+    inputs: &BTreeMap<String, InputTensor>,
+) -> Result<HashMap<String, RuntimeOutput>, RunnerError> {
+    let started = Instant::now();
+    let outputs = match built.context_options.remote_address.as_deref() {
+        Some(addr) => remote::run_remote(addr, &built.compiled, inputs),
+        None => run_compiled(&built.compiled, inputs),
+    };
+    telemetry::record(TelemetryEvent::DispatchFinished {
+        target: &built.compiled.target,
+        elapsed: started.elapsed(),
+        ok: outputs.is_ok(),
+    });
+    let outputs = outputs?;
+    let mut by_name: HashMap<String, _> =
+        outputs.into_iter().map(|o| (o.name.clone(), o)).collect();
+    output_alias::expand_outputs(&mut by_name, &built.prepared.output_aliases);
+    for name in &built.prepared.boolean_outputs {
+        if let Some(output) = by_name.get(name) {
+            boolean::check_values(name, &output.data)?;
+        }
+    }
+    Ok(by_name)
+}
+
+/// Run `built` on `inputs` and convert its outputs for the response.
+fn compute(
+    built: &BuiltGraph,
+    inputs: &BTreeMap<String, InputTensor>,
+    expected_outputs: BTreeMap<String, ExpectedOutput>,
+    warnings: &mut Vec<WarningPayload>,
+) -> Result<BTreeMap<String, OutputTensor>, RunnerError> {
+    let BuiltGraph {
+        prepared,
+        compiled,
+        context_options,
+    } = built;
+
+    if std::env::var("RUSTNNPT_DEBUG").as_deref() == Ok("1") {
+        eprintln!("[RUNNER] inputs (BTreeMap iteration order):");
+        for (name, input) in inputs {
+            eprintln!(
+                "  {} shape={:?} data.len()={}",
+                name,
+                input.descriptor.shape,
+                input.data.len()
+            );
+        }
+    }
+
+    let by_name = runtime_outputs(built, inputs)?;
+    // `None` unless an oracle answered; then every output it did not return fails.
+    let mut reference = None;
+    if let Some(command) = &context_options.compare_external {
+        match external::run(command, compiled, inputs) {
+            Ok(outputs) => {
+                let mut by_name = outputs.into_iter().map(|o| (o.name.clone(), o)).collect();
+                output_alias::expand_outputs(&mut by_name, &prepared.output_aliases);
+                reference = Some(by_name);
+            }
+            Err(err) => warnings.push(WarningPayload {
+                kind: "ExternalOracleFailed".to_string(),
+                message: err.to_string(),
+            }),
+        }
+    }
+    let external_verdict =
+        |name: &str, output: &RuntimeOutput, data_type: &str, tolerance: Option<&Tolerance>| {
+            let Some(reference) = &reference else {
+                return Ok(None);
+            };
+            match reference.get(name) {
+                Some(r) => external::compare(tolerance, data_type, output, r).map(Some),
+                None => Ok(Some(Verdict::missing())),
+            }
+        };
+
+    let mut out = BTreeMap::new();
+    if expected_outputs.is_empty() {
+        for (name, output) in by_name {
+            let data_type = prepared
+                .output_types
+                .get(&name)
+                .map_or("float32", String::as_str);
+            let external = external_verdict(&name, &output, data_type, None)?;
+            let spilled = spill_output(
+                &name,
+                &output,
+                data_type,
+                output.data.len(),
+                context_options,
+            )?;
+            let data = if spilled.is_some() {
+                Value::Null
+            } else {
+                Value::Array(cast_output_data(
+                    &output.data,
+                    output.int64_data.as_deref(),
+                    output.uint64_data.as_deref(),
+                    data_type,
+                ))
+            };
+            out.insert(
+                name,
+                OutputTensor {
+                    descriptor: TensorDescriptorOut {
+                        data_type: data_type.to_string(),
+                        shape: output.shape,
+                    },
+                    data,
+                    spilled,
+                    data_encoding: DataEncoding::Json,
+                    verdict: None,
+                    external,
+                    raw_bytes: None,
+                    postprocessed: None,
+                },
+            );
+        }
+    } else {
+        for (name, expected) in &expected_outputs {
+            let output = by_name.get(name).ok_or_else(|| {
+                RunnerError::RuntimeExecution(format!("missing output from runtime: {name}"))
+            })?;
+            if output.shape != expected.descriptor.shape {
+                return Err(RunnerError::ShapeMismatch(format!(
+                    "output {name}: runtime returned shape {:?} but expected {:?}",
+                    output.shape, expected.descriptor.shape
+                )));
+            }
+            let expected_element_count = ElementCount::of(&expected.descriptor.shape)?.get();
+            let actual_len = output.data.len();
+            if actual_len != expected_element_count {
+                return Err(RunnerError::RuntimeExecution(format!(
+                    "output {name}: runtime returned {actual_len} elements but expected {} (shape {:?})",
+                    expected_element_count,
+                    expected.descriptor.shape
+                )));
+            }
+            let returned_len = if expected.summarize {
+                actual_len.min(1)
+            } else {
+                actual_len
+            };
+            if returned_len < actual_len {
+                warnings.push(WarningPayload {
+                    kind: "OutputTruncated".to_string(),
+                    message: format!(
+                        "output {name}: returned {returned_len} of {actual_len} elements (summarize)"
+                    ),
+                });
+            }
+            let verdict = expected
+                .tolerance
+                .as_ref()
+                .map(|tolerance| {
+                    tolerance::check(
+                        tolerance,
+                        &expected.descriptor.data_type,
+                        output,
+                        &expected.data,
+                    )
+                })
+                .transpose()?;
+            let external = external_verdict(
+                name,
+                output,
+                &expected.descriptor.data_type,
+                expected.tolerance.as_ref(),
+            )?;
+            let postprocessed = expected
+                .postprocess
+                .as_ref()
+                .map(|postprocess| postprocess::apply(postprocess, output))
+                .transpose()?;
+            let data_type = expected.descriptor.data_type.as_str();
+            let spilled = spill_output(name, output, data_type, returned_len, context_options)?;
+            let encode =
+                |data_encoding| encode_output_data(output, data_type, returned_len, data_encoding);
+            // A spilled file already holds the storage bytes.
+            let raw_bytes =
+                (expected.raw_bytes && spilled.is_none()).then(|| encode(DataEncoding::Base64));
+            out.insert(
+                name.clone(),
+                OutputTensor {
+                    descriptor: TensorDescriptorOut {
+                        data_type: expected.descriptor.data_type.clone(),
+                        shape: output.shape.clone(),
+                    },
+                    data: match spilled {
+                        Some(_) => Value::Null,
+                        None if expected.bit_exact && expected.data_encoding.is_json() => {
+                            encoding::float_bit_patterns(&output.data[..returned_len], data_type)
+                                .map_or_else(|| encode(DataEncoding::Json), Value::Array)
+                        }
+                        None => encode(expected.data_encoding),
+                    },
+                    data_encoding: match spilled {
+                        Some(_) => DataEncoding::Json,
+                        None => expected.data_encoding,
+                    },
+                    spilled,
+                    verdict,
+                    external,
+                    raw_bytes,
+                    postprocessed,
+                },
+            );
+        }
+    }
+
+    Ok(out)
+}
+
+fn synthesize_op_graph(
+    op: &str,
+    inputs: &BTreeMap<String, InputTensor>,
+    input_order: Option<Vec<String>>,
+    options: serde_json::Map<String, Value>,
+    outputs: Option<Vec<String>>,
+) -> Result<Value, RunnerError> {
+    let input_order = input_order.unwrap_or_else(|| inputs.keys().cloned().collect());
+    for name in &input_order {
+        if !inputs.contains_key(name) {
+            return Err(RunnerError::BadRequest(format!(
+                "input_order references unknown input '{name}'"
+            )));
+        }
+    }
+    let outputs = outputs.unwrap_or_else(|| vec!["output".to_string()]);
+    if outputs.is_empty() {
+        return Err(RunnerError::BadRequest(
+            "run_op requires at least one output name".to_string(),
+        ));
+    }
+
+    let graph_inputs = inputs
+        .iter()
+        .map(|(name, input)| {
+            (
+                name.clone(),
+                serde_json::json!({
+                    "dataType": input.descriptor.data_type,
+                    "shape": input.descriptor.shape,
+                }),
+            )
+        })
+        .collect::<serde_json::Map<_, _>>();
+    let graph_outputs = outputs
+        .iter()
+        .map(|name| (name.clone(), Value::from(name.as_str())))
+        .collect::<serde_json::Map<_, _>>();
+
+    Ok(serde_json::json!({
+        "format": "webnn-graph-json",
+        "version": 2,
+        "name": format!("run_op_{op}"),
+        "quantized": false,
+        "inputs": graph_inputs,
+        "consts": {},
+        "nodes": [{
+            "id": "op_0",
+            "op": op,
+            "inputs": input_order,
+            "options": options,
+            "outputs": outputs,
+        }],
+        "outputs": graph_outputs,
+    }))
+}
+
+fn run_op(
+    mut request: RunOpRequest,
+    session: &str,
+    warnings: &mut Vec<WarningPayload>,
+) -> Result<BTreeMap<String, OutputTensor>, RunnerError> {
+    distribution::generate_inputs(&mut request.inputs)?;
+    let graph = synthesize_op_graph(
+        &request.op,
+        &request.inputs,
+        request.input_order,
+        request.options,
+        request.outputs,
+    )?;
+    execute_graph(
+        graph,
+        request.inputs,
+        request.expected_outputs,
+        request.context_options,
+        session,
+        warnings,
+    )
+}
+
+fn error_kind(err: &RunnerError) -> String {
+    match err {
+        RunnerError::BadRequest(_) => "BadRequestError",
+        RunnerError::GraphValidation(_) => "GraphValidationError",
+        RunnerError::InputValidation(_) => "InputValidationError",
+        RunnerError::InputData(_) => "InputDataError",
+        RunnerError::GraphConversion(_) => "GraphConversionError",
+        RunnerError::ShapeMismatch(_) => "ShapeMismatchError",
+        RunnerError::RuntimeExecution(_) => "RuntimeExecutionError",
+        RunnerError::NotSupported(_) => "NotSupportedError",
+        RunnerError::ContextOptions(_) => "ContextOptionsError",
+        RunnerError::Timeout(_) => "TimeoutError",
+        RunnerError::PayloadLimit(_) => "PayloadLimitError",
+    }
+    .to_string()
+}
+
+/// The error `error_kind` names `kind`, for errors relayed from another runner.
+fn error_from_kind(kind: &str, message: String) -> RunnerError {
+    match kind {
+        "BadRequestError" => RunnerError::BadRequest(message),
+        "GraphValidationError" => RunnerError::GraphValidation(message),
+        "InputValidationError" => RunnerError::InputValidation(message),
+        "InputDataError" => RunnerError::InputData(message),
+        "GraphConversionError" => RunnerError::GraphConversion(message),
+        "ShapeMismatchError" => RunnerError::ShapeMismatch(message),
+        "NotSupportedError" => RunnerError::NotSupported(message),
+        "ContextOptionsError" => RunnerError::ContextOptions(message),
+        "TimeoutError" => RunnerError::Timeout(message),
+        "PayloadLimitError" => RunnerError::PayloadLimit(message),
+        _ => RunnerError::RuntimeExecution(message),
+    }
+}
+
+fn error_response(id: String, err: RunnerError, warnings: Vec<WarningPayload>) -> Response {
+    Response {
+        id,
+        ok: false,
+        error: Some(ErrorPayload {
+            kind: error_kind(&err),
+            message: err.to_string(),
+            dom_exception: dom_exception(&err),
+        }),
+        warnings,
+        ..Default::default()
+    }
+}
+
+fn outputs_response(
+    id: String,
+    result: Result<BTreeMap<String, OutputTensor>, RunnerError>,
+    warnings: Vec<WarningPayload>,
+) -> Response {
+    match result {
+        Ok(outputs) => Response {
+            id,
+            ok: true,
+            outputs: Some(outputs),
+            warnings,
+            ..Default::default()
+        },
+        Err(err) => error_response(id, err, warnings),
+    }
+}
+
+fn handle_batch(id: String, requests: Vec<Value>, session: &str) -> Response {
+    let responses = requests
+        .into_iter()
+        .map(|item| {
+            let item_id = item
+                .get("id")
+                .and_then(Value::as_str)
+                .unwrap_or("unknown")
+                .to_string();
+            match parse_batch_item(item) {
+                Ok(Request::ExecuteBatch { id, .. }) => error_response(
+                    id,
+                    RunnerError::BadRequest("execute_batch cannot be nested".to_string()),
+                    Vec::new(),
+                ),
+                Ok(request) => handle_request(request, session),
+                Err(err) => error_response(item_id, err, Vec::new()),
+            }
+        })
+        .collect();
+    Response {
+        id,
+        ok: true,
+        responses: Some(responses),
+        ..Default::default()
+    }
+}
+
+const DEFAULT_MAX_ABANDONED_WORKERS: usize = 16;
+
+/// Timed-out workers whose execution is still running.
+static ABANDONED_WORKERS: AtomicUsize = AtomicUsize::new(0);
+
+/// Settles a timed worker when its thread ends, panicking or not; set by whichever of the worker
+/// ending and the watchdog giving up comes first.
+struct WorkerSettled(Arc<AtomicBool>);
+
+impl Drop for WorkerSettled {
+    fn drop(&mut self) {
+        if self.0.swap(true, Ordering::SeqCst) {
+            ABANDONED_WORKERS.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Run `work` on its own thread and stop waiting for it after `timeout_ms`.
+///
+/// rustnn executors have no cancellation hook, so a timed-out execution keeps running on its
+/// thread (and keeps whatever it allocated) until the runtime returns; the result is then
+/// dropped. The harness gets its answer on time either way, and later requests are not queued
+/// behind the runaway one. While `RUSTNNPT_MAX_ABANDONED_WORKERS` (default 16) such executions
+/// are still running, further timed requests fail with `PayloadLimitError` instead of spawning.
+fn with_timeout<T: Send + 'static>(
+    timeout_ms: u64,
+    work: impl FnOnce() -> T + Send + 'static,
+) -> Result<T, RunnerError> {
+    let max = payload_limits::limit(
+        "RUSTNNPT_MAX_ABANDONED_WORKERS",
+        DEFAULT_MAX_ABANDONED_WORKERS,
+    );
+    if ABANDONED_WORKERS.load(Ordering::SeqCst) >= max {
+        return Err(RunnerError::PayloadLimit(format!(
+            "{max} timed-out executions are still running"
+        )));
+    }
+    let (tx, rx) = mpsc::channel();
+    let reporter = progress::current();
+    let worker_reporter = reporter.clone();
+    let settled = Arc::new(AtomicBool::new(false));
+    let worker_settled = WorkerSettled(Arc::clone(&settled));
+    std::thread::spawn(move || {
+        let _settled = worker_settled;
+        // The receiver is gone once the watchdog gave up; nothing is left to notify.
+        let _ = tx.send(progress::scoped(worker_reporter, work));
+    });
+    match rx.recv_timeout(Duration::from_millis(timeout_ms)) {
+        Ok(value) => Ok(value),
+        Err(mpsc::RecvTimeoutError::Timeout) => {
+            // Counted before settling, so a worker finishing right now never decrements first.
+            ABANDONED_WORKERS.fetch_add(1, Ordering::SeqCst);
+            if settled.swap(true, Ordering::SeqCst) {
+                ABANDONED_WORKERS.fetch_sub(1, Ordering::SeqCst);
+            }
+            // The worker keeps running, but its progress frames must not outlive the response.
+            if let Some(reporter) = reporter {
+                reporter.cancel();
+            }
+            Err(RunnerError::Timeout(format!(
+                "execution did not finish within {timeout_ms} ms"
+            )))
+        }
+        Err(mpsc::RecvTimeoutError::Disconnected) => Err(RunnerError::RuntimeExecution(
+            "execution thread panicked".to_string(),
+        )),
+    }
+}
+
+type Outputs = Result<BTreeMap<String, OutputTensor>, RunnerError>;
+
+/// Warnings raised by bounded work, shared with the watchdog so a request that times out still
+/// reports the ones raised before it stalled.
+type WarningSink = Arc<Mutex<Vec<WarningPayload>>>;
+
+/// Hand the warnings collected so far in `local` to `sink`.
+fn flush_warnings(sink: &WarningSink, local: &mut Vec<WarningPayload>) {
+    sink.lock().unwrap_or_else(|e| e.into_inner()).append(local);
+}
+
+/// Run `run`, bounded by `timeout_ms` when set, and move its warnings into `warnings`.
+fn run_bounded(
+    timeout_ms: Option<u64>,
+    run: impl FnOnce(&WarningSink) -> Outputs + Send + 'static,
+    warnings: &mut Vec<WarningPayload>,
+) -> Outputs {
+    let sink = WarningSink::default();
+    let result = match timeout_ms {
+        None => run(&sink),
+        Some(ms) => {
+            let worker = Arc::clone(&sink);
+            with_timeout(ms, move || run(&worker)).and_then(|result| result)
+        }
+    };
+    warnings.append(&mut sink.lock().unwrap_or_else(|e| e.into_inner()));
+    result
+}
+
+fn handle_request(request: Request, session: &str) -> Response {
+    let mut warnings = Vec::new();
+    let (id, result, collect_stats) = match request {
+        Request::ExecuteGraph {
+            id,
+            mut graph,
+            mut inputs,
+            input_tensors,
+            output_tensors,
+            freeze_inputs,
+            mut expected_outputs,
+            mut context_options,
+            target,
+            timeout_ms,
+        } => {
+            if target.is_some() {
+                context_options.backend = target;
+            }
+            let collect_stats = context_options.collect_stats;
+            let session = session.to_string();
+            let run = move |sink: &WarningSink| {
+                let mut warnings = Vec::new();
+                let built = context_options
+                    .validate()
+                    .and_then(|()| tensors::bind_inputs(&session, &input_tensors, &mut inputs))
+                    .and_then(|()| {
+                        tensors::expect_outputs(&session, &output_tensors, &mut expected_outputs)
+                    })
+                    .and_then(|()| distribution::generate_inputs(&mut inputs))
+                    .and_then(|()| freeze::freeze_inputs(&mut graph, &mut inputs, &freeze_inputs))
+                    .and_then(|()| {
+                        let inputs = Some(&mut inputs);
+                        build_graph(graph, inputs, context_options, &session, &mut warnings)
+                    });
+                // Build warnings (device fallback, pass results) survive a stalled execution.
+                flush_warnings(sink, &mut warnings);
+                let result = built
+                    .and_then(|built| compute(&built, &inputs, expected_outputs, &mut warnings))
+                    .and_then(|mut outputs| {
+                        tensors::store_outputs(&session, &output_tensors, &mut outputs)
+                            .map(|()| outputs)
+                    });
+                flush_warnings(sink, &mut warnings);
+                result
+            };
+            let result = run_bounded(timeout_ms, run, &mut warnings);
+            (id, result, collect_stats)
+        }
+        Request::BuildGraph {
+            id,
+            graph,
+            mut context_options,
+            target,
+        } => {
+            if target.is_some() {
+                context_options.backend = target;
+            }
+            let built = build_graph(graph, None, context_options, session, &mut warnings)
+                .and_then(|built| graphs::insert(session, built));
+            return match built {
+                Ok(handle) => Response {
+                    id,
+                    ok: true,
+                    graph_handle: Some(handle),
+                    warnings,
+                    ..Default::default()
+                },
+                Err(err) => error_response(id, err, warnings),
+            };
+        }
+        Request::Compute {
+            id,
+            graph,
+            mut inputs,
+            input_tensors,
+            output_tensors,
+            mut expected_outputs,
+            timeout_ms,
+        } => {
+            let stored = match graphs::get(session, &graph) {
+                Ok(stored) => stored,
+                Err(err) => return error_response(id, err, warnings),
+            };
+            let collect_stats = stored.built.context_options.collect_stats;
+            let session = session.to_string();
+            let run = move |sink: &WarningSink| {
+                let mut warnings = Vec::new();
+                let result = tensors::bind_inputs(&session, &input_tensors, &mut inputs)
+                    .and_then(|()| {
+                        tensors::expect_outputs(&session, &output_tensors, &mut expected_outputs)
+                    })
+                    .and_then(|()| distribution::generate_inputs(&mut inputs))
+                    .and_then(|()| stored.prepare_inputs(&mut inputs))
+                    .and_then(|()| compute(&stored.built, &inputs, expected_outputs, &mut warnings))
+                    .and_then(|mut outputs| {
+                        tensors::store_outputs(&session, &output_tensors, &mut outputs)
+                            .map(|()| outputs)
+                    });
+                flush_warnings(sink, &mut warnings);
+                result
+            };
+            let result = run_bounded(timeout_ms, run, &mut warnings);
+            (id, result, collect_stats)
+        }
+        Request::Dispatch {
+            id,
+            graph,
+            inputs,
+            outputs,
+            timeout_ms,
+        } => {
+            let stored = match graphs::get(session, &graph) {
+                Ok(stored) => stored,
+                Err(err) => return error_response(id, err, warnings),
+            };
+            let collect_stats = stored.built.context_options.collect_stats;
+            let session = session.to_string();
+            let run = move |sink: &WarningSink| {
+                let mut warnings = Vec::new();
+                let mut bound = BTreeMap::new();
+                let result = tensors::bind_inputs(&session, &inputs, &mut bound)
+                    .and_then(|()| stored.prepare_inputs(&mut bound))
+                    .and_then(|()| runtime_outputs(&stored.built, &bound))
+                    .and_then(|runtime| {
+                        let types = &stored.built.prepared.output_types;
+                        tensors::write_outputs(&session, &outputs, &runtime, types, &mut warnings)
+                    })
+                    .map(|()| BTreeMap::new());
+                flush_warnings(sink, &mut warnings);
+                result
+            };
+            let result = run_bounded(timeout_ms, run, &mut warnings);
+            (id, result, collect_stats)
+        }
+        Request::DestroyGraph { id, graph } => {
+            let result = graphs::destroy(session, &graph).map(|()| BTreeMap::new());
+            return outputs_response(id, result, warnings);
+        }
+        Request::RunOp(request) => {
+            let id = request.id.clone();
+            let collect_stats = request.context_options.collect_stats;
+            (id, run_op(request, session, &mut warnings), collect_stats)
+        }
+        Request::ExecuteBatch { id, requests } => return handle_batch(id, requests, session),
+        Request::RunCompiled {
+            id,
+            compiled,
+            inputs,
+        } => {
+            return match run_compiled(&compiled, &inputs) {
+                Ok(outputs) => Response {
+                    id,
+                    ok: true,
+                    raw_outputs: Some(outputs),
+                    ..Default::default()
+                },
+                Err(err) => error_response(id, err, Vec::new()),
+            };
+        }
+        Request::ValidateGraph {
+            id,
+            graph,
+            context_options,
+        } => {
+            let table = operands::table(&graph);
+            let descriptors = op_limits::check_graph(session, &graph)
+                .and_then(|()| validate_graph(graph, &context_options, &mut warnings));
+            return match descriptors {
+                Ok(descriptors) => Response {
+                    id,
+                    ok: true,
+                    output_descriptors: Some(descriptors),
+                    operands: Some(table),
+                    warnings,
+                    ..Default::default()
+                },
+                Err(err) => error_response(id, err, warnings),
+            };
+        }
+        Request::ConvertGraph {
+            id,
+            graph,
+            mut context_options,
+            target,
+        } => {
+            if target.is_some() {
+                context_options.backend = target;
+            }
+            let compiled = op_limits::check_graph(session, &graph)
+                .and_then(|()| convert_graph(graph, &context_options, &mut warnings));
+            return match compiled {
+                Ok(compiled) => Response {
+                    id,
+                    ok: true,
+                    compiled: Some(compiled),
+                    warnings,
+                    ..Default::default()
+                },
+                Err(err) => error_response(id, err, warnings),
+            };
+        }
+        Request::PlanGraph {
+            id,
+            graph,
+            mut context_options,
+            target,
+        } => {
+            if target.is_some() {
+                context_options.backend = target;
+            }
+            let table = operands::table(&graph);
+            let plan = op_limits::check_graph(session, &graph)
+                .and_then(|()| plan_graph(graph, &context_options, &mut warnings));
+            return match plan {
+                Ok(plan) => Response {
+                    id,
+                    ok: true,
+                    plan: Some(plan),
+                    operands: Some(table),
+                    warnings,
+                    ..Default::default()
+                },
+                Err(err) => error_response(id, err, warnings),
+            };
+        }
+        Request::DumpGraph {
+            id,
+            graph,
+            context_options,
+            format,
+        } => {
+            let dump = op_limits::check_graph(session, &graph)
+                .and_then(|()| dump_graph(graph, &context_options, format, &mut warnings));
+            return match dump {
+                Ok(dump) => Response {
+                    id,
+                    ok: true,
+                    dump: Some(dump),
+                    warnings,
+                    ..Default::default()
+                },
+                Err(err) => error_response(id, err, warnings),
+            };
+        }
+        Request::DiffGraphs {
+            id,
+            graph,
+            other,
+            context_options,
+            tolerance,
+        } => {
+            let diff = std::iter::once(&graph)
+                .chain(&other)
+                .try_for_each(|graph| op_limits::check_graph(session, graph))
+                .and_then(|()| {
+                    diff_graphs(graph, other, &context_options, tolerance, &mut warnings)
+                });
+            return match diff {
+                Ok(diff) => Response {
+                    id,
+                    ok: true,
+                    diff: Some(diff),
+                    warnings,
+                    ..Default::default()
+                },
+                Err(err) => error_response(id, err, warnings),
+            };
+        }
+        Request::DeviationStats {
+            id,
+            graph,
+            inputs,
+            samples,
+            reference,
+            candidate,
+        } => {
+            let stats = deviation::deviation_stats(
+                &graph,
+                &inputs,
+                samples,
+                [&reference, &candidate],
+                session,
+                &mut warnings,
+            );
+            return match stats {
+                Ok(stats) => Response {
+                    id,
+                    ok: true,
+                    deviation: Some(stats),
+                    warnings,
+                    ..Default::default()
+                },
+                Err(err) => error_response(id, err, warnings),
+            };
+        }
+        Request::Benchmark {
+            id,
+            graph,
+            inputs,
+            mut context_options,
+            target,
+            warmups,
+            iterations,
+        } => {
+            if target.is_some() {
+                context_options.backend = target;
+            }
+            let report = benchmark::benchmark(
+                session,
+                graph,
+                inputs,
+                context_options,
+                warmups,
+                iterations,
+                &mut warnings,
+            );
+            return match report {
+                Ok(report) => Response {
+                    id,
+                    ok: true,
+                    benchmark: Some(report),
+                    warnings,
+                    ..Default::default()
+                },
+                Err(err) => error_response(id, err, warnings),
+            };
+        }
+        Request::QuantizeGraph {
+            id,
+            graph,
+            calibration,
+            context_options,
+        } => {
+            let quantized = quantize::quantize_graph(
+                graph,
+                calibration,
+                &context_options,
+                session,
+                &mut warnings,
+            );
+            return match quantized {
+                Ok(quantized) => Response {
+                    id,
+                    ok: true,
+                    quantized: Some(quantized),
+                    warnings,
+                    ..Default::default()
+                },
+                Err(err) => error_response(id, err, warnings),
+            };
+        }
+        Request::CreateTensor {
+            id,
+            handle,
+            descriptor,
+        } => {
+            let result = tensors::create(session, handle, descriptor).map(|()| BTreeMap::new());
+            return outputs_response(id, result, warnings);
+        }
+        Request::WriteTensor {
+            id,
+            handle,
+            data,
+            data_encoding,
+        } => {
+            let result =
+                tensors::write(session, &handle, data, data_encoding).map(|()| BTreeMap::new());
+            return outputs_response(id, result, warnings);
+        }
+        Request::ReadTensor {
+            id,
+            handle,
+            data_encoding,
+            raw_bytes,
+        } => {
+            let result = tensors::read(session, &handle, data_encoding, raw_bytes);
+            return outputs_response(id, result, warnings);
+        }
+        Request::DestroyTensor { id, handle } => {
+            let result = tensors::destroy(session, &handle).map(|()| BTreeMap::new());
+            return outputs_response(id, result, warnings);
+        }
+        Request::ListBackends { id } => {
+            return Response {
+                id,
+                ok: true,
+                backends: Some(Backend::ALL.map(Backend::info).into()),
+                ..Default::default()
+            };
+        }
+        Request::GetCapabilities { id } => {
+            return Response {
+                id,
+                ok: true,
+                backends: Some(Backend::ALL.map(Backend::info).into()),
+                op_support_limits: Some(op_limits::op_support_limits(session)),
+                ..Default::default()
+            };
+        }
+        Request::SetCapabilities { id, overrides } => {
+            return match op_limits::set_overrides(session, overrides) {
+                Ok(()) => Response {
+                    id,
+                    ok: true,
+                    op_support_limits: Some(op_limits::op_support_limits(session)),
+                    ..Default::default()
+                },
+                Err(err) => error_response(id, err, warnings),
+            };
+        }
+        Request::Handshake { id, .. } => {
+            // serve_lines handles connection-level handshakes; anything reaching here is nested.
+            return error_response(
+                id,
+                RunnerError::BadRequest("handshake cannot be batched".to_string()),
+                Vec::new(),
+            );
+        }
+    };
+
+    let mut response = outputs_response(id, result, warnings);
+    if collect_stats {
+        response.stats = Some(ExecutionStats {
+            scheduling: stats::scheduling_stats(),
+        });
+    }
+    response
+}
+
+/// Errors are classified where they are raised (validator, converter, executor), never by
+/// inspecting rustnn's message text.
+fn dom_exception(err: &RunnerError) -> Option<String> {
+    match err {
+        RunnerError::BadRequest(_) | RunnerError::PayloadLimit(_) => None,
+        RunnerError::GraphValidation(_)
+        | RunnerError::InputValidation(_)
+        | RunnerError::ContextOptions(_) => Some("TypeError"),
+        RunnerError::InputData(_) => Some("DataError"),
+        RunnerError::GraphConversion(_) | RunnerError::NotSupported(_) => Some("NotSupportedError"),
+        RunnerError::ShapeMismatch(_) | RunnerError::RuntimeExecution(_) => Some("OperationError"),
+        RunnerError::Timeout(_) => Some("TimeoutError"),
+    }
+    .map(str::to_string)
+}
+
+impl Request {
+    /// Tensor elements carried inline by this request, each parsed into a JSON value.
+    fn tensor_elements(&self) -> usize {
+        let count = |inputs: &BTreeMap<String, InputTensor>| {
+            inputs.values().map(|input| input.data.len()).sum::<usize>()
+        };
+        match self {
+            Request::ExecuteGraph { inputs, .. }
+            | Request::Compute { inputs, .. }
+            | Request::RunCompiled { inputs, .. }
+            | Request::DeviationStats { inputs, .. }
+            | Request::Benchmark { inputs, .. } => count(inputs),
+            Request::RunOp(request) => count(&request.inputs),
+            Request::QuantizeGraph { calibration, .. } => calibration.iter().map(count).sum(),
+            Request::WriteTensor {
+                data: Value::Array(data),
+                ..
+            } => data.len(),
+            _ => 0,
+        }
+    }
+
+    /// Graphs carried by this request; batched requests are parsed, and their graphs checked,
+    /// item by item.
+    fn graphs_mut(&mut self) -> Vec<&mut Value> {
+        match self {
+            Request::ExecuteGraph { graph, .. }
+            | Request::BuildGraph { graph, .. }
+            | Request::ValidateGraph { graph, .. }
+            | Request::ConvertGraph { graph, .. }
+            | Request::PlanGraph { graph, .. }
+            | Request::DumpGraph { graph, .. }
+            | Request::QuantizeGraph { graph, .. }
+            | Request::DeviationStats { graph, .. }
+            | Request::Benchmark { graph, .. } => vec![graph],
+            Request::DiffGraphs { graph, other, .. } => {
+                std::iter::once(graph).chain(other.as_mut()).collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Parse one protocol line within the payload limits and inline each graph's out-of-band
+/// `constants`. The inner result is the JSON parse, so callers keep reporting malformed
+/// requests as they always have.
+fn parse_request(raw: &str) -> Result<Result<Request, serde_json::Error>, RunnerError> {
+    payload_limits::check_depth(raw)?;
+    let mut parsed = serde_json::from_str::<Request>(raw);
+    if let Ok(request) = &mut parsed {
+        check_graphs(request)?;
+    }
+    Ok(parsed)
+}
+
+fn check_graphs(request: &mut Request) -> Result<(), RunnerError> {
+    payload_limits::check_elements(request.tensor_elements())?;
+    for graph in request.graphs_mut() {
+        payload_limits::check_graph(graph)?;
+        constants::inline(graph)?;
+    }
+    Ok(())
+}
+
+/// Parse one `execute_batch` item as [`parse_request`] parses a line; the line it came in was
+/// already depth checked.
+fn parse_batch_item(item: Value) -> Result<Request, RunnerError> {
+    let mut request = serde_json::from_value::<Request>(item)
+        .map_err(|e| RunnerError::BadRequest(format!("invalid json request: {e}")))?;
+    check_graphs(&mut request)?;
+    Ok(request)
+}
+
+/// Parse one protocol line and run every request check and tensor normalization step, without
+/// converting or executing anything.
+///
+/// This is the surface the protocol fuzz target drives.
+pub fn check_request(raw: &str) -> Result<(), String> {
+    let request = parse_request(raw)
+        .map_err(|e| e.to_string())?
+        .map_err(|e| format!("invalid json request: {e}"))?;
+    check_parsed_request(request)
+}
+
+fn check_parsed_request(request: Request) -> Result<(), String> {
+    let mut warnings = Vec::new();
+    let (graph, mut inputs, context_options) = match request {
+        Request::ExecuteGraph {
+            mut graph,
+            mut inputs,
+            input_tensors,
+            freeze_inputs,
+            context_options,
+            ..
+        } => {
+            if !input_tensors.is_empty() {
+                // Tensor contents only exist in a live session, so check the graph alone.
+                return prepare_graph(graph, None, &context_options, &mut warnings)
+                    .map(drop)
+                    .map_err(|e| e.to_string());
+            }
+            distribution::generate_inputs(&mut inputs).map_err(|e| e.to_string())?;
+            freeze::freeze_inputs(&mut graph, &mut inputs, &freeze_inputs)
+                .map_err(|e| e.to_string())?;
+            (graph, inputs, context_options)
+        }
+        Request::RunOp(mut request) => {
+            distribution::generate_inputs(&mut request.inputs).map_err(|e| e.to_string())?;
+            let graph = synthesize_op_graph(
+                &request.op,
+                &request.inputs,
+                request.input_order,
+                request.options,
+                request.outputs,
+            )
+            .map_err(|e| e.to_string())?;
+            (graph, request.inputs, request.context_options)
+        }
+        Request::Benchmark {
+            graph,
+            mut inputs,
+            context_options,
+            ..
+        } => {
+            distribution::generate_inputs(&mut inputs).map_err(|e| e.to_string())?;
+            (graph, inputs, context_options)
+        }
+        Request::ExecuteBatch { requests, .. } => {
+            // Nested batches are rejected at execution time; checking them is harmless.
+            return requests.into_iter().try_for_each(|item| {
+                check_parsed_request(parse_batch_item(item).map_err(|e| e.to_string())?)
+            });
+        }
+        Request::RunCompiled {
+            compiled, inputs, ..
+        } => {
+            compiled.check_fingerprint()?;
+            return inputs.values().try_for_each(|input| {
+                to_tensor_data(&input.descriptor, &input.data)
+                    .map(drop)
+                    .map_err(|e| e.to_string())
+            });
+        }
+        Request::ValidateGraph {
+            graph,
+            context_options,
+            ..
+        }
+        | Request::ConvertGraph {
+            graph,
+            context_options,
+            ..
+        }
+        | Request::PlanGraph {
+            graph,
+            context_options,
+            ..
+        }
+        | Request::DumpGraph {
+            graph,
+            context_options,
+            ..
+        }
+        | Request::DiffGraphs {
+            graph,
+            other: None,
+            context_options,
+            ..
+        } => {
+            return prepare_graph(graph, None, &context_options, &mut warnings)
+                .map(drop)
+                .map_err(|e| e.to_string());
+        }
+        Request::QuantizeGraph {
+            graph,
+            mut calibration,
+            context_options,
+            ..
+        } => {
+            prepare_graph(graph, None, &context_options, &mut warnings)
+                .map_err(|e| e.to_string())?;
+            for inputs in &mut calibration {
+                distribution::generate_inputs(inputs).map_err(|e| e.to_string())?;
+                for input in inputs.values() {
+                    to_tensor_data(&input.descriptor, &input.data).map_err(|e| e.to_string())?;
+                }
+            }
+            return Ok(());
+        }
+        Request::DeviationStats {
+            graph,
+            inputs,
+            reference,
+            candidate,
+            ..
+        } => {
+            for side in [reference, candidate] {
+                side.validate().map_err(|e| e.to_string())?;
+                let graph = side.graph(&graph).map_err(|e| e.to_string())?;
+                prepare_graph(graph, None, &side.options(), &mut warnings)
+                    .map_err(|e| e.to_string())?;
+            }
+            let inputs = deviation::sample_inputs(&inputs, 0).map_err(|e| e.to_string())?;
+            for input in inputs.values() {
+                to_tensor_data(&input.descriptor, &input.data).map_err(|e| e.to_string())?;
+            }
+            return Ok(());
+        }
+        Request::BuildGraph {
+            graph,
+            context_options,
+            ..
+        } => {
+            return prepare_graph(graph, None, &context_options, &mut warnings)
+                .map(drop)
+                .map_err(|e| e.to_string());
+        }
+        Request::Compute { mut inputs, .. } => {
+            // The graph only exists in a live session, so check the inputs alone.
+            distribution::generate_inputs(&mut inputs).map_err(|e| e.to_string())?;
+            return inputs.values().try_for_each(|input| {
+                to_tensor_data(&input.descriptor, &input.data)
+                    .map(drop)
+                    .map_err(|e| e.to_string())
+            });
+        }
+        Request::ListBackends { .. }
+        | Request::GetCapabilities { .. }
+        | Request::SetCapabilities { .. }
+        | Request::Handshake { .. }
+        | Request::DestroyGraph { .. }
+        | Request::Dispatch { .. }
+        | Request::DiffGraphs { .. }
+        | Request::CreateTensor { .. }
+        | Request::WriteTensor { .. }
+        | Request::ReadTensor { .. }
+        | Request::DestroyTensor { .. } => return Ok(()),
+    };
+    prepare_graph(graph, Some(&mut inputs), &context_options, &mut warnings)
+        .map_err(|e| e.to_string())?;
+    for input in inputs.values() {
+        to_tensor_data(&input.descriptor, &input.data).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Human-readable listing of the compiled-in backends, one per line.
+pub fn list_backends() -> String {
+    Backend::ALL
+        .map(|backend| {
+            let info = backend.info();
+            let status = if info.available {
+                format!("available ({})", info.features.join(", "))
+            } else {
+                "converter only".to_string()
+            };
+            format!(
+                "{:<8} {:<34} devices: {:<13} {}",
+                info.name,
+                status,
+                info.devices.join(","),
+                info.fingerprint
+            )
+        })
+        .join("\n")
+}
+
+/// Write float outputs with f32-shortest digits so responses diff cleanly across runs.
+pub fn enable_canonical_output() {
+    canonical::enable();
+}
+
+/// Serve the line-delimited JSON protocol on stdin/stdout until stdin closes.
+pub fn run_stdio() {
+    serve_lines(io::stdin().lock(), io::stdout());
+}
+
+/// Serve the line-delimited JSON protocol on every connection accepted on `addr`.
+pub fn run_tcp(addr: &str) -> io::Result<()> {
+    remote::serve(addr)
+}
+
+/// Replay the `*.jsonl` request files in `corpus` for `duration`, failing when resident memory
+/// or open handles grow past the limits, or the compile cache grows at all, after the first pass.
+pub fn run_soak(
+    corpus: &std::path::Path,
+    duration: Duration,
+    max_rss_growth: u64,
+    max_handle_growth: usize,
+) -> Result<(), String> {
+    soak::run(corpus, duration, max_rss_growth, max_handle_growth)
+}
+
+/// Anonymize the `execute_graph` request in `path` for a public bug report; with `verify`, fail
+/// unless the anonymized request still fails with the original error kind.
+pub fn run_anonymize(
+    path: &std::path::Path,
+    out: Option<&std::path::Path>,
+    seed: u64,
+    verify: bool,
+) -> Result<(), String> {
+    anonymize::run(path, out, seed, verify)
+}
+
+/// Execute the graph (or `execute_graph` request) in `graph` once with the JSON, `.npz` or
+/// `.npy` `inputs`, writing the outputs to `out` or stdout; with `benchmark` (iterations and
+/// optional warmups), write a benchmark report instead.
+pub fn run_once(
+    graph: &std::path::Path,
+    inputs: Option<&std::path::Path>,
+    out: Option<&std::path::Path>,
+    backend: Option<&str>,
+    device: Option<&str>,
+    benchmark: Option<(usize, Option<usize>)>,
+) -> Result<(), String> {
+    one_shot::run(graph, inputs, out, backend, device, benchmark)
+}
+
+/// Send telemetry events to `sink` instead of the default (`RUSTNNPT_TELEMETRY_FILE`, if set).
+pub fn set_telemetry_sink(sink: Arc<dyn TelemetrySink>) {
+    telemetry::set_sink(sink);
+}
+
+/// Worker threads per connection; 1 serves requests strictly in order.
+static JOBS: AtomicUsize = AtomicUsize::new(1);
+
+/// Dispatch requests to `jobs` worker threads. Responses are written as they complete, so with
+/// more than one job they may arrive out of request order; match them by `id`.
+pub fn set_jobs(jobs: usize) {
+    JOBS.store(jobs.max(1), Ordering::Relaxed);
+}
+
+fn write_line(writer: &Mutex<impl Write>, line: &str) {
+    let mut writer = writer.lock().unwrap_or_else(|e| e.into_inner());
+    let _ = writeln!(writer, "{line}");
+    let _ = writer.flush();
+}
+
+fn write_response(writer: &Mutex<impl Write>, response: &Response) {
+    match serde_json::to_string(response) {
+        Ok(json) => write_line(writer, &json),
+        Err(err) => write_line(
+            writer,
+            &format!(
+                "{{\"id\":\"unknown\",\"ok\":false,\"error\":{{\"kind\":\"BadRequestError\",\"message\":\"response encode failed: {}\"}}}}",
+                err
+            ),
+        ),
+    }
+}
+
+/// The `id` of the request in `raw`, read without deserializing the rest of it.
+fn request_id(raw: &str) -> Option<String> {
+    #[derive(Deserialize)]
+    struct RequestId {
+        #[serde(default)]
+        id: String,
+    }
+    serde_json::from_str::<RequestId>(raw).ok().map(|r| r.id)
+}
+
+/// The reporter for the request in `raw` when progress frames are on for the connection.
+fn progress_reporter(
+    raw: &str,
+    interval: Option<Duration>,
+    sink: &mpsc::Sender<Option<String>>,
+) -> Option<Arc<progress::Reporter>> {
+    let interval = interval?;
+    let id = request_id(raw)?;
+    Some(progress::Reporter::new(id, interval, sink.clone()))
+}
+
+/// Connections currently in the shared `""` session.
+static ANONYMOUS_CONNECTIONS: AtomicUsize = AtomicUsize::new(0);
+
+fn join_session(session: &str) {
+    if session.is_empty() {
+        ANONYMOUS_CONNECTIONS.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Drop the per-session state of `session` once no connection uses it any more: at once for a
+/// handshake session, with the last connection out for the shared `""` one.
+fn leave_session(session: &str) {
+    if session.is_empty() && ANONYMOUS_CONNECTIONS.fetch_sub(1, Ordering::Relaxed) > 1 {
+        return;
+    }
+    compile_cache::end_session(session);
+    tensors::end_session(session);
+    graphs::end_session(session);
+    op_limits::end_session(session);
+}
+
+/// Whether a read failed by timing out, as an idle `--listen` connection's read does.
+fn timed_out(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+    )
+}
+
+fn serve_lines(mut reader: impl BufRead, writer: impl Write + Send) {
+    let writer = Mutex::new(writer);
+    let jobs = JOBS.load(Ordering::Relaxed);
+    // Cache partition for this connection, set by `handshake`; `""` is the shared default.
+    let mut session = String::new();
+    join_session(&session);
+    let mut progress_interval = None;
+    // Shared with the scoped workers, so they must outlive the scope.
+    let (queue, pending) = mpsc::channel::<(Request, String, String, Option<_>)>();
+    let pending = Mutex::new(pending);
+    // Requests queued or running on workers; a handshake waits for them to finish.
+    let in_flight = (Mutex::new(0usize), Condvar::new());
+
+    std::thread::scope(|scope| {
+        let (progress_sink, progress_lines) = mpsc::channel::<Option<String>>();
+        let progress_writer = &writer;
+        scope.spawn(move || {
+            while let Ok(Some(line)) = progress_lines.recv() {
+                write_line(progress_writer, &line);
+            }
+        });
+        let mut workers = Vec::new();
+        if jobs > 1 {
+            for _ in 0..jobs {
+                workers.push(scope.spawn(|| {
+                    loop {
+                        let next = pending.lock().unwrap_or_else(|e| e.into_inner()).recv();
+                        let Ok((request, session, raw, reporter)) = next else {
+                            break;
+                        };
+                        let response =
+                            progress::scoped(reporter, || handle_request(request, &session));
+                        failure_dump::on_response(&raw, &response);
+                        write_response(&writer, &response);
+                        let (count, idle) = &in_flight;
+                        *count.lock().unwrap_or_else(|e| e.into_inner()) -= 1;
+                        idle.notify_all();
+                    }
+                }));
+            }
+        }
+
+        loop {
+            let raw = match payload_limits::read_line(&mut reader) {
+                Ok(None) => break,
+                Ok(Some(Ok(l))) => l,
+                Ok(Some(Err(err))) => {
+                    let response = error_response("unknown".to_string(), err, Vec::new());
+                    write_response(&writer, &response);
+                    continue;
+                }
+                // A `--listen` connection idle past its read timeout is closed.
+                Err(e) if timed_out(&e) => break,
+                Err(e) => {
+                    write_line(
+                        &writer,
+                        &format!(
+                            "{{\"id\":\"unknown\",\"ok\":false,\"error\":{{\"kind\":\"BadRequestError\",\"message\":\"{}\"}}}}",
+                            e
+                        ),
+                    );
+                    continue;
+                }
+            };
+
+            if raw.trim().is_empty() {
+                continue;
+            }
+
+            let parsed = match parse_request(&raw) {
+                Ok(parsed) => parsed,
+                Err(err) => {
+                    let id = request_id(&raw).unwrap_or_else(|| "unknown".to_string());
+                    write_response(&writer, &error_response(id, err, Vec::new()));
+                    continue;
+                }
+            };
+            let response = match parsed {
+                Ok(Request::Handshake {
+                    id,
+                    session_id,
+                    progress_interval_ms,
+                }) => {
+                    if progress_interval_ms == Some(0) {
+                        let err = RunnerError::BadRequest(
+                            "progress_interval_ms must be at least 1".to_string(),
+                        );
+                        write_response(&writer, &error_response(id, err, Vec::new()));
+                        continue;
+                    }
+                    // Queued requests still use the old session; freeing it under them would let
+                    // them store state into a session nobody frees again.
+                    let (count, idle) = &in_flight;
+                    let count = count.lock().unwrap_or_else(|e| e.into_inner());
+                    drop(idle.wait_while(count, |n| *n > 0));
+                    leave_session(&session);
+                    session = session_id.unwrap_or_default();
+                    join_session(&session);
+                    progress_interval = progress_interval_ms.map(Duration::from_millis);
+                    Response {
+                        id,
+                        ok: true,
+                        capabilities: Some(Capabilities {
+                            lossless_int64: true,
+                        }),
+                        ..Default::default()
+                    }
+                }
+                Ok(request) if jobs > 1 => {
+                    let reporter = progress_reporter(&raw, progress_interval, &progress_sink);
+                    *in_flight.0.lock().unwrap_or_else(|e| e.into_inner()) += 1;
+                    // Workers only stop once `queue` is dropped, so sending cannot fail here.
+                    let _ = queue.send((request, session.clone(), raw, reporter));
+                    continue;
+                }
+                Ok(request) => {
+                    let reporter = progress_reporter(&raw, progress_interval, &progress_sink);
+                    let response = progress::scoped(reporter, || handle_request(request, &session));
+                    failure_dump::on_response(&raw, &response);
+                    response
+                }
+                Err(err) => Response {
+                    id: "unknown".to_string(),
+                    ok: false,
+                    error: Some(ErrorPayload {
+                        kind: "BadRequestError".to_string(),
+                        message: format!("invalid json request: {err}"),
+                        dom_exception: None,
+                    }),
+                    ..Default::default()
+                },
+            };
+            write_response(&writer, &response);
+        }
+        // Closing the queue lets the workers drain it and exit.
+        drop(queue);
+        for worker in workers {
+            let _ = worker.join();
+        }
+        // Builds outliving a `timeoutMs` watchdog may still hold senders; stop explicitly.
+        let _ = progress_sink.send(None);
+    });
+
+    leave_session(&session);
+}
+
+
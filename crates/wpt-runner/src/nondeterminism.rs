@@ -0,0 +1,136 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Ops whose results are not reproducible run to run on the selected device.
+//!
+//! GPU and NPU executors split reductions across parallel workgroups and combine the partial
+//! results in whatever order they finish, and implement scatters with atomics, so duplicate
+//! indices resolve in arbitrary order. Tests of those nodes flake at tight tolerances, which is
+//! hard to pin on a node from the outputs alone. Nodes are matched by op on the submitted graph;
+//! `build_graph` reports them in a `NondeterministicOps` warning, or rejects the graph under
+//! `requireDeterminism`, and `plan_graph` highlights them. The plan is drawn after the runner's
+//! rewrites, which remove and insert nodes, so a node without an `id` is found there by its
+//! outputs rather than by its `#index` in the submitted graph.
+
+use serde_json::Value;
+
+use crate::graph_json::string_list;
+use crate::{DeviceType, RunnerError, WarningPayload};
+
+const PARALLEL_REDUCTION: &str = "parallel reduction; partial sums combine in varying order";
+const ATOMIC_SCATTER: &str = "atomics-based scatter; duplicate indices resolve in varying order";
+
+fn reason(op: &str) -> Option<&'static str> {
+    match op {
+        "reduceL1" | "reduceL2" | "reduceLogSum" | "reduceLogSumExp" | "reduceMean"
+        | "reduceProduct" | "reduceSum" | "reduceSumSquare" => Some(PARALLEL_REDUCTION),
+        "scatterElements" | "scatterND" => Some(ATOMIC_SCATTER),
+        _ => None,
+    }
+}
+
+/// A node that may compute differently on every run.
+pub struct Flagged {
+    /// The node's `id`, or `#index` in the submitted graph.
+    pub node: String,
+    pub op: String,
+    pub reason: &'static str,
+    has_id: bool,
+    outputs: Vec<String>,
+}
+
+impl Flagged {
+    /// Whether `node`, labeled `node_id` in a rewritten graph, is this flagged node.
+    pub fn is(&self, node: &Value, node_id: &str) -> bool {
+        if self.has_id {
+            return self.node == node_id;
+        }
+        let outputs = string_list(node.get("outputs"));
+        !outputs.is_empty() && outputs == self.outputs
+    }
+}
+
+/// Candidate nodes of `graph`, before the device is known; CPU executors run them
+/// deterministically, so [`on_device`] drops them there.
+pub fn find(graph: &Value) -> Vec<Flagged> {
+    graph
+        .get("nodes")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .enumerate()
+        .filter_map(|(index, node)| {
+            let op = node.get("op").and_then(Value::as_str)?;
+            let reason = reason(op)?;
+            let id = node.get("id").and_then(Value::as_str);
+            Some(Flagged {
+                node: id.map_or_else(|| format!("#{index}"), str::to_string),
+                op: op.to_string(),
+                reason,
+                has_id: id.is_some(),
+                outputs: string_list(node.get("outputs"))
+                    .into_iter()
+                    .map(str::to_string)
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+pub fn on_device(flagged: Vec<Flagged>, device: DeviceType) -> Vec<Flagged> {
+    if device == DeviceType::Cpu {
+        Vec::new()
+    } else {
+        flagged
+    }
+}
+
+fn describe(flagged: &[Flagged]) -> String {
+    flagged
+        .iter()
+        .map(|f| format!("node '{}' ({}): {}", f.node, f.op, f.reason))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Warn about `flagged` nodes running on `device`, or fail when `require` forbids them.
+pub fn check(
+    flagged: &[Flagged],
+    device: DeviceType,
+    require: bool,
+    warnings: &mut Vec<WarningPayload>,
+) -> Result<(), RunnerError> {
+    if flagged.is_empty() {
+        return Ok(());
+    }
+    let message = format!(
+        "nondeterministic on {}: {}",
+        device.name(),
+        describe(flagged)
+    );
+    if require {
+        return Err(RunnerError::NotSupported(format!(
+            "requireDeterminism: {message}"
+        )));
+    }
+    warnings.push(WarningPayload {
+        kind: "NondeterministicOps".to_string(),
+        message,
+    });
+    Ok(())
+}
@@ -0,0 +1,313 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Minimal `.npz` reader and writer for failure bundles and `wpt-runner run`.
+//!
+//! An `.npz` is an uncompressed zip of `.npy` arrays, which `numpy.load` reads directly.
+//! Only what bundles need is supported: little-endian C-order arrays and archives under 4 GiB.
+//! Archives written by `numpy.savez` are read, including their zip64 entry headers;
+//! `numpy.savez_compressed` archives are not.
+
+use std::collections::BTreeMap;
+
+use crate::{TensorData, tensor_data_to_le_bytes};
+
+fn descr(data: &TensorData) -> &'static str {
+    match data {
+        TensorData::Float32(_) => "<f4",
+        TensorData::Float64(_) => "<f8",
+        TensorData::Float16(_) => "<f2",
+        TensorData::Int8(_) => "|i1",
+        TensorData::Uint8(_) => "|u1",
+        TensorData::Int32(_) => "<i4",
+        TensorData::Uint32(_) => "<u4",
+        TensorData::Int64(_) => "<i8",
+        TensorData::Uint64(_) => "<u8",
+    }
+}
+
+/// WebNN `dataType` stored as `descr`, for the types `npy` writes.
+pub fn data_type(descr: &str) -> Option<&'static str> {
+    Some(match descr {
+        "<f4" => "float32",
+        "<f8" => "float64",
+        "<f2" => "float16",
+        "|i1" => "int8",
+        "|u1" => "uint8",
+        "<i4" => "int32",
+        "<u4" => "uint32",
+        "<i8" => "int64",
+        "<u8" => "uint64",
+        _ => return None,
+    })
+}
+
+/// Type a `data_type` tensor is written to `.npy` as; the inverse of `storage_type`.
+pub fn numpy_type(data_type: &str) -> &str {
+    match data_type {
+        "int4" => "int8",
+        "uint4" => "uint8",
+        "bfloat16" => "float32",
+        other => other,
+    }
+}
+
+/// Type to decode a `descr` array as for a `data_type` tensor, or `None` when the dtype cannot
+/// hold it. NumPy has no 4-bit or bfloat16 dtypes, so `int4`/`uint4` arrive as `int8`/`uint8`
+/// and `bfloat16` as `float32`; WebNN booleans are `uint8`, so NumPy `bool` arrays bind to them.
+pub fn storage_type(descr: &str, data_type: &str) -> Option<&'static str> {
+    match (descr, data_type) {
+        ("|i1", "int4") => Some("int8"),
+        ("|u1", "uint4") | ("|b1", "uint8") => Some("uint8"),
+        ("<f4", "bfloat16") => Some("float32"),
+        _ => self::data_type(descr).filter(|&stored| stored == data_type),
+    }
+}
+
+/// One `.npy` file (format version 1.0).
+pub fn npy(shape: &[usize], data: TensorData) -> Vec<u8> {
+    let dims = match shape {
+        [dim] => format!("{dim},"),
+        dims => dims
+            .iter()
+            .map(usize::to_string)
+            .collect::<Vec<_>>()
+            .join(", "),
+    };
+    let mut header = format!(
+        "{{'descr': '{}', 'fortran_order': False, 'shape': ({dims}), }}",
+        descr(&data)
+    );
+    // Magic, version and length take 10 bytes; the header pads the total to 64 and ends in '\n'.
+    let padded = (10 + header.len() + 1).next_multiple_of(64) - 10;
+    header.push_str(&" ".repeat(padded - header.len() - 1));
+    header.push('\n');
+
+    let mut out = b"\x93NUMPY\x01\x00".to_vec();
+    out.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    out.extend_from_slice(header.as_bytes());
+    out.extend_from_slice(&tensor_data_to_le_bytes(data));
+    out
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &b in bytes {
+        crc ^= u32::from(b);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// Zip `arrays` (name without `.npy` to file contents) as stored entries.
+pub fn archive(arrays: &BTreeMap<String, Vec<u8>>) -> Result<Vec<u8>, String> {
+    let too_large = || "npz archive exceeds 4 GiB".to_string();
+    let mut out = Vec::new();
+    let mut central = Vec::new();
+    for (name, contents) in arrays {
+        let name = format!("{name}.npy");
+        let offset = u32::try_from(out.len()).map_err(|_| too_large())?;
+        let size = u32::try_from(contents.len()).map_err(|_| too_large())?;
+        let name_len = u16::try_from(name.len()).map_err(|_| format!("name too long: {name}"))?;
+        // Version, flags, stored method, DOS time/date (1980-01-01), crc, sizes, name length.
+        let mut fields = Vec::new();
+        fields.extend_from_slice(&20u16.to_le_bytes());
+        fields.extend_from_slice(&0u16.to_le_bytes());
+        fields.extend_from_slice(&0u16.to_le_bytes());
+        fields.extend_from_slice(&0u16.to_le_bytes());
+        fields.extend_from_slice(&0x21u16.to_le_bytes());
+        fields.extend_from_slice(&crc32(contents).to_le_bytes());
+        fields.extend_from_slice(&size.to_le_bytes());
+        fields.extend_from_slice(&size.to_le_bytes());
+        fields.extend_from_slice(&name_len.to_le_bytes());
+        fields.extend_from_slice(&0u16.to_le_bytes());
+
+        out.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+        out.extend_from_slice(&fields);
+        out.extend_from_slice(name.as_bytes());
+        out.extend_from_slice(contents);
+
+        central.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+        central.extend_from_slice(&20u16.to_le_bytes());
+        central.extend_from_slice(&fields);
+        // Comment length, disk number, internal and external attributes, local header offset.
+        central.extend_from_slice(&[0; 10]);
+        central.extend_from_slice(&offset.to_le_bytes());
+        central.extend_from_slice(name.as_bytes());
+    }
+    let central_offset = u32::try_from(out.len()).map_err(|_| too_large())?;
+    let central_size = u32::try_from(central.len()).map_err(|_| too_large())?;
+    let entries = u16::try_from(arrays.len()).map_err(|_| "too many npz entries".to_string())?;
+    out.extend_from_slice(&central);
+    out.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+    out.extend_from_slice(&[0; 4]);
+    out.extend_from_slice(&entries.to_le_bytes());
+    out.extend_from_slice(&entries.to_le_bytes());
+    out.extend_from_slice(&central_size.to_le_bytes());
+    out.extend_from_slice(&central_offset.to_le_bytes());
+    out.extend_from_slice(&0u16.to_le_bytes());
+    Ok(out)
+}
+
+/// One array of an `.npy` file.
+pub struct Array<'a> {
+    pub descr: String,
+    pub shape: Vec<usize>,
+    pub data: &'a [u8],
+}
+
+/// Text of `'key': ...` in an `.npy` header dict, up to the end of the header.
+fn header_field<'a>(header: &'a str, key: &str) -> Option<&'a str> {
+    let start = header.find(&format!("'{key}':"))? + key.len() + 3;
+    Some(header[start..].trim_start())
+}
+
+/// Parse a `.npy` file (format versions 1.0 to 3.0).
+pub fn parse_npy(bytes: &[u8]) -> Result<Array<'_>, String> {
+    let invalid = |what: &str| format!("invalid .npy file: {what}");
+    if bytes.len() < 10 || !bytes.starts_with(b"\x93NUMPY") {
+        return Err(invalid("bad magic"));
+    }
+    let (len, start) = match bytes[6] {
+        1 => (usize::from(u16::from_le_bytes([bytes[8], bytes[9]])), 10),
+        2 | 3 if bytes.len() >= 12 => {
+            let len = u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]);
+            (len as usize, 12)
+        }
+        version => return Err(invalid(&format!("unsupported version {version}"))),
+    };
+    let header = bytes
+        .get(start..start + len)
+        .and_then(|h| std::str::from_utf8(h).ok())
+        .ok_or_else(|| invalid("truncated header"))?;
+    let descr = header_field(header, "descr")
+        .and_then(|rest| rest.strip_prefix('\''))
+        .and_then(|rest| rest.split('\'').next())
+        .ok_or_else(|| invalid("missing descr"))?;
+    if !header_field(header, "fortran_order").is_some_and(|rest| rest.starts_with("False")) {
+        return Err(invalid("only C-order arrays are supported"));
+    }
+    let shape = header_field(header, "shape")
+        .and_then(|rest| rest.strip_prefix('('))
+        .and_then(|rest| rest.split(')').next())
+        .ok_or_else(|| invalid("missing shape"))?;
+    let shape = shape
+        .split(',')
+        .map(str::trim)
+        .filter(|dim| !dim.is_empty())
+        .map(|dim| dim.parse::<usize>().map_err(|_| invalid("bad shape")))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Array {
+        descr: descr.to_string(),
+        shape,
+        data: &bytes[start + len..],
+    })
+}
+
+fn u16_at(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn u64_at(bytes: &[u8], at: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(bytes.get(at..at + 8)?.try_into().ok()?))
+}
+
+/// Sizes and offset of a central directory entry, widened from its zip64 extra field where
+/// the 32-bit fields are saturated.
+fn entry_extent(entry: &[u8], extra: &[u8]) -> Option<(u64, u64)> {
+    let mut size = u64::from(u32_at(entry, 20)?);
+    let uncompressed = u64::from(u32_at(entry, 24)?);
+    let mut offset = u64::from(u32_at(entry, 42)?);
+    let mut at = 0;
+    while at + 4 <= extra.len() {
+        let (id, len) = (u16_at(extra, at)?, usize::from(u16_at(extra, at + 2)?));
+        if id == 0x0001 {
+            // Only the saturated fields are present, in this order.
+            let mut field = at + 4;
+            if uncompressed == u64::from(u32::MAX) {
+                field += 8;
+            }
+            if size == u64::from(u32::MAX) {
+                size = u64_at(extra, field)?;
+                field += 8;
+            }
+            if offset == u64::from(u32::MAX) {
+                offset = u64_at(extra, field)?;
+            }
+        }
+        at += 4 + len;
+    }
+    Some((size, offset))
+}
+
+/// Unzip the `.npy` files of an archive, keyed by name without `.npy`.
+pub fn read_archive(bytes: &[u8]) -> Result<BTreeMap<String, &[u8]>, String> {
+    let invalid = || "invalid npz archive".to_string();
+    let eocd = (0..bytes.len().saturating_sub(21))
+        .rev()
+        .find(|&at| u32_at(bytes, at) == Some(0x0605_4b50))
+        .ok_or_else(invalid)?;
+    let entries = u16_at(bytes, eocd + 10).ok_or_else(invalid)?;
+    let mut at = u32_at(bytes, eocd + 16).ok_or_else(invalid)? as usize;
+    let mut arrays = BTreeMap::new();
+    for _ in 0..entries {
+        let entry = bytes.get(at..at + 46).ok_or_else(invalid)?;
+        if u32_at(entry, 0) != Some(0x0201_4b50) {
+            return Err(invalid());
+        }
+        let method = u16_at(entry, 10).ok_or_else(invalid)?;
+        let name_len = usize::from(u16_at(entry, 28).ok_or_else(invalid)?);
+        let extra_len = usize::from(u16_at(entry, 30).ok_or_else(invalid)?);
+        let comment_len = usize::from(u16_at(entry, 32).ok_or_else(invalid)?);
+        let name = bytes.get(at + 46..at + 46 + name_len).ok_or_else(invalid)?;
+        let name = String::from_utf8_lossy(name).into_owned();
+        let extra = bytes
+            .get(at + 46 + name_len..at + 46 + name_len + extra_len)
+            .ok_or_else(invalid)?;
+        let (size, offset) = entry_extent(entry, extra).ok_or_else(invalid)?;
+        at += 46 + name_len + extra_len + comment_len;
+
+        if method != 0 {
+            return Err(format!(
+                "npz entry {name} is compressed; write the archive with numpy.savez"
+            ));
+        }
+        let local = usize::try_from(offset).map_err(|_| invalid())?;
+        if u32_at(bytes, local) != Some(0x0403_4b50) {
+            return Err(invalid());
+        }
+        let local_name = usize::from(u16_at(bytes, local + 26).ok_or_else(invalid)?);
+        let local_extra = usize::from(u16_at(bytes, local + 28).ok_or_else(invalid)?);
+        let start = local + 30 + local_name + local_extra;
+        let size = usize::try_from(size).map_err(|_| invalid())?;
+        let contents = bytes.get(start..start + size).ok_or_else(invalid)?;
+        let name = name.strip_suffix(".npy").unwrap_or(&name).to_string();
+        arrays.insert(name, contents);
+    }
+    Ok(arrays)
+}
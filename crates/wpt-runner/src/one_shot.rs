@@ -0,0 +1,222 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! One-shot graph execution for `wpt-runner run`.
+//!
+//! Executes a single `execute_graph` request assembled from files and prints its outputs, so a
+//! failing case can be debugged without writing protocol lines. The graph file holds
+//! webnn-graph-json, or a whole `execute_graph` request such as a failure bundle's
+//! `request.json`, whose inputs and context options are then used as well. `--inputs` replaces
+//! them with a JSON object mapping input names to `{descriptor, data}` as in `execute_graph`,
+//! an `.npz` archive such as a bundle's `inputs.npz`, or a single `.npy` array named after the
+//! file. Outputs are written as JSON, or as `.npz`/`.npy` when `--out` has that extension.
+//! `--benchmark` sends the same request as a `benchmark` and writes its latency report.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::Serialize;
+use serde_json::{Map, Value, json};
+
+use crate::{
+    OutputTensor, TensorDescriptor, encoding, handle_request, npy_file, npz, parse_request,
+};
+
+const SESSION: &str = "run";
+
+fn read(path: &Path) -> Result<Vec<u8>, String> {
+    std::fs::read(path).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+fn read_json(path: &Path) -> Result<Value, String> {
+    serde_json::from_slice(&read(path)?)
+        .map_err(|e| format!("{}: invalid JSON: {e}", path.display()))
+}
+
+fn has_extension(path: &Path, extension: &str) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case(extension))
+}
+
+/// Inputs of an `.npz` archive or `.npy` file, sent as `npy` data. Each takes the `dataType`
+/// the graph declares for it, so NumPy arrays bind to 4-bit, bfloat16 and boolean inputs.
+fn numpy_inputs(path: &Path, declared: &Value) -> Result<Value, String> {
+    let bytes = read(path)?;
+    let located = |e: String| format!("{}: {e}", path.display());
+    let arrays = if has_extension(path, "npz") {
+        npz::read_archive(&bytes).map_err(located)?
+    } else {
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        BTreeMap::from([(stem.into_owned(), bytes.as_slice())])
+    };
+    let mut inputs = Map::new();
+    for (name, contents) in arrays {
+        let array = npz::parse_npy(contents).map_err(|e| located(format!("{name}: {e}")))?;
+        let data_type = declared
+            .get(&name)
+            .and_then(|desc| desc.get("dataType"))
+            .and_then(Value::as_str)
+            .or_else(|| npz::data_type(&array.descr))
+            .ok_or_else(|| located(format!("{name}: unsupported dtype {}", array.descr)))?;
+        inputs.insert(
+            name,
+            json!({
+                "descriptor": {"dataType": data_type, "shape": array.shape},
+                "data": encoding::encode_bytes(contents),
+                "dataEncoding": "npy",
+            }),
+        );
+    }
+    Ok(Value::Object(inputs))
+}
+
+/// `outputs` as one `.npy` file per output, keyed by output name.
+fn npy_outputs(
+    outputs: &BTreeMap<String, OutputTensor>,
+) -> Result<BTreeMap<String, Vec<u8>>, String> {
+    outputs
+        .iter()
+        .map(|(name, output)| {
+            let Value::Array(data) = &output.data else {
+                return Err(format!("output {name} is not JSON-encoded data"));
+            };
+            let descriptor = TensorDescriptor {
+                data_type: output.descriptor.data_type.clone(),
+                shape: output.descriptor.shape.clone(),
+            };
+            let file = npy_file(&descriptor, data).map_err(|e| format!("output {name}: {e}"))?;
+            Ok((name.clone(), file))
+        })
+        .collect()
+}
+
+/// Write `outputs` as an `.npz` archive, or as an `.npy` file when there is only one.
+fn write_arrays(outputs: &BTreeMap<String, OutputTensor>, out: &Path) -> Result<(), String> {
+    let contents = if has_extension(out, "npz") {
+        npz::archive(&npy_outputs(outputs)?)?
+    } else {
+        let mut files = npy_outputs(outputs)?.into_values();
+        match (files.next(), files.next()) {
+            (Some(file), None) => file,
+            _ => {
+                return Err(format!(
+                    "{} holds one array, but the graph has {} outputs; write an .npz",
+                    out.display(),
+                    outputs.len()
+                ));
+            }
+        }
+    };
+    std::fs::write(out, contents).map_err(|e| format!("{}: {e}", out.display()))
+}
+
+fn write_json(value: &impl Serialize, out: Option<&Path>) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(value)
+        .map_err(|e| format!("cannot serialize response: {e}"))?;
+    match out {
+        Some(out) => {
+            std::fs::write(out, format!("{json}\n")).map_err(|e| format!("{}: {e}", out.display()))
+        }
+        None => {
+            println!("{json}");
+            Ok(())
+        }
+    }
+}
+
+/// The `execute_graph` request for `graph`, with `inputs`, `backend` and `device` overriding
+/// whatever a request file carried.
+fn request(
+    graph: &Path,
+    inputs: Option<&Path>,
+    backend: Option<&str>,
+    device: Option<&str>,
+) -> Result<Value, String> {
+    let contents = read_json(graph)?;
+    let mut request = match contents.get("cmd").and_then(Value::as_str) {
+        Some("execute_graph") => contents,
+        Some(cmd) => {
+            return Err(format!(
+                "{}: expected an execute_graph request, got {cmd}",
+                graph.display()
+            ));
+        }
+        None => json!({"cmd": "execute_graph", "graph": contents}),
+    };
+    request["id"] = json!(SESSION);
+    if let Some(path) = inputs {
+        request["inputs"] = if has_extension(path, "npz") || has_extension(path, "npy") {
+            numpy_inputs(path, &request["graph"]["inputs"])?
+        } else {
+            read_json(path)?
+        };
+    }
+    if !request["context_options"].is_object() {
+        request["context_options"] = json!({});
+    }
+    if let Some(backend) = backend {
+        request["context_options"]["backend"] = json!(backend);
+    }
+    if let Some(device) = device {
+        request["context_options"]["deviceType"] = json!(device);
+    }
+    Ok(request)
+}
+
+/// Execute `graph` once and write its outputs to `out`, or as JSON to stdout.
+///
+/// With `benchmark` (iterations and optional warmups), run a `benchmark` request instead and
+/// write its report as JSON.
+pub fn run(
+    graph: &Path,
+    inputs: Option<&Path>,
+    out: Option<&Path>,
+    backend: Option<&str>,
+    device: Option<&str>,
+    benchmark: Option<(usize, Option<usize>)>,
+) -> Result<(), String> {
+    let mut request = request(graph, inputs, backend, device)?;
+    if let Some((iterations, warmups)) = benchmark {
+        request["cmd"] = json!("benchmark");
+        request["iterations"] = json!(iterations);
+        if let Some(warmups) = warmups {
+            request["warmups"] = json!(warmups);
+        }
+    }
+    let raw = request.to_string();
+    let parsed = parse_request(&raw)
+        .map_err(|e| e.to_string())?
+        .map_err(|e| format!("invalid request: {e}"))?;
+    let response = handle_request(parsed, SESSION);
+    for warning in &response.warnings {
+        eprintln!("[RUN] {}: {}", warning.kind, warning.message);
+    }
+    if let Some(error) = response.error {
+        return Err(format!("{}: {}", error.kind, error.message));
+    }
+    if benchmark.is_some() {
+        return write_json(&response.benchmark, out);
+    }
+    let outputs = response.outputs.unwrap_or_default();
+    match out {
+        Some(out) if has_extension(out, "npz") || has_extension(out, "npy") => {
+            write_arrays(&outputs, out)
+        }
+        _ => write_json(&outputs, out),
+    }
+}
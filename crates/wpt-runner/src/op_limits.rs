@@ -0,0 +1,428 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! `MLContext.opSupportLimits()` for the `get_capabilities` request.
+//!
+//! Which operators convert is decided inside rustnn's validator and converters, which expose no
+//! table to query. The limits reported here are the ones the runner itself enforces before
+//! rustnn sees a graph: the data types its tensor transport accepts, the tensor size limit, the
+//! rank of declared inputs and constants, and the per-operator rules checked on the raw graph.
+//! Every WebNN operator is listed with its operands, so feature detection sees an operator set;
+//! operand ranks are the ones the WebNN spec fixes, otherwise any up to [`MAX_RANK`]. `bfloat16`
+//! is not an `MLOperandDataType`, so it is accepted (through widening) but never reported. A
+//! harness can skip cases outside these limits instead of reading each failure as a bug.
+//!
+//! `set_capabilities` overrides entries of that table for the rest of a session, to exercise a
+//! harness's fallback paths deterministically ("pretend resample2d is unsupported"). An entry
+//! set to `null` removes the operator: graphs using it then fail validation with a
+//! `GraphValidationError`. Overridden `input`/`constant` data types are enforced on the graph's
+//! declarations the same way. Other entries only change what `get_capabilities` reports.
+//! Connections without a handshake session share the `""` partition, which is cleared when the
+//! last of them closes, so overrides never outlive the connections that could have set them.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use serde_json::{Map, Value, json};
+
+use crate::{RunnerError, element_count};
+
+/// Per-session overrides; requests without a session share the `""` partition.
+fn overrides() -> &'static Mutex<HashMap<String, Map<String, Value>>> {
+    static OVERRIDES: OnceLock<Mutex<HashMap<String, Map<String, Value>>>> = OnceLock::new();
+    OVERRIDES.get_or_init(Mutex::default)
+}
+
+fn session_overrides(session: &str) -> Map<String, Value> {
+    let sessions = overrides().lock().unwrap_or_else(|e| e.into_inner());
+    sessions.get(session).cloned().unwrap_or_default()
+}
+
+/// `MLOperandDataType`s every tensor path of the runner parses and returns.
+const DATA_TYPES: &[&str] = &[
+    "float32", "float16", "int8", "uint8", "int4", "uint4", "int32", "uint32", "int64", "uint64",
+];
+
+/// Highest operand rank reported, and accepted on input and constant declarations.
+pub const MAX_RANK: u64 = 8;
+
+/// Operand rank range, inclusive.
+type Ranks = (u64, u64);
+
+const ANY: Ranks = (0, MAX_RANK);
+
+/// Operators sharing one operand list, with each operand's rank range.
+const OPERATORS: &[(&[&str], &[(&str, Ranks)])] = &[
+    (
+        &[
+            "add",
+            "sub",
+            "mul",
+            "div",
+            "max",
+            "min",
+            "pow",
+            "equal",
+            "notEqual",
+            "greater",
+            "greaterOrEqual",
+            "lesser",
+            "lesserOrEqual",
+            "logicalAnd",
+            "logicalOr",
+            "logicalXor",
+        ],
+        &[("a", ANY), ("b", ANY), ("output", ANY)],
+    ),
+    (&["logicalNot"], &[("a", ANY), ("output", ANY)]),
+    (
+        &[
+            "abs",
+            "ceil",
+            "cos",
+            "erf",
+            "exp",
+            "floor",
+            "identity",
+            "log",
+            "neg",
+            "reciprocal",
+            "sin",
+            "sqrt",
+            "tan",
+            "sign",
+            "isNaN",
+            "isInfinite",
+            "roundEven",
+            "elu",
+            "gelu",
+            "hardSigmoid",
+            "hardSwish",
+            "leakyRelu",
+            "linear",
+            "relu",
+            "sigmoid",
+            "softplus",
+            "softsign",
+            "tanh",
+            "softmax",
+            "argMin",
+            "argMax",
+            "cast",
+            "clamp",
+            "cumulativeSum",
+            "expand",
+            "pad",
+            "reshape",
+            "reverse",
+            "slice",
+            "tile",
+            "transpose",
+            "triangular",
+            "reduceL1",
+            "reduceL2",
+            "reduceLogSum",
+            "reduceLogSumExp",
+            "reduceMax",
+            "reduceMean",
+            "reduceMin",
+            "reduceProduct",
+            "reduceSum",
+            "reduceSumSquare",
+        ],
+        &[("input", ANY), ("output", ANY)],
+    ),
+    (
+        &["averagePool2d", "l2Pool2d", "maxPool2d", "resample2d"],
+        &[("input", (4, 4)), ("output", (4, 4))],
+    ),
+    (&["split"], &[("input", ANY), ("outputs", ANY)]),
+    (&["concat"], &[("inputs", ANY), ("output", ANY)]),
+    (
+        &["prelu"],
+        &[("input", ANY), ("slope", ANY), ("output", ANY)],
+    ),
+    (
+        &["conv2d", "convTranspose2d"],
+        &[
+            ("input", (4, 4)),
+            ("filter", (4, 4)),
+            ("bias", (1, 1)),
+            ("output", (4, 4)),
+        ],
+    ),
+    (
+        &["gemm"],
+        &[
+            ("a", (2, 2)),
+            ("b", (2, 2)),
+            ("c", (0, 2)),
+            ("output", (2, 2)),
+        ],
+    ),
+    (
+        &["matmul"],
+        &[
+            ("a", (2, MAX_RANK)),
+            ("b", (2, MAX_RANK)),
+            ("output", (2, MAX_RANK)),
+        ],
+    ),
+    (
+        &["batchNormalization"],
+        &[
+            ("input", ANY),
+            ("mean", (1, 1)),
+            ("variance", (1, 1)),
+            ("scale", (1, 1)),
+            ("bias", (1, 1)),
+            ("output", ANY),
+        ],
+    ),
+    (
+        &["instanceNormalization"],
+        &[
+            ("input", (4, 4)),
+            ("scale", (1, 1)),
+            ("bias", (1, 1)),
+            ("output", (4, 4)),
+        ],
+    ),
+    (
+        &["layerNormalization"],
+        &[
+            ("input", ANY),
+            ("scale", ANY),
+            ("bias", ANY),
+            ("output", ANY),
+        ],
+    ),
+    (
+        &["quantizeLinear", "dequantizeLinear"],
+        &[
+            ("input", ANY),
+            ("scale", ANY),
+            ("zeroPoint", ANY),
+            ("output", ANY),
+        ],
+    ),
+    (
+        &["gather", "gatherElements", "gatherND"],
+        &[("input", ANY), ("indices", ANY), ("output", ANY)],
+    ),
+    (
+        &["scatterElements", "scatterND"],
+        &[
+            ("input", ANY),
+            ("indices", ANY),
+            ("updates", ANY),
+            ("output", ANY),
+        ],
+    ),
+    (
+        &["gru"],
+        &[
+            ("input", (3, 3)),
+            ("weight", (3, 3)),
+            ("recurrentWeight", (3, 3)),
+            ("bias", (2, 2)),
+            ("recurrentBias", (2, 2)),
+            ("initialHiddenState", (3, 3)),
+            ("outputs", (3, 4)),
+        ],
+    ),
+    (
+        &["gruCell"],
+        &[
+            ("input", (2, 2)),
+            ("weight", (2, 2)),
+            ("recurrentWeight", (2, 2)),
+            ("hiddenState", (2, 2)),
+            ("bias", (1, 1)),
+            ("recurrentBias", (1, 1)),
+            ("output", (2, 2)),
+        ],
+    ),
+    (
+        &["lstm"],
+        &[
+            ("input", (3, 3)),
+            ("weight", (3, 3)),
+            ("recurrentWeight", (3, 3)),
+            ("bias", (2, 2)),
+            ("recurrentBias", (2, 2)),
+            ("peepholeWeight", (2, 2)),
+            ("initialHiddenState", (3, 3)),
+            ("initialCellState", (3, 3)),
+            ("outputs", (3, 4)),
+        ],
+    ),
+    (
+        &["lstmCell"],
+        &[
+            ("input", (2, 2)),
+            ("weight", (2, 2)),
+            ("recurrentWeight", (2, 2)),
+            ("hiddenState", (2, 2)),
+            ("cellState", (2, 2)),
+            ("bias", (1, 1)),
+            ("recurrentBias", (1, 1)),
+            ("peepholeWeight", (1, 1)),
+            ("outputs", (2, 2)),
+        ],
+    ),
+];
+
+fn tensor_limits(data_types: &[&str], (min, max): Ranks) -> Value {
+    json!({ "dataTypes": data_types, "rankRange": { "min": min, "max": max } })
+}
+
+fn base_limits() -> Value {
+    let any = tensor_limits(DATA_TYPES, ANY);
+    let mut limits = json!({
+        "preferredInputLayout": "nchw",
+        "maxTensorByteLength": element_count::max_tensor_bytes(),
+        "input": any,
+        "constant": any,
+        "output": any,
+        // See `boolean::check_where_conditions`.
+        "where": {
+            "condition": tensor_limits(&["uint8"], ANY),
+            "trueValue": any,
+            "falseValue": any,
+            "output": any,
+        },
+    });
+    for (ops, operands) in OPERATORS {
+        let entry = operands
+            .iter()
+            .map(|&(name, ranks)| (name.to_string(), tensor_limits(DATA_TYPES, ranks)))
+            .collect::<Map<_, _>>();
+        for op in *ops {
+            limits[*op] = Value::Object(entry.clone());
+        }
+    }
+    limits
+}
+
+/// The limits `session` reports: the runner's own, with its overrides applied.
+pub fn op_support_limits(session: &str) -> Value {
+    let mut limits = base_limits();
+    if let Some(table) = limits.as_object_mut() {
+        for (key, value) in session_overrides(session) {
+            if value.is_null() {
+                table.remove(&key);
+            } else {
+                table.insert(key, value);
+            }
+        }
+    }
+    limits
+}
+
+/// Replace the overrides of `session`; an empty map restores the runner's own limits.
+pub fn set_overrides(session: &str, table: Map<String, Value>) -> Result<(), RunnerError> {
+    for (key, value) in &table {
+        if !value.is_null() && !value.is_object() {
+            return Err(RunnerError::BadRequest(format!(
+                "capability override '{key}' must be an object or null, got {value}"
+            )));
+        }
+    }
+    let mut sessions = overrides().lock().unwrap_or_else(|e| e.into_inner());
+    if table.is_empty() {
+        sessions.remove(session);
+    } else {
+        sessions.insert(session.to_string(), table);
+    }
+    Ok(())
+}
+
+/// Drop the overrides of `session`; called when its connection closes.
+pub fn end_session(session: &str) {
+    let mut sessions = overrides().lock().unwrap_or_else(|e| e.into_inner());
+    sessions.remove(session);
+}
+
+/// Reject declarations of `section` whose rank is outside the `rankRange` of `limits`.
+fn declared_ranks(graph: &Value, section: &str, limits: &Value) -> Result<(), RunnerError> {
+    let range = |key: &str| limits.get("rankRange")?.get(key)?.as_u64();
+    let (min, max) = (range("min").unwrap_or(0), range("max").unwrap_or(u64::MAX));
+    let declarations = graph.get(section).and_then(Value::as_object);
+    for (name, desc) in declarations.into_iter().flatten() {
+        let rank = desc
+            .get("shape")
+            .and_then(Value::as_array)
+            .map_or(0, Vec::len) as u64;
+        if rank < min || rank > max {
+            return Err(RunnerError::GraphValidation(format!(
+                "{section} '{name}' has rank {rank}, outside the supported {min}..={max}"
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn declared_types(graph: &Value, section: &str, limits: &Value) -> Result<(), RunnerError> {
+    let Some(allowed) = limits.get("dataTypes").and_then(Value::as_array) else {
+        return Ok(());
+    };
+    let declarations = graph.get(section).and_then(Value::as_object);
+    for (name, desc) in declarations.into_iter().flatten() {
+        let data_type = desc
+            .get("dataType")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        if !allowed.iter().any(|t| t.as_str() == Some(data_type)) {
+            return Err(RunnerError::GraphValidation(format!(
+                "{section} '{name}' has dataType {data_type}, which set_capabilities excluded"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Reject `graph` where its declared ranks fall outside the limits of `session`, or where the
+/// overrides of `session` exclude its ops or declared data types.
+pub fn check_graph(session: &str, graph: &Value) -> Result<(), RunnerError> {
+    let limits = op_support_limits(session);
+    for (section, key) in [("inputs", "input"), ("consts", "constant")] {
+        if let Some(limits) = limits.get(key) {
+            declared_ranks(graph, section, limits)?;
+        }
+    }
+    let table = session_overrides(session);
+    if table.is_empty() {
+        return Ok(());
+    }
+    let nodes = graph.get("nodes").and_then(Value::as_array);
+    for node in nodes.into_iter().flatten() {
+        let op = node.get("op").and_then(Value::as_str).unwrap_or_default();
+        if table.get(op).is_some_and(Value::is_null) {
+            return Err(RunnerError::GraphValidation(format!(
+                "{op} is unsupported: removed by set_capabilities"
+            )));
+        }
+    }
+    for (section, key) in [("inputs", "input"), ("consts", "constant")] {
+        if let Some(limits) = table.get(key) {
+            declared_types(graph, section, limits)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,133 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Stable operand ids for external tooling.
+//!
+//! rustnn's `GraphInfo` numbers operands internally, but that numbering follows its own
+//! lowering and is not part of any API. Minimizers, graph diffs and trace viewers need to name
+//! an operand the same way in every command, so the runner numbers the operands of the graph as
+//! submitted: graph inputs in sorted order, then constants in sorted order (the order
+//! operand-valued options index into), then node outputs in node order. `validate_graph` and
+//! `plan_graph` return the table with each operand's producer and consumers; runner rewrites
+//! never renumber it.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::graph_json::string_list;
+use crate::inference_rewrite::{operand_order, option_operands};
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Kind {
+    Input,
+    Constant,
+    Intermediate,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Operand {
+    id: usize,
+    name: String,
+    kind: Kind,
+    /// Id of the node computing this operand; none for inputs and constants.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    producer: Option<String>,
+    /// Ids of the nodes reading it through their `inputs` or operand-valued options, in node
+    /// order.
+    consumers: Vec<String>,
+    /// Graph output names exporting it.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    outputs: Vec<String>,
+}
+
+fn sorted_keys(graph: &Value, section: &str) -> Vec<String> {
+    let mut keys: Vec<String> = graph
+        .get(section)
+        .and_then(Value::as_object)
+        .map(|map| map.keys().cloned().collect())
+        .unwrap_or_default();
+    keys.sort();
+    keys
+}
+
+/// Every operand of `graph`, indexed by id.
+pub fn table(graph: &Value) -> Vec<Operand> {
+    let mut operands: Vec<Operand> = Vec::new();
+    let mut ids: HashMap<String, usize> = HashMap::new();
+    let mut declare = |operands: &mut Vec<Operand>, name: &str, kind, producer: Option<&str>| {
+        if ids.contains_key(name) {
+            return;
+        }
+        ids.insert(name.to_string(), operands.len());
+        operands.push(Operand {
+            id: operands.len(),
+            name: name.to_string(),
+            kind,
+            producer: producer.map(str::to_string),
+            consumers: Vec::new(),
+            outputs: Vec::new(),
+        });
+    };
+    for (section, kind) in [("inputs", Kind::Input), ("consts", Kind::Constant)] {
+        for name in sorted_keys(graph, section) {
+            declare(&mut operands, &name, kind, None);
+        }
+    }
+    let nodes = graph
+        .get("nodes")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let node_id = |index: usize, node: &Value| {
+        node.get("id")
+            .and_then(Value::as_str)
+            .map_or_else(|| format!("#{index}"), str::to_string)
+    };
+    for (index, node) in nodes.iter().enumerate() {
+        let id = node_id(index, node);
+        for name in string_list(node.get("outputs")) {
+            declare(&mut operands, name, Kind::Intermediate, Some(&id));
+        }
+    }
+    let order = operand_order(graph);
+    for (index, node) in nodes.iter().enumerate() {
+        let id = node_id(index, node);
+        let options = option_operands(node, &order);
+        let read = string_list(node.get("inputs"))
+            .into_iter()
+            .chain(options.iter().map(|(_, name)| name.as_str()));
+        for name in read {
+            if let Some(&operand) = ids.get(name) {
+                let consumers = &mut operands[operand].consumers;
+                if consumers.last() != Some(&id) {
+                    consumers.push(id.clone());
+                }
+            }
+        }
+    }
+    let outputs = graph.get("outputs").and_then(Value::as_object);
+    for (output, operand) in outputs.into_iter().flatten() {
+        if let Some(&operand) = operand.as_str().and_then(|name| ids.get(name)) {
+            operands[operand].outputs.push(output.clone());
+        }
+    }
+    operands
+}
@@ -0,0 +1,133 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Graph outputs that export the same operand under several names.
+//!
+//! Converters keep one graph output per operand, so a second name for the same operand is either
+//! dropped or attributed to the wrong tensor. We keep only the first name in the graph handed to
+//! rustnn and fan the computed tensor back out under every requested name afterwards.
+//!
+//! Outputs that export a graph input or constant directly have no producing node at all, which
+//! converters handle poorly, so those get an explicit `identity` node.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use serde_json::Value;
+
+use crate::RuntimeOutput;
+
+/// Remove duplicate graph outputs, returning `alias name -> kept output name`.
+pub fn dedup_outputs(graph: &mut Value) -> BTreeMap<String, String> {
+    let mut aliases = BTreeMap::new();
+    let Some(outputs) = graph.get_mut("outputs").and_then(Value::as_object_mut) else {
+        return aliases;
+    };
+
+    let mut first_by_operand: HashMap<String, String> = HashMap::new();
+    for (name, operand) in outputs.iter() {
+        let Some(operand) = operand.as_str() else {
+            continue;
+        };
+        match first_by_operand.get(operand) {
+            Some(kept) => {
+                aliases.insert(name.clone(), kept.clone());
+            }
+            None => {
+                first_by_operand.insert(operand.to_string(), name.clone());
+            }
+        }
+    }
+    for alias in aliases.keys() {
+        outputs.remove(alias);
+    }
+    aliases
+}
+
+/// Add a copy of each kept output under its alias names.
+pub fn expand_outputs(
+    by_name: &mut HashMap<String, RuntimeOutput>,
+    aliases: &BTreeMap<String, String>,
+) {
+    for (alias, kept) in aliases {
+        if let Some(output) = by_name.get(kept) {
+            let mut copy = output.clone();
+            copy.name = alias.clone();
+            by_name.insert(alias.clone(), copy);
+        }
+    }
+}
+
+/// Route outputs that name a graph input or constant through an `identity` node.
+pub fn materialize_passthrough_outputs(graph: &mut Value) {
+    let mut sources = BTreeSet::new();
+    for section in ["inputs", "consts"] {
+        if let Some(map) = graph.get(section).and_then(Value::as_object) {
+            sources.extend(map.keys().cloned());
+        }
+    }
+    let mut taken = sources.clone();
+    for node in graph
+        .get("nodes")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+    {
+        if let Some(outputs) = node.get("outputs").and_then(Value::as_array) {
+            taken.extend(outputs.iter().filter_map(Value::as_str).map(str::to_string));
+        }
+    }
+
+    let passthrough = graph
+        .get("outputs")
+        .and_then(Value::as_object)
+        .map(|outputs| {
+            outputs
+                .iter()
+                .filter_map(|(name, operand)| {
+                    let operand = operand.as_str()?;
+                    sources
+                        .contains(operand)
+                        .then(|| (name.clone(), operand.to_string()))
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    for (index, (name, source)) in passthrough.into_iter().enumerate() {
+        let mut produced = format!("{name}__identity");
+        while taken.contains(&produced) {
+            produced.push('_');
+        }
+        taken.insert(produced.clone());
+
+        let node = serde_json::json!({
+            "id": format!("passthrough_{index}"),
+            "op": "identity",
+            "inputs": [source],
+            "options": {},
+            "outputs": [produced],
+        });
+        if !graph.get("nodes").is_some_and(Value::is_array) {
+            graph["nodes"] = Value::Array(Vec::new());
+        }
+        if let Some(nodes) = graph["nodes"].as_array_mut() {
+            nodes.push(node);
+        }
+        graph["outputs"][name.as_str()] = Value::from(produced);
+    }
+}
@@ -0,0 +1,99 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Output data types inferred from the graph.
+//!
+//! Executors hand outputs back as untyped numbers, and without `expected_outputs` the runner has
+//! no descriptor to cast them to. Data types are propagated through the raw webnn-graph-json
+//! before any lowering rewrites them, so ad-hoc callers get the `dataType` the graph actually
+//! produces. Most operators keep the type of their first input; the exceptions are listed in
+//! [`node_output_type`].
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde_json::Value;
+
+use crate::boolean;
+use crate::graph_json::string_list;
+
+fn node_output_type<'a>(node: &'a Value, types: &HashMap<String, &'a str>) -> Option<&'a str> {
+    let op = node.get("op").and_then(Value::as_str)?;
+    let option = |key: &str| {
+        node.get("options")
+            .and_then(|options| options.get(key))
+            .and_then(Value::as_str)
+    };
+    let inputs = string_list(node.get("inputs"));
+    let input_type = |index: usize| inputs.get(index).and_then(|name| types.get(*name)).copied();
+    match op {
+        "cast" => option("to"),
+        "argMin" | "argMax" => Some(option("outputDataType").unwrap_or("int32")),
+        "dequantizeLinear" => input_type(1),
+        "quantizeLinear" => input_type(2),
+        // The condition comes first, but the result has the type of the selected values.
+        "where" => input_type(1),
+        op if boolean::is_logical(op) => Some("uint8"),
+        _ => option("outputDataType")
+            .or_else(|| option("dataType"))
+            .or_else(|| input_type(0)),
+    }
+}
+
+/// `dataType` of every operand whose type can be inferred, keyed by operand name.
+pub fn operand_types(graph: &Value) -> HashMap<String, &str> {
+    let mut types: HashMap<String, &str> = HashMap::new();
+    for section in ["inputs", "consts"] {
+        if let Some(map) = graph.get(section).and_then(Value::as_object) {
+            for (name, desc) in map {
+                if let Some(data_type) = desc.get("dataType").and_then(Value::as_str) {
+                    types.insert(name.clone(), data_type);
+                }
+            }
+        }
+    }
+
+    let nodes = graph
+        .get("nodes")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    for node in nodes {
+        let Some(data_type) = node_output_type(node, &types) else {
+            continue;
+        };
+        for name in string_list(node.get("outputs")) {
+            types.insert(name.to_string(), data_type);
+        }
+    }
+    types
+}
+
+/// `dataType` of every graph output whose type can be inferred, keyed by output name.
+pub fn infer(graph: &Value) -> BTreeMap<String, String> {
+    let types = operand_types(graph);
+    graph
+        .get("outputs")
+        .and_then(Value::as_object)
+        .into_iter()
+        .flatten()
+        .filter_map(|(name, operand)| {
+            let data_type = types.get(operand.as_str()?)?;
+            Some((name.clone(), (*data_type).to_string()))
+        })
+        .collect()
+}
@@ -0,0 +1,208 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Size limits on request payloads, for runners serving untrusted harnesses with `--listen`.
+//!
+//! Lines are read at most `RUSTNNPT_MAX_LINE_BYTES` at a time, so a line that never ends cannot
+//! grow the read buffer, and each line's JSON nesting depth is checked with a byte scan before it
+//! is parsed at all. Parsed JSON costs far more than its text: every element of a JSON array is a
+//! `serde_json::Value` of [`VALUE_BYTES`], so once parsed, a request's tensor elements are
+//! counted, and every graph in it is checked for its operation count, its operand count (inputs,
+//! constants and node outputs) and the in-memory size of each inline constant, before any pass,
+//! validator or converter walks it. A payload over a limit is answered with `PayloadLimitError`.
+//! Each limit has a `RUSTNNPT_MAX_*` environment override.
+
+use std::io::{self, BufRead};
+
+use serde_json::Value;
+
+use crate::RunnerError;
+
+const DEFAULT_MAX_LINE_BYTES: usize = 1 << 28;
+const DEFAULT_MAX_JSON_DEPTH: usize = 64;
+const DEFAULT_MAX_REQUEST_ELEMENTS: usize = 1 << 25;
+const DEFAULT_MAX_GRAPH_OPERATIONS: usize = 1 << 16;
+const DEFAULT_MAX_GRAPH_OPERANDS: usize = 1 << 18;
+const DEFAULT_MAX_INLINE_CONSTANT_BYTES: usize = 1 << 30;
+
+/// Memory one parsed JSON element takes, whatever its text.
+pub const VALUE_BYTES: usize = std::mem::size_of::<Value>();
+
+/// `var` parsed as a limit, `default` when unset or invalid.
+pub fn limit(var: &str, default: usize) -> usize {
+    std::env::var(var)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
+/// The next line of `reader` without its line ending, `None` at the end of input. A line over
+/// `RUSTNNPT_MAX_LINE_BYTES` is skipped to its end without being buffered and answered with the
+/// inner error.
+pub fn read_line(reader: &mut impl BufRead) -> io::Result<Option<Result<String, RunnerError>>> {
+    let max = limit("RUSTNNPT_MAX_LINE_BYTES", DEFAULT_MAX_LINE_BYTES);
+    let (mut line, mut over, mut read_any) = (Vec::new(), false, false);
+    loop {
+        let buf = match reader.fill_buf() {
+            Ok(buf) => buf,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        if buf.is_empty() {
+            if !read_any {
+                return Ok(None);
+            }
+            break;
+        }
+        read_any = true;
+        let end = buf.iter().position(|&b| b == b'\n');
+        let chunk = &buf[..end.unwrap_or(buf.len())];
+        if !over && line.len() + chunk.len() > max {
+            over = true;
+            line = Vec::new();
+        }
+        if !over {
+            line.extend_from_slice(chunk);
+        }
+        let used = end.map_or(buf.len(), |end| end + 1);
+        reader.consume(used);
+        if end.is_some() {
+            break;
+        }
+    }
+    if over {
+        return Ok(Some(Err(RunnerError::PayloadLimit(format!(
+            "request line is longer than {max} bytes"
+        )))));
+    }
+    if line.last() == Some(&b'\r') {
+        line.pop();
+    }
+    String::from_utf8(line)
+        .map(|line| Some(Ok(line)))
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Fail if a request carries more than `RUSTNNPT_MAX_REQUEST_ELEMENTS` tensor elements.
+pub fn check_elements(elements: usize) -> Result<(), RunnerError> {
+    let max = limit(
+        "RUSTNNPT_MAX_REQUEST_ELEMENTS",
+        DEFAULT_MAX_REQUEST_ELEMENTS,
+    );
+    if elements > max {
+        return Err(RunnerError::PayloadLimit(format!(
+            "request carries {elements} tensor elements, over the limit of {max}"
+        )));
+    }
+    Ok(())
+}
+
+/// Fail if `raw` nests arrays and objects deeper than `RUSTNNPT_MAX_JSON_DEPTH`.
+pub fn check_depth(raw: &str) -> Result<(), RunnerError> {
+    let max = limit("RUSTNNPT_MAX_JSON_DEPTH", DEFAULT_MAX_JSON_DEPTH);
+    let (mut depth, mut in_string, mut escaped) = (0usize, false, false);
+    for b in raw.bytes() {
+        if in_string {
+            match b {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match b {
+            b'"' => in_string = true,
+            b'[' | b'{' => {
+                depth += 1;
+                if depth > max {
+                    return Err(RunnerError::PayloadLimit(format!(
+                        "request nests deeper than {max} levels"
+                    )));
+                }
+            }
+            b']' | b'}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn section_len(graph: &Value, key: &str) -> usize {
+    graph
+        .get(key)
+        .and_then(Value::as_object)
+        .map_or(0, serde_json::Map::len)
+}
+
+/// Fail if `graph` is over the operation, operand or inline constant limits.
+pub fn check_graph(graph: &Value) -> Result<(), RunnerError> {
+    let nodes = graph
+        .get("nodes")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let max_operations = limit(
+        "RUSTNNPT_MAX_GRAPH_OPERATIONS",
+        DEFAULT_MAX_GRAPH_OPERATIONS,
+    );
+    if nodes.len() > max_operations {
+        return Err(RunnerError::PayloadLimit(format!(
+            "graph has {} operations, over the limit of {max_operations}",
+            nodes.len()
+        )));
+    }
+    let node_outputs = nodes
+        .iter()
+        .filter_map(|node| node.get("outputs").and_then(Value::as_array))
+        .map(Vec::len)
+        .sum::<usize>();
+    let declared = ["inputs", "consts", "constants"].map(|key| section_len(graph, key));
+    let operands = declared.iter().sum::<usize>() + node_outputs;
+    let max_operands = limit("RUSTNNPT_MAX_GRAPH_OPERANDS", DEFAULT_MAX_GRAPH_OPERANDS);
+    if operands > max_operands {
+        return Err(RunnerError::PayloadLimit(format!(
+            "graph has {operands} operands, over the limit of {max_operands}"
+        )));
+    }
+    let max_bytes = limit(
+        "RUSTNNPT_MAX_INLINE_CONSTANT_BYTES",
+        DEFAULT_MAX_INLINE_CONSTANT_BYTES,
+    );
+    let consts = graph.get("consts").and_then(Value::as_object);
+    let constants = graph.get("constants").and_then(Value::as_object);
+    for (name, decl) in consts.into_iter().chain(constants).flatten() {
+        let elements = match decl.get("init").and_then(|init| init.get("bytes")) {
+            Some(Value::Array(bytes)) => bytes.len(),
+            // Out-of-band `constants` are base64, 4 characters per 3 bytes, and are inlined as
+            // `init.bytes` arrays.
+            _ => decl
+                .get("data")
+                .and_then(Value::as_str)
+                .map_or(0, |data| data.len() / 4 * 3),
+        };
+        let bytes = elements.saturating_mul(VALUE_BYTES);
+        if bytes > max_bytes {
+            return Err(RunnerError::PayloadLimit(format!(
+                "inline constant {name} takes {bytes} bytes once parsed, over the limit of \
+                 {max_bytes}"
+            )));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,175 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Graphviz DOT rendering of the graph a backend will actually run.
+//!
+//! The plan is drawn from the webnn-graph-json after the runner's rewrites, with every node
+//! colored by the backend and device it is placed on. Nodes the runner inserted are dashed,
+//! operands whose data type was emulated are labeled with the original and executed types, and
+//! constants repacked into the backend-native layout are marked as layout conversions, and nodes
+//! that are nondeterministic on the device are outlined, so the reason a graph runs slower or
+//! differs numerically on a device is visible at a glance.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Write;
+
+use serde_json::Value;
+
+use crate::graph_json::{section, string_list};
+use crate::nondeterminism::Flagged;
+
+/// Where the graph runs; rustnn places whole graphs on a single backend.
+pub struct Placement<'a> {
+    pub backend: &'a str,
+    pub device: &'a str,
+}
+
+fn fill_color(backend: &str) -> &'static str {
+    match backend {
+        "onnx" => "lightblue",
+        "coreml" => "palegreen",
+        "trtx" => "khaki",
+        _ => "lightgray",
+    }
+}
+
+pub fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn data_type(desc: &Value) -> &str {
+    desc.get("dataType")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+}
+
+fn node_ids(graph: &Value) -> BTreeSet<&str> {
+    graph
+        .get("nodes")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|node| node.get("id").and_then(Value::as_str))
+        .collect()
+}
+
+/// Render `rewritten` (the prepared graph) as DOT, annotated against `original` (the graph as
+/// submitted), the constants `prepacked` by layout rewriting and the `nondeterministic` nodes.
+pub fn to_dot(
+    original: &Value,
+    rewritten: &Value,
+    placement: &Placement,
+    prepacked: &[String],
+    nondeterministic: &[Flagged],
+) -> String {
+    let color = fill_color(placement.backend);
+    let original_types: HashMap<&str, &str> = ["inputs", "consts"]
+        .into_iter()
+        .flat_map(|key| section(original, key))
+        .map(|(name, desc)| (name.as_str(), data_type(desc)))
+        .collect();
+    let original_nodes = node_ids(original);
+
+    let mut dot = String::new();
+    let _ = writeln!(dot, "digraph plan {{");
+    let _ = writeln!(dot, "  rankdir=TB;");
+    let _ = writeln!(
+        dot,
+        "  label={};",
+        quote(&format!("{} on {}", placement.backend, placement.device))
+    );
+    let _ = writeln!(dot, "  node [style=filled, fillcolor={color}];");
+
+    // Operand name -> DOT node that produces it.
+    let mut producers: HashMap<String, String> = HashMap::new();
+    for (key, shape) in [("inputs", "ellipse"), ("consts", "box")] {
+        for (name, desc) in section(rewritten, key) {
+            let executed = data_type(desc);
+            let mut label = format!("{name}\\n{executed}");
+            let mut attrs = format!("shape={shape}");
+            let submitted = original_types.get(name.as_str()).copied();
+            if let Some(submitted) = submitted.filter(|&t| t != executed) {
+                label = format!("{name}\\n{submitted} emulated as {executed}");
+                attrs.push_str(", color=red");
+            }
+            if prepacked.contains(name) {
+                label.push_str("\\nlayout conversion (prepacked)");
+                attrs.push_str(", peripheries=2");
+            }
+            let id = format!("{key}:{name}");
+            let _ = writeln!(dot, "  {} [label={}, {attrs}];", quote(&id), quote(&label));
+            producers.insert(name.clone(), id);
+        }
+    }
+
+    let nodes = rewritten
+        .get("nodes")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    for (index, node) in nodes.iter().enumerate() {
+        let op = node.get("op").and_then(Value::as_str).unwrap_or("?");
+        let node_id = node
+            .get("id")
+            .and_then(Value::as_str)
+            .map_or_else(|| format!("#{index}"), str::to_string);
+        let id = format!("node:{node_id}");
+        let (mut label, mut attrs) = if original_nodes.contains(node_id.as_str()) {
+            (format!("{node_id}\\n{op}"), String::new())
+        } else {
+            (
+                format!("{node_id}\\n{op}\\ninserted by runner"),
+                ", style=\"filled,dashed\"".to_string(),
+            )
+        };
+        if nondeterministic.iter().any(|f| f.is(node, &node_id)) {
+            label.push_str("\\nnondeterministic");
+            attrs.push_str(", color=orange, penwidth=2");
+        }
+        let _ = writeln!(dot, "  {} [label={}{attrs}];", quote(&id), quote(&label));
+        for input in string_list(node.get("inputs")) {
+            if let Some(from) = producers.get(input) {
+                let _ = writeln!(
+                    dot,
+                    "  {} -> {} [label={}];",
+                    quote(from),
+                    quote(&id),
+                    quote(input)
+                );
+            }
+        }
+        for output in string_list(node.get("outputs")) {
+            producers.insert(output.to_string(), id.clone());
+        }
+    }
+
+    for (name, operand) in section(rewritten, "outputs") {
+        let id = format!("outputs:{name}");
+        let _ = writeln!(
+            dot,
+            "  {} [label={}, shape=doubleoctagon, fillcolor=white];",
+            quote(&id),
+            quote(name)
+        );
+        if let Some(from) = operand.as_str().and_then(|op| producers.get(op)) {
+            let _ = writeln!(dot, "  {} -> {};", quote(from), quote(&id));
+        }
+    }
+    let _ = writeln!(dot, "}}");
+    dot
+}
@@ -0,0 +1,245 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Output post-processing for model-level smoke tests.
+//!
+//! An `expected_outputs` entry can name a `postprocess` step, and the runner returns its compact
+//! result as `postprocessed` next to the output's `data`: the top-k classes of classification
+//! logits, or the detections decoded from a box tensor. End-to-end model runs can then assert
+//! "tabby cat first" instead of comparing a thousand logits.
+//!
+//! Labels are sent inline. The runner never reads a labels file itself, since a path in the
+//! request would let any `--listen` client read files on the runner's machine.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{RunnerError, RuntimeOutput};
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BoxFormat {
+    /// Center x, center y, width, height.
+    #[default]
+    Xywh,
+    /// Corner coordinates.
+    Xyxy,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum PostProcess {
+    /// The `k` highest scores along the last axis, for each row.
+    TopK {
+        #[serde(default = "default_k")]
+        k: usize,
+        #[serde(default)]
+        labels: Vec<String>,
+    },
+    /// Detections from rows of four box coordinates followed by one score per class, along the
+    /// last axis. Rows under `scoreThreshold` are dropped and overlapping boxes of one class are
+    /// reduced by greedy non-maximum suppression.
+    Boxes {
+        #[serde(default)]
+        format: BoxFormat,
+        #[serde(rename = "scoreThreshold", default = "default_score_threshold")]
+        score_threshold: f64,
+        #[serde(rename = "iouThreshold", default = "default_iou_threshold")]
+        iou_threshold: f64,
+        #[serde(rename = "maxDetections", default = "default_max_detections")]
+        max_detections: usize,
+        #[serde(default)]
+        labels: Vec<String>,
+    },
+}
+
+fn default_k() -> usize {
+    5
+}
+
+fn default_score_threshold() -> f64 {
+    0.5
+}
+
+fn default_iou_threshold() -> f64 {
+    0.5
+}
+
+fn default_max_detections() -> usize {
+    100
+}
+
+#[derive(Debug, Serialize)]
+struct Ranked {
+    index: usize,
+    score: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    label: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct Detection {
+    /// `[x0, y0, x1, y1]`.
+    #[serde(rename = "box")]
+    corners: [f64; 4],
+    class: usize,
+    score: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    label: Option<String>,
+}
+
+fn label(labels: &[String], index: usize) -> Option<String> {
+    labels.get(index).cloned()
+}
+
+fn last_axis(output: &RuntimeOutput) -> usize {
+    output.shape.last().copied().unwrap_or(1)
+}
+
+fn top_k(output: &RuntimeOutput, k: usize, labels: &[String]) -> Vec<Vec<Ranked>> {
+    let width = last_axis(output);
+    if width == 0 {
+        return Vec::new();
+    }
+    output
+        .data
+        .chunks(width)
+        .map(|row| {
+            let mut ranked = row.iter().copied().enumerate().collect::<Vec<_>>();
+            ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+            ranked
+                .into_iter()
+                .take(k)
+                .map(|(index, score)| Ranked {
+                    index,
+                    score,
+                    label: label(labels, index),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn iou(a: &[f64; 4], b: &[f64; 4]) -> f64 {
+    let area = |r: &[f64; 4]| (r[2] - r[0]).max(0.0) * (r[3] - r[1]).max(0.0);
+    let width = (a[2].min(b[2]) - a[0].max(b[0])).max(0.0);
+    let height = (a[3].min(b[3]) - a[1].max(b[1])).max(0.0);
+    let intersection = width * height;
+    let union = area(a) + area(b) - intersection;
+    if union > 0.0 {
+        intersection / union
+    } else {
+        0.0
+    }
+}
+
+/// Detections per batch entry; every axis before the last two is a batch axis.
+fn boxes(
+    output: &RuntimeOutput,
+    format: BoxFormat,
+    score_threshold: f64,
+    iou_threshold: f64,
+    max_detections: usize,
+    labels: &[String],
+) -> Result<Vec<Vec<Detection>>, RunnerError> {
+    let width = last_axis(output);
+    if output.shape.len() < 2 || width < 5 {
+        return Err(RunnerError::BadRequest(format!(
+            "boxes post-processing needs rows of 4 coordinates and at least one class score, got shape {:?}",
+            output.shape
+        )));
+    }
+    let rows = output.shape[output.shape.len() - 2];
+    if rows == 0 {
+        return Ok(Vec::new());
+    }
+    let batches = output
+        .data
+        .chunks(rows * width)
+        .map(|batch| {
+            let mut candidates = batch
+                .chunks(width)
+                .filter_map(|row| {
+                    let (class, score) = row[4..]
+                        .iter()
+                        .copied()
+                        .enumerate()
+                        .max_by(|a, b| a.1.total_cmp(&b.1))?;
+                    if score.is_nan() || score < score_threshold {
+                        return None;
+                    }
+                    let corners = match format {
+                        BoxFormat::Xyxy => [row[0], row[1], row[2], row[3]],
+                        BoxFormat::Xywh => [
+                            row[0] - row[2] / 2.0,
+                            row[1] - row[3] / 2.0,
+                            row[0] + row[2] / 2.0,
+                            row[1] + row[3] / 2.0,
+                        ],
+                    };
+                    Some(Detection {
+                        corners,
+                        class,
+                        score,
+                        label: label(labels, class),
+                    })
+                })
+                .collect::<Vec<_>>();
+            candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+            let mut kept: Vec<Detection> = Vec::new();
+            for candidate in candidates {
+                if kept.len() == max_detections {
+                    break;
+                }
+                let suppressed = kept.iter().any(|k| {
+                    k.class == candidate.class
+                        && iou(&k.corners, &candidate.corners) > iou_threshold
+                });
+                if !suppressed {
+                    kept.push(candidate);
+                }
+            }
+            kept
+        })
+        .collect();
+    Ok(batches)
+}
+
+/// Run `postprocess` on a computed output.
+pub fn apply(postprocess: &PostProcess, output: &RuntimeOutput) -> Result<Value, RunnerError> {
+    let result = match postprocess {
+        PostProcess::TopK { k, labels } => serde_json::to_value(top_k(output, *k, labels)),
+        PostProcess::Boxes {
+            format,
+            score_threshold,
+            iou_threshold,
+            max_detections,
+            labels,
+        } => serde_json::to_value(boxes(
+            output,
+            *format,
+            *score_threshold,
+            *iou_threshold,
+            *max_detections,
+            labels,
+        )?),
+    };
+    result.map_err(|e| {
+        RunnerError::RuntimeExecution(format!("cannot encode post-processed output: {e}"))
+    })
+}
@@ -0,0 +1,135 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Compile progress frames.
+//!
+//! Converting a multi-hundred-MB model can take minutes with no output, which a harness watching
+//! for hangs cannot tell from a dead runner. After a `handshake` with `progress_interval_ms`,
+//! every graph build of that connection writes `{"id", "progress": {stage, percent, elapsedMs}}`
+//! lines ahead of its response: one when a stage starts and the latest one again every interval
+//! until the build ends, or until its `timeoutMs` fires and the request has been answered.
+//! Frames carry no `ok` field. rustnn converts in one call, so `percent` only advances between
+//! stages.
+//!
+//! The reporter of the request being handled is a thread-local, carried over to the threads a
+//! request runs on (`--jobs` workers, `timeoutMs` watchdogs).
+
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde_json::json;
+
+/// Where one request's progress frames go.
+pub struct Reporter {
+    id: String,
+    interval: Duration,
+    /// Lines for the connection's writer; `None`, sent by `serve_lines`, stops it.
+    sink: Sender<Option<String>>,
+    started: Instant,
+    latest: Mutex<(&'static str, u8)>,
+    /// Set once the request was answered without its build; no frame may follow the response.
+    cancelled: AtomicBool,
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<Arc<Reporter>>> = const { RefCell::new(None) };
+}
+
+impl Reporter {
+    pub fn new(id: String, interval: Duration, sink: Sender<Option<String>>) -> Arc<Self> {
+        Arc::new(Self {
+            id,
+            interval,
+            sink,
+            started: Instant::now(),
+            latest: Mutex::new(("queued", 0)),
+            cancelled: AtomicBool::new(false),
+        })
+    }
+
+    /// Stop every frame of this request, for a build still running after its timeout.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    fn send_latest(&self) {
+        if self.is_cancelled() {
+            return;
+        }
+        let (stage, percent) = *self.latest.lock().unwrap_or_else(|e| e.into_inner());
+        let frame = json!({
+            "id": self.id,
+            "progress": {
+                "stage": stage,
+                "percent": percent,
+                "elapsedMs": self.started.elapsed().as_millis() as u64,
+            },
+        });
+        // The connection may already be closed; nobody is left to tell.
+        let _ = self.sink.send(Some(frame.to_string()));
+    }
+}
+
+pub fn current() -> Option<Arc<Reporter>> {
+    CURRENT.with(|current| current.borrow().clone())
+}
+
+/// Run `work` with `reporter` as this thread's reporter.
+pub fn scoped<T>(reporter: Option<Arc<Reporter>>, work: impl FnOnce() -> T) -> T {
+    let previous = CURRENT.with(|current| current.replace(reporter));
+    let result = work();
+    CURRENT.with(|current| current.replace(previous));
+    result
+}
+
+/// Report that the current build entered `stage`.
+pub fn stage(stage: &'static str, percent: u8) {
+    if let Some(reporter) = current() {
+        *reporter.latest.lock().unwrap_or_else(|e| e.into_inner()) = (stage, percent);
+        reporter.send_latest();
+    }
+}
+
+/// Repeats the latest frame every interval until dropped.
+pub struct Heartbeat {
+    _stop: Option<Sender<()>>,
+}
+
+/// Start repeating frames for the current build; a no-op without a reporter.
+pub fn heartbeat() -> Heartbeat {
+    let Some(reporter) = current() else {
+        return Heartbeat { _stop: None };
+    };
+    let (stop, stopped) = mpsc::channel::<()>();
+    std::thread::spawn(move || {
+        while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(reporter.interval) {
+            if reporter.is_cancelled() {
+                break;
+            }
+            reporter.send_latest();
+        }
+    });
+    Heartbeat { _stop: Some(stop) }
+}
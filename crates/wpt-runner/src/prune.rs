@@ -0,0 +1,129 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Dead-node elimination on webnn-graph-json.
+//!
+//! Nodes whose outputs never reach a declared graph output are dropped before conversion, so
+//! unused branches neither inflate the emitted model nor fail conversion for ops that cannot
+//! affect the result. Graph inputs and constants are kept even when unused: requests still bind
+//! every declared input, and operand-valued options refer to declarations by sorted index.
+//! A node is live when a live node reads its output through `inputs` or an operand-valued
+//! option. Index-valued options also count node outputs, so they are renumbered once dead nodes
+//! are gone.
+
+use std::collections::{HashMap, HashSet};
+
+use serde_json::Value;
+
+use crate::graph_json::string_list;
+use crate::inference_rewrite::{operand_order, option_operands, renumber_options};
+
+/// Remove nodes that no graph output depends on.
+///
+/// Returns the ids of the removed nodes.
+pub fn prune_dead_nodes(graph: &mut Value) -> Vec<String> {
+    let mut live: HashSet<String> = graph
+        .get("outputs")
+        .and_then(Value::as_object)
+        .map(|outputs| {
+            outputs
+                .values()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default();
+    let order = operand_order(graph);
+    let Some(nodes) = graph.get_mut("nodes").and_then(Value::as_array_mut) else {
+        return Vec::new();
+    };
+
+    // Walk backwards: a node is live when any of its outputs is, which makes its inputs live.
+    let mut keep = vec![false; nodes.len()];
+    for (index, node) in nodes.iter().enumerate().rev() {
+        if string_list(node.get("outputs"))
+            .iter()
+            .any(|output| live.contains(*output))
+        {
+            keep[index] = true;
+            live.extend(
+                string_list(node.get("inputs"))
+                    .into_iter()
+                    .map(str::to_string),
+            );
+            live.extend(
+                option_operands(node, &order)
+                    .into_iter()
+                    .map(|(_, name)| name),
+            );
+        }
+    }
+
+    let mut removed = Vec::new();
+    let mut keep = keep.into_iter();
+    nodes.retain(|node| {
+        let kept = keep.next().unwrap_or(true);
+        if !kept {
+            removed.push(node.get("id").and_then(Value::as_str).map_or_else(
+                || string_list(node.get("outputs")).join(","),
+                str::to_string,
+            ));
+        }
+        kept
+    });
+    if !removed.is_empty() {
+        renumber_options(graph, &order, &HashMap::new());
+    }
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn computed_bias_index_survives_pruning() {
+        // Operand order: x, b0, w, unused, b, y.
+        let mut graph = json!({
+            "inputs": {"x": {"dataType": "float32", "shape": [1, 1, 3, 3]}},
+            "consts": {
+                "b0": {"dataType": "float32", "shape": [1]},
+                "w": {"dataType": "float32", "shape": [1, 1, 1, 1]},
+            },
+            "nodes": [
+                {"id": "dead", "op": "relu", "inputs": ["x"], "outputs": ["unused"]},
+                {"id": "bias", "op": "relu", "inputs": ["b0"], "outputs": ["b"]},
+                {
+                    "id": "conv",
+                    "op": "conv2d",
+                    "inputs": ["x", "w"],
+                    "options": {"bias": 4},
+                    "outputs": ["y"],
+                },
+            ],
+            "outputs": {"y": "y"},
+        });
+        assert_eq!(prune_dead_nodes(&mut graph), ["dead"]);
+        let order = operand_order(&graph);
+        assert_eq!(order, ["x", "b0", "w", "b", "y"]);
+        assert_eq!(graph["nodes"].as_array().map(Vec::len), Some(2));
+        assert_eq!(graph["nodes"][1]["options"]["bias"], json!(3));
+    }
+}
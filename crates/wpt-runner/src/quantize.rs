@@ -0,0 +1,397 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Post-training int8 quantization into QDQ form.
+//!
+//! `quantize_graph` first runs the float graph on every calibration input set with each float32
+//! node output exported as an extra graph output, so the range of every tensor is observed.
+//! Each float32 graph input, constant and node output then gets a `quantizeLinear` +
+//! `dequantizeLinear` pair with per-tensor int8 parameters, and its consumers read the
+//! dequantized operand. Constants use symmetric scales (zero point 0); inputs and activations use
+//! asymmetric ranges that always include zero. The report gives, per tensor, the error of that
+//! quantize/dequantize round trip over the calibration values.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use serde::Serialize;
+use serde_json::{Value, json};
+
+use crate::graph_json::string_list;
+use crate::{
+    ContextOptions, InputTensor, RunnerError, WarningPayload, distribution, execute_graph,
+    output_dtype, parse_f32, prepare_graph, validated_graph_info, weight_prepack,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum TensorKind {
+    Input,
+    Constant,
+    Activation,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TensorQuantization {
+    kind: TensorKind,
+    scale: f32,
+    #[serde(rename = "zeroPoint")]
+    zero_point: i8,
+    /// Calibrated range; always includes zero.
+    min: f32,
+    max: f32,
+    #[serde(rename = "maxAbsError")]
+    max_abs_error: f64,
+    rmse: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct QuantizedGraph {
+    graph: Value,
+    tensors: BTreeMap<String, TensorQuantization>,
+}
+
+/// Values of one tensor across all calibration runs.
+struct Observed {
+    kind: TensorKind,
+    rank: usize,
+    values: Vec<f32>,
+}
+
+fn nodes(graph: &Value) -> &[Value] {
+    graph
+        .get("nodes")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+fn nonzero(scale: f32) -> f32 {
+    if scale > 0.0 && scale.is_finite() {
+        scale
+    } else {
+        1.0
+    }
+}
+
+/// Scale and zero point for `kind` over the calibrated `[min, max]`.
+fn parameters(kind: TensorKind, min: f32, max: f32) -> (f32, i8) {
+    if kind == TensorKind::Constant {
+        return (nonzero(min.abs().max(max.abs()) / 127.0), 0);
+    }
+    let scale = nonzero((max - min) / 255.0);
+    let zero_point = (-128.0 - min / scale).round().clamp(-128.0, 127.0) as i8;
+    (scale, zero_point)
+}
+
+/// `x` after quantizeLinear and dequantizeLinear, rounding half to even like WebNN.
+fn round_trip(x: f32, scale: f32, zero_point: i8) -> f32 {
+    let zero_point = f32::from(zero_point);
+    let q = ((x / scale).round_ties_even() + zero_point).clamp(-128.0, 127.0);
+    (q - zero_point) * scale
+}
+
+fn quantization(observed: &Observed) -> TensorQuantization {
+    let finite = || observed.values.iter().copied().filter(|v| v.is_finite());
+    let (min, max) = finite().fold((0.0f32, 0.0f32), |(lo, hi), v| (lo.min(v), hi.max(v)));
+    let (scale, zero_point) = parameters(observed.kind, min, max);
+    let (mut max_abs_error, mut squares, mut count) = (0.0f64, 0.0f64, 0usize);
+    for v in finite() {
+        let err = f64::from((round_trip(v, scale, zero_point) - v).abs());
+        max_abs_error = max_abs_error.max(err);
+        squares += err * err;
+        count += 1;
+    }
+    TensorQuantization {
+        kind: observed.kind,
+        scale,
+        zero_point,
+        min,
+        max,
+        max_abs_error,
+        rmse: if count == 0 {
+            0.0
+        } else {
+            (squares / count as f64).sqrt()
+        },
+    }
+}
+
+/// `graph` with every operand in `activations` exported, and the output name each is read from.
+fn calibration_graph(
+    graph: &Value,
+    activations: &BTreeSet<String>,
+) -> (Value, BTreeMap<String, String>) {
+    let mut graph = graph.clone();
+    let mut keys = BTreeMap::new();
+    let mut outputs = graph["outputs"]
+        .as_object_mut()
+        .map(std::mem::take)
+        .unwrap_or_default();
+    for (key, operand) in &outputs {
+        if let Some(operand) = operand.as_str().filter(|o| activations.contains(*o)) {
+            keys.entry(operand.to_string())
+                .or_insert_with(|| key.clone());
+        }
+    }
+    for operand in activations {
+        if keys.contains_key(operand) {
+            continue;
+        }
+        let mut key = operand.clone();
+        while outputs.contains_key(&key) {
+            key.push_str("_calibration");
+        }
+        outputs.insert(key.clone(), Value::from(operand.as_str()));
+        keys.insert(operand.clone(), key);
+    }
+    graph["outputs"] = Value::Object(outputs);
+    (graph, keys)
+}
+
+/// Insert the QDQ pairs for `quantized` into `graph` and point consumers at them.
+fn rewrite(graph: &mut Value, quantized: &BTreeMap<String, (TensorKind, usize, f32, i8)>) {
+    let mut declared: BTreeSet<String> = BTreeSet::new();
+    for section in ["inputs", "consts"] {
+        if let Some(map) = graph.get(section).and_then(Value::as_object) {
+            declared.extend(map.keys().cloned());
+        }
+    }
+    for node in nodes(graph) {
+        declared.extend(
+            string_list(node.get("outputs"))
+                .into_iter()
+                .map(str::to_string),
+        );
+    }
+    let mut fresh = |base: String| {
+        let mut name = base.clone();
+        let mut n = 1;
+        while !declared.insert(name.clone()) {
+            name = format!("{base}_{n}");
+            n += 1;
+        }
+        name
+    };
+
+    let mut renamed = HashMap::new();
+    let mut pairs = HashMap::new();
+    for (name, &(_, rank, scale, zero_point)) in quantized {
+        let scale_name = fresh(format!("{name}_scale"));
+        let zero_point_name = fresh(format!("{name}_zero_point"));
+        let q_name = fresh(format!("{name}_quantized"));
+        let dq_name = fresh(format!("{name}_dequantized"));
+        let shape = vec![1usize; rank];
+        graph["consts"][scale_name.as_str()] = json!({
+            "dataType": "float32",
+            "shape": shape,
+            "init": { "kind": "inlineBytes", "bytes": scale.to_le_bytes() },
+        });
+        graph["consts"][zero_point_name.as_str()] = json!({
+            "dataType": "int8",
+            "shape": shape,
+            "init": { "kind": "inlineBytes", "bytes": [zero_point as u8] },
+        });
+        let pair = vec![
+            json!({
+                "id": format!("{name}_quantize"),
+                "op": "quantizeLinear",
+                "inputs": [name, scale_name, zero_point_name],
+                "options": {},
+                "outputs": [q_name],
+            }),
+            json!({
+                "id": format!("{name}_dequantize"),
+                "op": "dequantizeLinear",
+                "inputs": [q_name, scale_name, zero_point_name],
+                "options": {},
+                "outputs": [dq_name],
+            }),
+        ];
+        pairs.insert(name.clone(), pair);
+        renamed.insert(name.clone(), dq_name);
+    }
+
+    let rename = |value: &mut Value| match value {
+        Value::String(name) => {
+            if let Some(to) = renamed.get(name.as_str()) {
+                *name = to.clone();
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                if let Some(to) = item.as_str().and_then(|name| renamed.get(name)) {
+                    *item = Value::from(to.as_str());
+                }
+            }
+        }
+        _ => {}
+    };
+
+    let original = match graph.get_mut("nodes") {
+        Some(Value::Array(nodes)) => std::mem::take(nodes),
+        _ => Vec::new(),
+    };
+    // Inputs and constants are available before the first node runs.
+    let mut rewritten: Vec<Value> = quantized
+        .iter()
+        .filter(|(_, (kind, ..))| *kind != TensorKind::Activation)
+        .filter_map(|(name, _)| pairs.remove(name))
+        .flatten()
+        .collect();
+    for mut node in original {
+        if let Some(inputs) = node.get_mut("inputs") {
+            rename(inputs);
+        }
+        let outputs: Vec<String> = string_list(node.get("outputs"))
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        rewritten.push(node);
+        for output in outputs {
+            rewritten.extend(pairs.remove(&output).into_iter().flatten());
+        }
+    }
+    graph["nodes"] = Value::Array(rewritten);
+    if let Some(outputs) = graph.get_mut("outputs").and_then(Value::as_object_mut) {
+        outputs.values_mut().for_each(rename);
+    }
+}
+
+/// Calibrate `graph` on `calibration` and return its QDQ form with the per-tensor report.
+pub fn quantize_graph(
+    graph: Value,
+    mut calibration: Vec<BTreeMap<String, InputTensor>>,
+    context_options: &ContextOptions,
+    session: &str,
+    warnings: &mut Vec<WarningPayload>,
+) -> Result<QuantizedGraph, RunnerError> {
+    if calibration.is_empty() {
+        return Err(RunnerError::BadRequest(
+            "quantize_graph needs at least one calibration input set".to_string(),
+        ));
+    }
+    let types = output_dtype::operand_types(&graph);
+    let is_float = |name: &str| types.get(name) == Some(&"float32");
+
+    let mut observed: BTreeMap<String, Observed> = BTreeMap::new();
+    if let Some(consts) = graph.get("consts").and_then(Value::as_object) {
+        for (name, decl) in consts.iter().filter(|(name, _)| is_float(name)) {
+            // Constants without inline bytes keep their float values.
+            let Some(bytes) = weight_prepack::const_bytes(decl) else {
+                continue;
+            };
+            let values = bytes
+                .chunks_exact(4)
+                .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect();
+            let rank = decl
+                .get("shape")
+                .and_then(Value::as_array)
+                .map_or(0, Vec::len);
+            observed.insert(
+                name.clone(),
+                Observed {
+                    kind: TensorKind::Constant,
+                    rank,
+                    values,
+                },
+            );
+        }
+    }
+    let activations: BTreeSet<String> = nodes(&graph)
+        .iter()
+        .flat_map(|node| string_list(node.get("outputs")))
+        .filter(|name| is_float(name))
+        .map(str::to_string)
+        .collect();
+    let (calibration_graph, keys) = calibration_graph(&graph, &activations);
+    // Activation values are read back from `data`, which spilling would leave empty.
+    let mut calibration_options = context_options.clone();
+    calibration_options.spill_threshold_bytes = None;
+
+    for inputs in &mut calibration {
+        distribution::generate_inputs(inputs)?;
+    }
+    for inputs in calibration {
+        for (name, input) in inputs.iter().filter(|(name, _)| is_float(name)) {
+            let entry = observed.entry(name.clone()).or_insert_with(|| Observed {
+                kind: TensorKind::Input,
+                rank: input.descriptor.shape.len(),
+                values: Vec::new(),
+            });
+            for value in &input.data {
+                entry.values.push(parse_f32(value)?);
+            }
+        }
+        let outputs = execute_graph(
+            calibration_graph.clone(),
+            inputs,
+            BTreeMap::new(),
+            calibration_options.clone(),
+            session,
+            warnings,
+        )?;
+        for (operand, key) in &keys {
+            let Some(output) = outputs.get(key) else {
+                continue;
+            };
+            let entry = observed.entry(operand.clone()).or_insert_with(|| Observed {
+                kind: TensorKind::Activation,
+                rank: output.descriptor.shape.len(),
+                values: Vec::new(),
+            });
+            for value in output
+                .data
+                .as_array()
+                .map(Vec::as_slice)
+                .unwrap_or_default()
+            {
+                entry.values.push(parse_f32(value)?);
+            }
+        }
+    }
+
+    let tensors: BTreeMap<String, TensorQuantization> = observed
+        .iter()
+        .map(|(name, observed)| (name.clone(), quantization(observed)))
+        .collect();
+    let parameters = observed
+        .iter()
+        .map(|(name, o)| {
+            let t = &tensors[name];
+            (name.clone(), (o.kind, o.rank, t.scale, t.zero_point))
+        })
+        .collect();
+    let mut quantized = graph;
+    rewrite(&mut quantized, &parameters);
+
+    // The QDQ graph must still build; report what rustnn rejects instead of returning it.
+    let mut check_warnings = Vec::new();
+    let prepared = prepare_graph(
+        quantized.clone(),
+        None,
+        context_options,
+        &mut check_warnings,
+    )?;
+    validated_graph_info(&prepared)?;
+
+    Ok(QuantizedGraph {
+        graph: quantized,
+        tensors,
+    })
+}
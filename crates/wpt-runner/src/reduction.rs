@@ -0,0 +1,84 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Reductions over an empty `axes` list.
+//!
+//! In WebNN, `axes: []` reduces over no dimension at all, so each element is its own reduction:
+//! `reduceSum` and friends return their input and `reduceL1` returns its absolute value. ONNX
+//! `Reduce*` treats an empty `axes` input as "all axes" unless `noop_with_empty_axes` is set, and
+//! even then skips the elementwise part, so forwarding the option gives a scalar instead. Such
+//! nodes are rewritten into the elementwise op they amount to before conversion. An absent `axes`
+//! still means every axis and is left alone.
+
+use serde_json::{Map, Value, json};
+
+/// The elementwise op a reduction over no axes amounts to, and whether it takes `x` twice.
+fn elementwise(op: &str) -> Option<(&'static str, bool)> {
+    Some(match op {
+        "reduceSum" | "reduceMean" | "reduceMax" | "reduceMin" | "reduceProduct" => {
+            ("identity", false)
+        }
+        "reduceL1" => ("abs", false),
+        // log(exp(x)) and sqrt(x * x), without the overflow of the intermediate.
+        "reduceLogSumExp" => ("identity", false),
+        "reduceL2" => ("abs", false),
+        "reduceLogSum" => ("log", false),
+        "reduceSumSquare" => ("mul", true),
+        _ => return None,
+    })
+}
+
+fn has_empty_axes(node: &Value) -> bool {
+    node.get("options")
+        .and_then(|options| options.get("axes"))
+        .and_then(Value::as_array)
+        .is_some_and(Vec::is_empty)
+}
+
+/// Rewrite every reduction with `axes: []`, returning the ids of the rewritten nodes.
+pub fn lower_empty_axes(graph: &mut Value) -> Vec<String> {
+    let mut rewritten = Vec::new();
+    let Some(nodes) = graph.get_mut("nodes").and_then(Value::as_array_mut) else {
+        return rewritten;
+    };
+    for node in nodes {
+        let Some(op) = node.get("op").and_then(Value::as_str) else {
+            continue;
+        };
+        let Some((replacement, squared)) = elementwise(op).filter(|_| has_empty_axes(node)) else {
+            continue;
+        };
+        let Some(x) = node.get("inputs").and_then(|inputs| inputs.get(0)).cloned() else {
+            continue;
+        };
+        let id = node
+            .get("id")
+            .and_then(Value::as_str)
+            .map_or_else(|| op.to_string(), str::to_string);
+        // keepDimensions has nothing to keep; only the label carries over.
+        let mut options = Map::new();
+        if let Some(label) = node.get("options").and_then(|o| o.get("label")) {
+            options.insert("label".to_string(), label.clone());
+        }
+        node["op"] = json!(replacement);
+        node["inputs"] = if squared { json!([x, x]) } else { json!([x]) };
+        node["options"] = Value::Object(options);
+        rewritten.push(id);
+    }
+    rewritten
+}
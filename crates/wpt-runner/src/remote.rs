@@ -0,0 +1,226 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Remote executor: run a [`CompiledGraph`] on another wpt-runner over TCP.
+//!
+//! A runner started with `--listen ADDR` serves the regular line-delimited protocol on every
+//! accepted connection. The harness-side runner compiles locally and sends a `run_compiled`
+//! request carrying the artifact and the inputs, so the lab machine only needs the executor.
+//! There is no authentication; only listen on a trusted network. At most [`MAX_CONNECTIONS`]
+//! connections are served at once; further ones are closed right after being accepted. A
+//! connection that sends nothing for `RUSTNNPT_IDLE_TIMEOUT_SECS` (default 600) is closed once its
+//! in-flight requests are answered, so idle clients cannot hold every slot.
+
+use std::collections::BTreeMap;
+use std::io::{self, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::compiled::CompiledGraph;
+use crate::{InputTensor, RunnerError, RuntimeOutput, error_from_kind, payload_limits};
+
+/// Upper bound for one remote execution, including artifact upload.
+const REMOTE_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Seconds a served connection may stay silent before it is closed.
+const DEFAULT_IDLE_TIMEOUT_SECS: usize = 600;
+
+/// Connections a listening runner serves concurrently.
+pub const MAX_CONNECTIONS: usize = 64;
+
+#[derive(Serialize)]
+struct RunCompiledRequest<'a> {
+    cmd: &'static str,
+    id: &'static str,
+    compiled: &'a CompiledGraph,
+    inputs: &'a BTreeMap<String, InputTensor>,
+}
+
+#[derive(Deserialize)]
+struct RemoteResponse {
+    ok: bool,
+    #[serde(default)]
+    raw_outputs: Option<Vec<RuntimeOutput>>,
+    #[serde(default)]
+    error: Option<RemoteError>,
+}
+
+#[derive(Deserialize)]
+struct RemoteError {
+    kind: String,
+    message: String,
+}
+
+/// One `run_compiled` request line, newline included.
+pub fn request_line(
+    compiled: &CompiledGraph,
+    inputs: &BTreeMap<String, InputTensor>,
+) -> Result<Vec<u8>, String> {
+    let request = RunCompiledRequest {
+        cmd: "run_compiled",
+        id: "remote",
+        compiled,
+        inputs,
+    };
+    let mut line =
+        serde_json::to_vec(&request).map_err(|e| format!("request encode failed: {e}"))?;
+    line.push(b'\n');
+    Ok(line)
+}
+
+/// Outputs of a `run_compiled` response line received from `peer`.
+pub fn parse_reply(peer: &str, reply: &str) -> Result<Vec<RuntimeOutput>, RunnerError> {
+    if reply.is_empty() {
+        return Err(RunnerError::RuntimeExecution(format!(
+            "{peer}: connection closed without a response"
+        )));
+    }
+    let response: RemoteResponse = serde_json::from_str(reply)
+        .map_err(|e| RunnerError::RuntimeExecution(format!("{peer}: invalid response: {e}")))?;
+
+    match (response.ok, response.raw_outputs, response.error) {
+        (true, Some(outputs), _) => Ok(outputs),
+        // Keep the remote kind so a harness sees the same error as with a local executor.
+        (_, _, Some(err)) => Err(error_from_kind(
+            &err.kind,
+            format!("{peer}: {}", err.message),
+        )),
+        _ => Err(RunnerError::RuntimeExecution(format!(
+            "{peer}: response carries no outputs"
+        ))),
+    }
+}
+
+/// Execute `compiled` on the runner listening at `addr`.
+pub fn run_remote(
+    addr: &str,
+    compiled: &CompiledGraph,
+    inputs: &BTreeMap<String, InputTensor>,
+) -> Result<Vec<RuntimeOutput>, RunnerError> {
+    let io_error = |e: io::Error| RunnerError::RuntimeExecution(format!("remote {addr}: {e}"));
+
+    let mut stream = TcpStream::connect(addr).map_err(io_error)?;
+    stream
+        .set_read_timeout(Some(REMOTE_TIMEOUT))
+        .map_err(io_error)?;
+    stream
+        .set_write_timeout(Some(REMOTE_TIMEOUT))
+        .map_err(io_error)?;
+
+    let line = request_line(compiled, inputs)
+        .map_err(|e| RunnerError::RuntimeExecution(format!("remote {addr}: {e}")))?;
+    stream.write_all(&line).map_err(io_error)?;
+    stream.flush().map_err(io_error)?;
+
+    let reply = payload_limits::read_line(&mut BufReader::new(&stream))
+        .map_err(io_error)?
+        .transpose()?
+        .unwrap_or_default();
+    parse_reply(&format!("remote {addr}"), &reply)
+}
+
+/// Decrements the open-connection count when a connection thread ends.
+struct ConnectionSlot(Arc<AtomicUsize>);
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Accept connections on `addr` and serve the protocol on each one in its own thread.
+pub fn serve(addr: &str) -> io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    eprintln!("[RUNNER] listening on {}", listener.local_addr()?);
+    let open = Arc::new(AtomicUsize::new(0));
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                eprintln!("[RUNNER] accept failed: {err}");
+                continue;
+            }
+        };
+        if open.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+            open.fetch_sub(1, Ordering::SeqCst);
+            eprintln!("[RUNNER] refusing connection: {MAX_CONNECTIONS} already open");
+            continue;
+        }
+        let slot = ConnectionSlot(Arc::clone(&open));
+        let idle = payload_limits::limit("RUSTNNPT_IDLE_TIMEOUT_SECS", DEFAULT_IDLE_TIMEOUT_SECS);
+        let idle = Duration::from_secs(idle.max(1) as u64);
+        std::thread::spawn(move || {
+            let _slot = slot;
+            let timeouts = stream
+                .set_read_timeout(Some(idle))
+                .and_then(|()| stream.set_write_timeout(Some(idle)));
+            if let Err(err) = timeouts {
+                eprintln!("[RUNNER] connection setup failed: {err}");
+                return;
+            }
+            let reader = match stream.try_clone() {
+                Ok(reader) => BufReader::new(reader),
+                Err(err) => {
+                    eprintln!("[RUNNER] connection setup failed: {err}");
+                    return;
+                }
+            };
+            crate::serve_lines(reader, stream);
+        });
+    }
+    Ok(())
+}
+
+/// `RuntimeOutput::data` as base64 little-endian f64, so NaN and infinities survive JSON.
+pub mod f64_bytes {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(values: &[f64], serializer: S) -> Result<S::Ok, S::Error> {
+        let bytes = values
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect::<Vec<_>>();
+        serializer.serialize_str(&STANDARD.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<f64>, D::Error> {
+        let bytes = STANDARD
+            .decode(String::deserialize(deserializer)?)
+            .map_err(serde::de::Error::custom)?;
+        if bytes.len() % 8 != 0 {
+            return Err(serde::de::Error::custom(format!(
+                "f64 payload of {} bytes is not a multiple of 8",
+                bytes.len()
+            )));
+        }
+        Ok(bytes
+            .chunks_exact(8)
+            .map(|chunk| {
+                let mut arr = [0u8; 8];
+                arr.copy_from_slice(chunk);
+                f64::from_le_bytes(arr)
+            })
+            .collect())
+    }
+}
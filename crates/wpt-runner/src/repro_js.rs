@@ -0,0 +1,400 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Browser-runnable WebNN repros of runner graphs.
+//!
+//! Failure bundles carry the graph as webnn-graph-json, which only the runner reads. `to_js`
+//! turns it back into `MLGraphBuilder` calls that build the same graph, write the request's
+//! inputs and print every output, so a failure can be replayed in a browser and reported
+//! against its WebNN implementation. Operand-valued options become the builder's variables
+//! again, and arguments the shim moved into `options` (`newShape`, `axis`, ...) go back to
+//! their positions. Tensor bytes are embedded as base64.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write;
+
+use serde_json::Value;
+
+use crate::graph_json::string_list;
+use crate::inference_rewrite::{OPERAND_OPTIONS, operand_order};
+use crate::weight_prepack::const_bytes;
+use crate::{
+    InputTensor, RunnerError, TensorDescriptor, encoding, int4, parse_4bit,
+    tensor_data_to_le_bytes, to_tensor_data,
+};
+
+/// `MLGraphBuilder` parameters, after the operands, that graph-json keeps in `options`.
+const POSITIONAL: &[(&str, &[&str])] = &[
+    ("argMax", &["axis"]),
+    ("argMin", &["axis"]),
+    ("cast", &["to"]),
+    ("concat", &["axis"]),
+    ("cumulativeSum", &["axis"]),
+    ("expand", &["newShape"]),
+    ("gru", &["steps", "hiddenSize"]),
+    ("gruCell", &["hiddenSize"]),
+    ("lstm", &["steps", "hiddenSize"]),
+    ("lstmCell", &["hiddenSize"]),
+    ("pad", &["beginningPadding", "endingPadding"]),
+    ("reshape", &["newShape"]),
+    ("slice", &["starts", "sizes"]),
+    ("softmax", &["axis"]),
+    ("split", &["splits"]),
+    ("tile", &["repetitions"]),
+];
+
+/// Builder methods returning a sequence of operands, whatever the node's output count.
+const SEQUENCE_OPS: &[&str] = &["gru", "lstm", "lstmCell", "split"];
+
+const POOL2D_OPS: &[&str] = &[
+    "averagePool2d",
+    "globalAveragePool",
+    "globalMaxPool",
+    "l2Pool2d",
+    "maxPool2d",
+];
+
+/// Names the generated script declares itself, the globals it relies on, and every JavaScript
+/// reserved word (plus `arguments`, `eval` and `undefined`), none of which an operand may shadow.
+const RESERVED: &[&str] = &[
+    "builder",
+    "bytes",
+    "context",
+    "graph",
+    "inputTensors",
+    "outputTensors",
+    "outputs",
+    "VIEWS",
+    // Globals of the prelude and epilogue.
+    "atob",
+    "console",
+    "globalThis",
+    "navigator",
+    "MLGraphBuilder",
+    "Object",
+    "BigInt64Array",
+    "BigUint64Array",
+    "Float16Array",
+    "Float32Array",
+    "Int32Array",
+    "Int8Array",
+    "Uint16Array",
+    "Uint32Array",
+    "Uint8Array",
+    // Reserved words, strict-mode included.
+    "await",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "debugger",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "enum",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "for",
+    "function",
+    "if",
+    "implements",
+    "import",
+    "in",
+    "instanceof",
+    "interface",
+    "let",
+    "new",
+    "null",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "static",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "var",
+    "void",
+    "while",
+    "with",
+    "yield",
+    "arguments",
+    "eval",
+    "undefined",
+];
+
+const PRELUDE: &str = "\
+const bytes = (b64) => Uint8Array.from(atob(b64), (c) => c.charCodeAt(0));
+const VIEWS = {
+  float32: Float32Array, float16: globalThis.Float16Array ?? Uint16Array, int8: Int8Array,
+  uint8: Uint8Array, int4: Uint8Array, uint4: Uint8Array, int32: Int32Array,
+  uint32: Uint32Array, int64: BigInt64Array, uint64: BigUint64Array,
+};
+const context = await navigator.ml.createContext();
+const builder = new MLGraphBuilder(context);
+";
+
+const EPILOGUE: &str = "\
+const graph = await builder.build(outputs);
+const outputTensors = {};
+for (const [name, operand] of Object.entries(outputs)) {
+  const descriptor = { dataType: operand.dataType, shape: operand.shape, readable: true };
+  outputTensors[name] = await context.createTensor(descriptor);
+}
+context.dispatch(graph, inputTensors, outputTensors);
+for (const [name, tensor] of Object.entries(outputTensors)) {
+  const View = VIEWS[tensor.dataType] ?? Uint8Array;
+  console.log(name, new View(await context.readTensor(tensor)));
+}
+";
+
+fn literal(value: impl Into<Value>) -> String {
+    value.into().to_string()
+}
+
+/// An operand or tensor descriptor literal; `extra` adds members such as `writable: true`.
+fn descriptor(decl: &Value, extra: &str) -> String {
+    let data_type = decl
+        .get("dataType")
+        .and_then(Value::as_str)
+        .unwrap_or("float32");
+    let shape = decl
+        .get("shape")
+        .cloned()
+        .unwrap_or_else(|| Value::Array(Vec::new()));
+    format!(
+        "{{ dataType: {}, shape: {shape}{extra} }}",
+        literal(data_type)
+    )
+}
+
+/// Storage bytes of a tensor as `MLTensor`s and constants take them (`int4`/`uint4` packed).
+fn storage_bytes(descriptor: &TensorDescriptor, data: &[Value]) -> Result<Vec<u8>, RunnerError> {
+    let data_type = descriptor.data_type.as_str();
+    if int4::is_4bit(data_type) {
+        parse_4bit(data_type, data).map(|values| int4::pack(&values))
+    } else {
+        to_tensor_data(descriptor, data).map(tensor_data_to_le_bytes)
+    }
+}
+
+fn base64_bytes(bytes: &[u8]) -> String {
+    format!("bytes({})", encoding::encode_bytes(bytes))
+}
+
+/// JavaScript identifiers for operand names, unique and clear of reserved words.
+#[derive(Default)]
+struct Identifiers {
+    by_name: HashMap<String, String>,
+    used: HashSet<String>,
+}
+
+impl Identifiers {
+    fn declare(&mut self, name: &str) -> String {
+        let mut base: String = name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        if base.is_empty() || base.starts_with(|c: char| c.is_ascii_digit()) {
+            base.insert(0, '_');
+        }
+        if RESERVED.contains(&base.as_str()) {
+            base.push('_');
+        }
+        let mut ident = base.clone();
+        let mut suffix = 1;
+        while !self.used.insert(ident.clone()) {
+            suffix += 1;
+            ident = format!("{base}_{suffix}");
+        }
+        self.by_name.insert(name.to_string(), ident.clone());
+        ident
+    }
+
+    fn get(&self, name: &str) -> Result<&str, RunnerError> {
+        self.by_name
+            .get(name)
+            .map(String::as_str)
+            .ok_or_else(|| RunnerError::GraphValidation(format!("unknown operand {name}")))
+    }
+}
+
+/// One builder call for `node`, assigned to its output variables.
+fn node_call(
+    node: &Value,
+    order: &[String],
+    idents: &mut Identifiers,
+) -> Result<String, RunnerError> {
+    let op = node.get("op").and_then(Value::as_str).unwrap_or_default();
+    let mut options = node
+        .get("options")
+        .and_then(Value::as_object)
+        .cloned()
+        .unwrap_or_default();
+    let inputs = string_list(node.get("inputs"))
+        .into_iter()
+        .map(|name| idents.get(name).map(str::to_string))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut args = if op == "concat" {
+        vec![format!("[{}]", inputs.join(", "))]
+    } else {
+        inputs
+    };
+    let positional = POSITIONAL
+        .iter()
+        .find(|(name, _)| *name == op)
+        .map_or(&[][..], |(_, params)| params);
+    for param in positional {
+        args.push(options.remove(*param).unwrap_or(Value::Null).to_string());
+    }
+    let mut entries = Vec::new();
+    for (key, value) in options {
+        let key = if POOL2D_OPS.contains(&op) && key == "outputShapeRounding" {
+            "roundingType".to_string()
+        } else {
+            key
+        };
+        let text = if OPERAND_OPTIONS.contains(&key.as_str()) {
+            let name = match &value {
+                Value::Number(index) => index
+                    .as_u64()
+                    .and_then(|i| order.get(usize::try_from(i).ok()?)),
+                Value::String(name) => Some(name),
+                _ => None,
+            };
+            match name {
+                Some(name) => idents.get(name)?.to_string(),
+                None => value.to_string(),
+            }
+        } else {
+            value.to_string()
+        };
+        entries.push(format!("{}: {text}", literal(key)));
+    }
+    if !entries.is_empty() {
+        args.push(format!("{{ {} }}", entries.join(", ")));
+    }
+    let outputs = string_list(node.get("outputs"))
+        .into_iter()
+        .map(|name| idents.declare(name))
+        .collect::<Vec<_>>();
+    let target = if outputs.len() == 1 && !SEQUENCE_OPS.contains(&op) {
+        outputs[0].clone()
+    } else {
+        format!("[{}]", outputs.join(", "))
+    };
+    Ok(format!(
+        "const {target} = builder.{op}({});\n",
+        args.join(", ")
+    ))
+}
+
+/// A script that builds `graph` with `MLGraphBuilder`, dispatches it on `inputs` and logs every
+/// output. Graph inputs missing from `inputs` are left zero-filled.
+pub fn to_js(graph: &Value, inputs: &BTreeMap<String, InputTensor>) -> Result<String, RunnerError> {
+    let order = operand_order(graph);
+    let nodes = graph
+        .get("nodes")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+
+    let mut idents = Identifiers::default();
+    let mut js = String::from(PRELUDE);
+    let mut tensors = String::from("const inputTensors = {};\n");
+    let declared = |section: &str| {
+        graph
+            .get(section)
+            .and_then(Value::as_object)
+            .into_iter()
+            .flatten()
+    };
+    for (name, decl) in declared("inputs") {
+        let ident = idents.declare(name);
+        let _ = writeln!(
+            js,
+            "const {ident} = builder.input({}, {});",
+            literal(name.as_str()),
+            descriptor(decl, "")
+        );
+        let tensor = format!("inputTensors[{}]", literal(name.as_str()));
+        let _ = writeln!(
+            tensors,
+            "{tensor} = await context.createTensor({});",
+            descriptor(decl, ", writable: true")
+        );
+        if let Some(input) = inputs.get(name) {
+            let bytes = storage_bytes(&input.descriptor, &input.data)?;
+            let _ = writeln!(
+                tensors,
+                "context.writeTensor({tensor}, {});",
+                base64_bytes(&bytes)
+            );
+        }
+    }
+    for (name, decl) in declared("consts") {
+        let ident = idents.declare(name);
+        let mut bytes = const_bytes(decl).ok_or_else(|| {
+            RunnerError::NotSupported(format!("constant {name} is not inline bytes"))
+        })?;
+        if decl
+            .get("dataType")
+            .and_then(Value::as_str)
+            .is_some_and(int4::is_4bit)
+        {
+            // Inline 4-bit constants take a byte per element; builder constants are packed.
+            let values = bytes
+                .iter()
+                .map(|b| i64::from(*b as i8))
+                .collect::<Vec<_>>();
+            bytes = int4::pack(&values);
+        }
+        let _ = writeln!(
+            js,
+            "const {ident} = builder.constant({}, {});",
+            descriptor(decl, ""),
+            base64_bytes(&bytes)
+        );
+    }
+    for node in nodes {
+        js.push_str(&node_call(node, &order, &mut idents)?);
+    }
+    let mut outputs = Vec::new();
+    for (name, operand) in declared("outputs") {
+        let operand = operand.as_str().unwrap_or(name);
+        outputs.push(format!(
+            "{}: {}",
+            literal(name.as_str()),
+            idents.get(operand)?
+        ));
+    }
+    let _ = writeln!(js, "const outputs = {{ {} }};", outputs.join(", "));
+    js.push_str(&tensors);
+    js.push_str(EPILOGUE);
+    Ok(js)
+}
@@ -0,0 +1,200 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Long-running soak mode.
+//!
+//! `wpt-runner soak` replays a corpus of protocol requests in a loop for a fixed time, sampling
+//! the process's resident memory, open handles, compile-cache entries, stored tensors and built
+//! graphs after every pass. The
+//! first pass warms caches and allocator pools and becomes the baseline; leaks in sessions,
+//! tensors or caches that only show after thousands of requests make later samples outgrow it,
+//! and the soak fails as soon as one exceeds its limit.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::{Request, compile_cache, graphs, handle_request, parse_request, tensors};
+
+/// Session the soak requests run in, so their cache entries are separate from other partitions.
+const SESSION: &str = "soak";
+
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    /// `None` where the platform does not expose it.
+    rss_bytes: Option<u64>,
+    handles: Option<usize>,
+    cache_entries: usize,
+    tensors: usize,
+    graphs: usize,
+}
+
+fn rss_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kb = line.split_whitespace().nth(1)?.parse::<u64>().ok()?;
+    Some(kb * 1024)
+}
+
+fn handles() -> Option<usize> {
+    let dir = std::fs::read_dir("/proc/self/fd").or_else(|_| std::fs::read_dir("/dev/fd"));
+    Some(dir.ok()?.count())
+}
+
+impl Sample {
+    fn take() -> Self {
+        Self {
+            rss_bytes: rss_bytes(),
+            handles: handles(),
+            cache_entries: compile_cache::entry_count(),
+            tensors: tensors::tensor_count(),
+            graphs: graphs::graph_count(),
+        }
+    }
+}
+
+fn show<T: std::fmt::Display>(value: Option<T>) -> String {
+    value.map_or_else(|| "n/a".to_string(), |v| v.to_string())
+}
+
+/// `*.jsonl` files of `corpus` (or `corpus` itself), one request per non-blank line.
+fn load_corpus(corpus: &Path) -> Result<Vec<(String, String)>, String> {
+    let files: Vec<PathBuf> = if corpus.is_dir() {
+        let mut files = std::fs::read_dir(corpus)
+            .map_err(|e| format!("cannot read corpus {}: {e}", corpus.display()))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "jsonl"))
+            .collect::<Vec<_>>();
+        files.sort();
+        files
+    } else {
+        vec![corpus.to_path_buf()]
+    };
+    let mut requests = Vec::new();
+    for file in files {
+        let text = std::fs::read_to_string(&file)
+            .map_err(|e| format!("cannot read {}: {e}", file.display()))?;
+        for (index, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let location = format!("{}:{}", file.display(), index + 1);
+            serde_json::from_str::<Request>(line)
+                .map_err(|e| format!("{location}: invalid request: {e}"))?;
+            requests.push((location, line.to_string()));
+        }
+    }
+    if requests.is_empty() {
+        return Err(format!("no requests found in {}", corpus.display()));
+    }
+    Ok(requests)
+}
+
+fn check(
+    baseline: Sample,
+    sample: Sample,
+    max_rss_growth: u64,
+    max_handle_growth: usize,
+) -> Result<(), String> {
+    if let (Some(base), Some(now)) = (baseline.rss_bytes, sample.rss_bytes) {
+        if now.saturating_sub(base) > max_rss_growth {
+            return Err(format!(
+                "resident memory grew from {base} to {now} bytes (limit +{max_rss_growth})"
+            ));
+        }
+    }
+    if let (Some(base), Some(now)) = (baseline.handles, sample.handles) {
+        if now.saturating_sub(base) > max_handle_growth {
+            return Err(format!(
+                "open handles grew from {base} to {now} (limit +{max_handle_growth})"
+            ));
+        }
+    }
+    // The corpus is fixed and the cache bounded, so every entry exists after the first pass.
+    if sample.cache_entries > baseline.cache_entries {
+        return Err(format!(
+            "compile cache grew from {} to {} entries after the warm-up pass",
+            baseline.cache_entries, sample.cache_entries
+        ));
+    }
+    // Each pass replays the same creates and destroys, so the tensor and graph stores stay put.
+    if sample.tensors > baseline.tensors {
+        return Err(format!(
+            "tensor store grew from {} to {} tensors after the warm-up pass",
+            baseline.tensors, sample.tensors
+        ));
+    }
+    if sample.graphs > baseline.graphs {
+        return Err(format!(
+            "graph store grew from {} to {} graphs after the warm-up pass",
+            baseline.graphs, sample.graphs
+        ));
+    }
+    Ok(())
+}
+
+/// Replay `corpus` until `duration` has elapsed (at least two passes), failing on the first
+/// sample that outgrows the warm-up baseline.
+pub fn run(
+    corpus: &Path,
+    duration: Duration,
+    max_rss_growth: u64,
+    max_handle_growth: usize,
+) -> Result<(), String> {
+    let requests = load_corpus(corpus)?;
+    eprintln!(
+        "[SOAK] {} requests from {}, running for {}s",
+        requests.len(),
+        corpus.display(),
+        duration.as_secs()
+    );
+    let start = Instant::now();
+    let mut baseline = None;
+    let (mut pass, mut failed) = (0u64, 0u64);
+    while pass < 2 || start.elapsed() < duration {
+        for (location, raw) in &requests {
+            let request = parse_request(raw)
+                .map_err(|e| format!("{location}: {e}"))?
+                .map_err(|e| format!("{location}: invalid request: {e}"))?;
+            if !handle_request(request, SESSION).ok {
+                failed += 1;
+            }
+        }
+        pass += 1;
+        let sample = Sample::take();
+        eprintln!(
+            "[SOAK] pass {pass} at {}s: rss={} handles={} cache={} tensors={} graphs={} \
+             failed responses={failed}",
+            start.elapsed().as_secs(),
+            show(sample.rss_bytes),
+            show(sample.handles),
+            sample.cache_entries,
+            sample.tensors,
+            sample.graphs
+        );
+        match baseline {
+            None => baseline = Some(sample),
+            Some(baseline) => check(baseline, sample, max_rss_growth, max_handle_growth)
+                .map_err(|e| format!("pass {pass}: {e}"))?,
+        }
+    }
+    eprintln!(
+        "[SOAK] passed: {pass} passes in {}s",
+        start.elapsed().as_secs()
+    );
+    Ok(())
+}
@@ -0,0 +1,130 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Spilling large outputs to files.
+//!
+//! With `spillThresholdBytes` set, an output whose storage exceeds the threshold is written to a
+//! file as its raw little-endian element bytes (the `base64` layout, unencoded) and the response
+//! carries `dataFile` and `byteLength` instead of `data`. A 512×512×512 float32 output would
+//! otherwise be one response line of over a hundred million JSON numbers. The reader owns the
+//! file and deletes it once consumed.
+//!
+//! Files go to a directory private to the runner process (mode 0700 on Unix), created under
+//! `RUSTNNPT_SPILL_DIR` or the system temp directory with an unpredictable name. Each file is
+//! created fresh, never opened through an existing path, so other users of a shared temp
+//! directory can neither read spilled outputs nor redirect the writes with planted symlinks.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use crate::RunnerError;
+use crate::element_count::element_size;
+use crate::{compile_cache, int4};
+
+#[derive(Debug, Serialize)]
+pub struct SpilledData {
+    #[serde(rename = "dataFile")]
+    data_file: String,
+    #[serde(rename = "byteLength")]
+    byte_length: usize,
+}
+
+/// Bytes `len` elements of `data_type` take in the spilled layout.
+pub fn storage_len(data_type: &str, len: usize) -> usize {
+    if int4::is_4bit(data_type) {
+        len.div_ceil(2)
+    } else {
+        len.saturating_mul(element_size(data_type))
+    }
+}
+
+fn create_private_dir(path: &Path) -> std::io::Result<()> {
+    let mut builder = std::fs::DirBuilder::new();
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(path)
+}
+
+/// Create this process's spill directory, retrying on names that are already taken.
+fn create_dir() -> Result<PathBuf, String> {
+    let root = std::env::var_os("RUSTNNPT_SPILL_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(std::env::temp_dir);
+    std::fs::create_dir_all(&root).map_err(|e| format!("{}: {e}", root.display()))?;
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos());
+    let seed = format!("{}-{nanos}", std::process::id());
+    for attempt in 0u64..16 {
+        let hash = compile_cache::fnv1a(seed.as_bytes(), compile_cache::FNV_OFFSET ^ attempt);
+        let path = root.join(format!("rustnnpt-spill-{hash:016x}"));
+        match create_private_dir(&path) {
+            Ok(()) => return Ok(path),
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(format!("{}: {err}", path.display())),
+        }
+    }
+    Err(format!("{}: every candidate name is taken", root.display()))
+}
+
+fn dir() -> Result<&'static Path, String> {
+    static DIR: OnceLock<Result<PathBuf, String>> = OnceLock::new();
+    DIR.get_or_init(create_dir)
+        .as_deref()
+        .map_err(String::clone)
+}
+
+/// Write the storage `bytes` of output `name` to a new spill file.
+pub fn write(name: &str, bytes: &[u8]) -> Result<SpilledData, RunnerError> {
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+    let dir = dir()
+        .map_err(|e| RunnerError::RuntimeExecution(format!("cannot create spill directory {e}")))?;
+    let io_error = |e: std::io::Error| {
+        RunnerError::RuntimeExecution(format!(
+            "cannot spill output {name} to {}: {e}",
+            dir.display()
+        ))
+    };
+    let safe_name = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    let serial = NEXT.fetch_add(1, Ordering::Relaxed);
+    let path = dir.join(format!("{serial}-{safe_name}.bin"));
+    std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(bytes))
+        .map_err(io_error)?;
+    Ok(SpilledData {
+        data_file: path.to_string_lossy().into_owned(),
+        byte_length: bytes.len(),
+    })
+}
@@ -0,0 +1,79 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Execution statistics attached to responses when `collectStats` is requested.
+//!
+//! The harness pins the runner with `taskset`/`nice` at spawn time; reporting what the process
+//! actually got lets benchmark comparisons on shared CI machines check the settings took effect.
+
+use serde::Serialize;
+
+#[derive(Debug, Default, Serialize)]
+pub struct SchedulingStats {
+    /// CPUs the runner may run on, in Linux list format (`0-3,8`).
+    #[serde(rename = "cpuAffinity", skip_serializing_if = "Option::is_none")]
+    pub cpu_affinity: Option<String>,
+    /// Nice value of the runner process.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nice: Option<i64>,
+    #[serde(
+        rename = "availableParallelism",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub available_parallelism: Option<usize>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ExecutionStats {
+    pub scheduling: SchedulingStats,
+}
+
+#[cfg(target_os = "linux")]
+fn linux_scheduling() -> (Option<String>, Option<i64>) {
+    let affinity = std::fs::read_to_string("/proc/self/status")
+        .ok()
+        .and_then(|status| {
+            status
+                .lines()
+                .find_map(|line| line.strip_prefix("Cpus_allowed_list:"))
+                .map(|list| list.trim().to_string())
+        });
+    // Field 19 of /proc/self/stat; skip past the parenthesized command name, which may contain
+    // spaces, before splitting.
+    let nice = std::fs::read_to_string("/proc/self/stat")
+        .ok()
+        .and_then(|stat| {
+            let rest = &stat[stat.rfind(')')? + 1..];
+            rest.split_whitespace().nth(16)?.parse().ok()
+        });
+    (affinity, nice)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn linux_scheduling() -> (Option<String>, Option<i64>) {
+    (None, None)
+}
+
+pub fn scheduling_stats() -> SchedulingStats {
+    let (cpu_affinity, nice) = linux_scheduling();
+    SchedulingStats {
+        cpu_affinity,
+        nice,
+        available_parallelism: std::thread::available_parallelism().ok().map(usize::from),
+    }
+}
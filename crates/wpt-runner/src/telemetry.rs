@@ -0,0 +1,147 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Telemetry for embedders.
+//!
+//! The runner reports when graph validation, conversion and dispatch finish, and when a build
+//! is served from the compile cache, to one process-wide [`TelemetrySink`]. Embedders feed their
+//! own metrics systems by installing a sink with `set_telemetry_sink`. Nothing is recorded by
+//! default; with `RUSTNNPT_TELEMETRY_FILE` set, events are appended to that file as JSON lines
+//! by [`JsonlSink`].
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::Duration;
+
+use serde_json::{Value, json};
+
+#[derive(Debug, Clone, Copy)]
+pub enum TelemetryEvent<'a> {
+    /// rustnn's validator finished with a graph.
+    ValidationFinished { elapsed: Duration, ok: bool },
+    /// The `target` converter finished with a validated graph.
+    ConversionFinished {
+        target: &'a str,
+        elapsed: Duration,
+        ok: bool,
+    },
+    /// The `target` executor finished running a compiled graph.
+    DispatchFinished {
+        target: &'a str,
+        elapsed: Duration,
+        ok: bool,
+    },
+    /// A build found its `target` artifact in the compile cache.
+    CacheHit { target: &'a str },
+}
+
+impl TelemetryEvent<'_> {
+    /// The event as one JSON object, as [`JsonlSink`] writes it.
+    pub fn to_json(&self) -> Value {
+        let ms = |elapsed: &Duration| elapsed.as_secs_f64() * 1e3;
+        match self {
+            Self::ValidationFinished { elapsed, ok } => {
+                json!({ "event": "validationFinished", "elapsedMs": ms(elapsed), "ok": ok })
+            }
+            Self::ConversionFinished {
+                target,
+                elapsed,
+                ok,
+            } => json!({
+                "event": "conversionFinished",
+                "target": target,
+                "elapsedMs": ms(elapsed),
+                "ok": ok,
+            }),
+            Self::DispatchFinished {
+                target,
+                elapsed,
+                ok,
+            } => json!({
+                "event": "dispatchFinished",
+                "target": target,
+                "elapsedMs": ms(elapsed),
+                "ok": ok,
+            }),
+            Self::CacheHit { target } => json!({ "event": "cacheHit", "target": target }),
+        }
+    }
+}
+
+/// Receives every telemetry event, from whichever thread raised it.
+pub trait TelemetrySink: Send + Sync {
+    fn record(&self, event: &TelemetryEvent<'_>);
+}
+
+/// Drops every event.
+pub struct NoopSink;
+
+impl TelemetrySink for NoopSink {
+    fn record(&self, _event: &TelemetryEvent<'_>) {}
+}
+
+/// Appends each event to a file as one JSON line.
+pub struct JsonlSink {
+    file: Mutex<File>,
+}
+
+impl JsonlSink {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+}
+
+impl TelemetrySink for JsonlSink {
+    fn record(&self, event: &TelemetryEvent<'_>) {
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        // Telemetry must never fail a request; a full disk only loses events.
+        let _ = writeln!(file, "{}", event.to_json());
+    }
+}
+
+fn default_sink() -> Arc<dyn TelemetrySink> {
+    let Some(path) = std::env::var_os("RUSTNNPT_TELEMETRY_FILE") else {
+        return Arc::new(NoopSink);
+    };
+    match JsonlSink::create(Path::new(&path)) {
+        Ok(sink) => Arc::new(sink),
+        Err(err) => {
+            eprintln!("[RUNNER] telemetry disabled: cannot open {path:?}: {err}");
+            Arc::new(NoopSink)
+        }
+    }
+}
+
+fn sink() -> &'static RwLock<Arc<dyn TelemetrySink>> {
+    static SINK: OnceLock<RwLock<Arc<dyn TelemetrySink>>> = OnceLock::new();
+    SINK.get_or_init(|| RwLock::new(default_sink()))
+}
+
+pub fn set_sink(new: Arc<dyn TelemetrySink>) {
+    *sink().write().unwrap_or_else(|e| e.into_inner()) = new;
+}
+
+pub fn record(event: TelemetryEvent<'_>) {
+    let sink = Arc::clone(&sink().read().unwrap_or_else(|e| e.into_inner()));
+    sink.record(&event);
+}
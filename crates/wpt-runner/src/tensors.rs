@@ -0,0 +1,469 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Persistent tensor handles, in the style of WebNN's `MLTensor`.
+//!
+//! `create_tensor` allocates a zero-filled tensor under a caller-chosen handle, `write_tensor`
+//! and `read_tensor` move data in and out, and `destroy_tensor` frees it. `execute_graph` can
+//! bind graph inputs to handles with `inputTensors` and store graph outputs into handles with
+//! `outputTensors`, so chained dispatches keep intermediates in the runner instead of sending
+//! them over the protocol twice. `dispatch` goes further for built graphs: inputs and outputs
+//! are all handles, outputs are written straight into their tensors from the runtime's results
+//! without being encoded for a response, and nothing but the status is returned. Handles are
+//! partitioned by harness session like the compile cache, and a session's tensors are dropped
+//! when its connection closes (for the shared `""` session, when the last connection without a
+//! session closes).
+//!
+//! Tensors are held in their `base64` storage layout, so a tensor costs its byte length. A
+//! session holds at most `RUSTNNPT_MAX_SESSION_TENSORS` tensors (default 4096) taking at most
+//! `RUSTNNPT_MAX_SESSION_TENSOR_BYTES` together (default: the per-tensor limit); creating one
+//! past either limit fails with `PayloadLimitError`. Bound inputs count against the request
+//! element limit, since they are decoded into one JSON value per element like inline data, and
+//! tensors are decoded outside the store lock.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Mutex, OnceLock};
+
+use serde_json::Value;
+
+use crate::element_count::{ElementCount, max_tensor_bytes};
+use crate::encoding::{self, DataEncoding};
+use crate::{
+    ExpectedOutput, InputTensor, OutputTensor, RunnerError, RuntimeOutput, TensorDescriptor,
+    TensorDescriptorOut, WarningPayload, cast_output_data, int4, npy_file, parse_4bit,
+    payload_limits, spill, tensor_data_to_le_bytes, to_tensor_data,
+};
+
+const DEFAULT_MAX_SESSION_TENSORS: usize = 4096;
+
+#[derive(Clone)]
+struct StoredTensor {
+    descriptor: TensorDescriptor,
+    /// Little-endian element bytes, `int4`/`uint4` packed two per byte.
+    bytes: Vec<u8>,
+}
+
+impl StoredTensor {
+    fn element_count(&self) -> Result<usize, RunnerError> {
+        Ok(ElementCount::of(&self.descriptor.shape)?.get())
+    }
+
+    /// One JSON value per element, as requests carry tensor data.
+    fn values(&self) -> Result<Vec<Value>, RunnerError> {
+        encoding::decode_bytes(
+            &self.descriptor.data_type,
+            &self.bytes,
+            self.element_count()?,
+        )
+        .map_err(RunnerError::RuntimeExecution)
+    }
+}
+
+/// Per-session tensors; requests without a session share the `""` partition.
+fn store() -> &'static Mutex<HashMap<String, HashMap<String, StoredTensor>>> {
+    static TENSORS: OnceLock<Mutex<HashMap<String, HashMap<String, StoredTensor>>>> =
+        OnceLock::new();
+    TENSORS.get_or_init(Mutex::default)
+}
+
+fn with_session<T>(session: &str, f: impl FnOnce(&mut HashMap<String, StoredTensor>) -> T) -> T {
+    let mut sessions = store().lock().unwrap_or_else(|e| e.into_inner());
+    f(sessions.entry(session.to_string()).or_default())
+}
+
+/// Storage bytes of `values` in `descriptor`'s layout (`int4`/`uint4` packed), as base64
+/// transport expects. Fails on values the data type cannot hold.
+fn storage_bytes(descriptor: &TensorDescriptor, values: &[Value]) -> Result<Vec<u8>, RunnerError> {
+    let data_type = descriptor.data_type.as_str();
+    if int4::is_4bit(data_type) {
+        parse_4bit(data_type, values).map(|values| int4::pack(&values))
+    } else {
+        to_tensor_data(descriptor, values).map(tensor_data_to_le_bytes)
+    }
+}
+
+fn unknown(handle: &str) -> RunnerError {
+    RunnerError::BadRequest(format!("unknown tensor handle '{handle}'"))
+}
+
+/// Fail unless `tensors` has room for one more tensor of `bytes` bytes.
+fn check_capacity(
+    tensors: &HashMap<String, StoredTensor>,
+    bytes: usize,
+) -> Result<(), RunnerError> {
+    let max_tensors =
+        payload_limits::limit("RUSTNNPT_MAX_SESSION_TENSORS", DEFAULT_MAX_SESSION_TENSORS);
+    if tensors.len() >= max_tensors {
+        return Err(RunnerError::PayloadLimit(format!(
+            "session already holds {max_tensors} tensors"
+        )));
+    }
+    let max_bytes = payload_limits::limit("RUSTNNPT_MAX_SESSION_TENSOR_BYTES", max_tensor_bytes());
+    let held = tensors.values().map(|t| t.bytes.len()).sum::<usize>();
+    if held.saturating_add(bytes) > max_bytes {
+        return Err(RunnerError::PayloadLimit(format!(
+            "session tensors would take {} bytes, over the limit of {max_bytes}",
+            held.saturating_add(bytes)
+        )));
+    }
+    Ok(())
+}
+
+/// Allocate a zero-filled tensor, as `MLContext.createTensor()` does.
+pub fn create(
+    session: &str,
+    handle: String,
+    descriptor: TensorDescriptor,
+) -> Result<(), RunnerError> {
+    let count = ElementCount::of(&descriptor.shape)?;
+    count.check_allocation(&descriptor.data_type)?;
+    let len = spill::storage_len(&descriptor.data_type, count.get());
+    with_session(session, |tensors| {
+        if tensors.contains_key(&handle) {
+            return Err(RunnerError::BadRequest(format!(
+                "tensor handle '{handle}' already exists"
+            )));
+        }
+        check_capacity(tensors, len)?;
+        let bytes = vec![0; len];
+        tensors.insert(handle, StoredTensor { descriptor, bytes });
+        Ok(())
+    })
+}
+
+/// Replace the whole contents of `handle`; `data` must hold exactly one value per element.
+pub fn write(
+    session: &str,
+    handle: &str,
+    data: Value,
+    data_encoding: DataEncoding,
+) -> Result<(), RunnerError> {
+    with_session(session, |tensors| {
+        let tensor = tensors.get_mut(handle).ok_or_else(|| unknown(handle))?;
+        let count = ElementCount::of(&tensor.descriptor.shape)?.get();
+        let values = match (data_encoding, data) {
+            (DataEncoding::Json, Value::Array(values)) => values,
+            (DataEncoding::Base64, Value::String(encoded)) => {
+                encoding::decode_values(&tensor.descriptor.data_type, &encoded, count)
+                    .map_err(RunnerError::BadRequest)?
+            }
+            (DataEncoding::Json, _) => {
+                return Err(RunnerError::BadRequest(
+                    "tensor data must be an array".to_string(),
+                ));
+            }
+            (DataEncoding::Npy, Value::String(encoded)) => encoding::decode_npy(
+                &tensor.descriptor.data_type,
+                &tensor.descriptor.shape,
+                &encoded,
+            )
+            .map_err(RunnerError::BadRequest)?,
+            (DataEncoding::Base64, _) => {
+                return Err(RunnerError::BadRequest(
+                    "base64 tensor data must be a string".to_string(),
+                ));
+            }
+            (DataEncoding::Npy, _) => {
+                return Err(RunnerError::BadRequest(
+                    "npy tensor data must be a base64 string".to_string(),
+                ));
+            }
+        };
+        if values.len() != count {
+            return Err(RunnerError::InputValidation(format!(
+                "tensor '{handle}' holds {count} elements of shape {:?}, write has {}",
+                tensor.descriptor.shape,
+                values.len()
+            )));
+        }
+        // Reject values the tensor's dataType cannot hold now rather than at dispatch.
+        tensor.bytes = storage_bytes(&tensor.descriptor, &values)?;
+        Ok(())
+    })
+}
+
+/// Contents of `handle` as an output tensor keyed by the handle, with its storage bytes as
+/// `rawBytes` too when `raw_bytes` is set.
+pub fn read(
+    session: &str,
+    handle: &str,
+    data_encoding: DataEncoding,
+    raw_bytes: bool,
+) -> Result<BTreeMap<String, OutputTensor>, RunnerError> {
+    let tensor = with_session(session, |tensors| {
+        tensors.get(handle).cloned().ok_or_else(|| unknown(handle))
+    })?;
+    // JSON and npy readback decode one value per element, as bound inputs do.
+    if data_encoding != DataEncoding::Base64 {
+        payload_limits::check_elements(tensor.element_count()?)?;
+    }
+    let data = match data_encoding {
+        DataEncoding::Json => Value::Array(tensor.values()?),
+        DataEncoding::Base64 => encoding::encode_bytes(&tensor.bytes),
+        DataEncoding::Npy => {
+            encoding::encode_bytes(&npy_file(&tensor.descriptor, &tensor.values()?)?)
+        }
+    };
+    let output = OutputTensor {
+        descriptor: TensorDescriptorOut {
+            data_type: tensor.descriptor.data_type.clone(),
+            shape: tensor.descriptor.shape.clone(),
+        },
+        data,
+        spilled: None,
+        data_encoding,
+        verdict: None,
+        external: None,
+        raw_bytes: raw_bytes.then(|| encoding::encode_bytes(&tensor.bytes)),
+        postprocessed: None,
+    };
+    Ok(BTreeMap::from([(handle.to_string(), output)]))
+}
+
+pub fn destroy(session: &str, handle: &str) -> Result<(), RunnerError> {
+    with_session(session, |tensors| {
+        tensors
+            .remove(handle)
+            .map(drop)
+            .ok_or_else(|| unknown(handle))
+    })
+}
+
+/// Add the `inputTensors` bindings of an `execute_graph` request to its inputs.
+///
+/// Bound and inline elements together must stay within the request element limit. The bound
+/// tensors are copied out of the store and decoded after its lock is released.
+pub fn bind_inputs(
+    session: &str,
+    bindings: &BTreeMap<String, String>,
+    inputs: &mut BTreeMap<String, InputTensor>,
+) -> Result<(), RunnerError> {
+    if bindings.is_empty() {
+        return Ok(());
+    }
+    let inline = inputs.values().map(|input| input.data.len()).sum::<usize>();
+    let bound = with_session(session, |tensors| {
+        let mut elements = inline;
+        for (name, handle) in bindings {
+            if inputs.contains_key(name) {
+                return Err(RunnerError::BadRequest(format!(
+                    "input {name} is given both inline and as tensor '{handle}'"
+                )));
+            }
+            let tensor = tensors.get(handle).ok_or_else(|| unknown(handle))?;
+            elements = elements.saturating_add(tensor.element_count()?);
+        }
+        payload_limits::check_elements(elements)?;
+        bindings
+            .iter()
+            .map(|(name, handle)| {
+                let tensor = tensors.get(handle).ok_or_else(|| unknown(handle))?;
+                Ok((name.clone(), tensor.clone()))
+            })
+            .collect::<Result<Vec<_>, RunnerError>>()
+    })?;
+    for (name, tensor) in bound {
+        let data = tensor.values()?;
+        inputs.insert(
+            name,
+            InputTensor {
+                descriptor: tensor.descriptor,
+                data,
+                distribution: None,
+            },
+        );
+    }
+    Ok(())
+}
+
+/// Request every `outputTensors` output that `expected_outputs` leaves out, so outputs bound to
+/// tensors are returned by the runtime even when the harness only checks some of the others.
+pub fn expect_outputs(
+    session: &str,
+    bindings: &BTreeMap<String, String>,
+    expected_outputs: &mut BTreeMap<String, ExpectedOutput>,
+) -> Result<(), RunnerError> {
+    if expected_outputs.is_empty() {
+        return Ok(());
+    }
+    with_session(session, |tensors| {
+        for (name, handle) in bindings {
+            let tensor = tensors.get(handle).ok_or_else(|| unknown(handle))?;
+            expected_outputs
+                .entry(name.clone())
+                .or_insert_with(|| ExpectedOutput {
+                    descriptor: tensor.descriptor.clone(),
+                    data: Vec::new(),
+                    data_encoding: DataEncoding::Json,
+                    summarize: false,
+                    tolerance: None,
+                    raw_bytes: false,
+                    postprocess: None,
+                    bit_exact: false,
+                });
+        }
+        Ok(())
+    })
+}
+
+/// Move every `outputTensors` output out of the response and into its tensor.
+///
+/// Every binding is checked and encoded before any tensor is written, so a failing request
+/// leaves all tensors as they were.
+pub fn store_outputs(
+    session: &str,
+    bindings: &BTreeMap<String, String>,
+    outputs: &mut BTreeMap<String, OutputTensor>,
+) -> Result<(), RunnerError> {
+    with_session(session, |tensors| {
+        let mut stored = Vec::with_capacity(bindings.len());
+        for (name, handle) in bindings {
+            let tensor = tensors.get(handle).ok_or_else(|| unknown(handle))?;
+            let output = outputs.get(name).ok_or_else(|| {
+                RunnerError::RuntimeExecution(format!("missing output from runtime: {name}"))
+            })?;
+            if output.descriptor.data_type != tensor.descriptor.data_type
+                || output.descriptor.shape != tensor.descriptor.shape
+            {
+                return Err(RunnerError::InputValidation(format!(
+                    "output {name} is {} {:?} but tensor '{handle}' is {} {:?}",
+                    output.descriptor.data_type,
+                    output.descriptor.shape,
+                    tensor.descriptor.data_type,
+                    tensor.descriptor.shape
+                )));
+            }
+            if output.spilled.is_some() {
+                return Err(RunnerError::BadRequest(format!(
+                    "output {name} is bound to tensor '{handle}' and cannot be spilled"
+                )));
+            }
+            let count = tensor.element_count()?;
+            let bytes = match (output.data_encoding, &output.data) {
+                (DataEncoding::Json, Value::Array(values)) if values.len() == count => {
+                    storage_bytes(&tensor.descriptor, values)?
+                }
+                (DataEncoding::Base64 | DataEncoding::Npy, Value::String(encoded)) => {
+                    let values = if output.data_encoding == DataEncoding::Npy {
+                        encoding::decode_npy(
+                            &tensor.descriptor.data_type,
+                            &tensor.descriptor.shape,
+                            encoded,
+                        )
+                    } else {
+                        encoding::decode_values(&tensor.descriptor.data_type, encoded, count)
+                    }
+                    .map_err(RunnerError::RuntimeExecution)?;
+                    storage_bytes(&tensor.descriptor, &values)?
+                }
+                _ => {
+                    return Err(RunnerError::BadRequest(format!(
+                        "output {name} is bound to tensor '{handle}' and cannot be summarized"
+                    )));
+                }
+            };
+            stored.push((name, handle, bytes));
+        }
+        for (name, handle, bytes) in stored {
+            outputs.remove(name);
+            if let Some(tensor) = tensors.get_mut(handle) {
+                tensor.bytes = bytes;
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Write `dispatch` outputs into their tensors, which must match the output's shape and
+/// `dataType` (`output_types`, float32 when absent) exactly since they are never reallocated.
+/// Graph outputs bound to no tensor are dropped with an `OutputDiscarded` warning.
+pub fn write_outputs(
+    session: &str,
+    bindings: &BTreeMap<String, String>,
+    outputs: &HashMap<String, RuntimeOutput>,
+    output_types: &BTreeMap<String, String>,
+    warnings: &mut Vec<WarningPayload>,
+) -> Result<(), RunnerError> {
+    let mut unbound = outputs
+        .keys()
+        .filter(|name| !bindings.contains_key(*name))
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+    with_session(session, |tensors| {
+        // Check and encode every binding before writing any, so a bad dispatch leaves all
+        // tensors intact.
+        let mut written = Vec::with_capacity(bindings.len());
+        for (name, handle) in bindings {
+            let tensor = tensors.get(handle).ok_or_else(|| unknown(handle))?;
+            let output = outputs
+                .get(name)
+                .ok_or_else(|| RunnerError::BadRequest(format!("graph has no output {name}")))?;
+            let data_type = output_types.get(name).map_or("float32", String::as_str);
+            if data_type != tensor.descriptor.data_type || output.shape != tensor.descriptor.shape {
+                return Err(RunnerError::InputValidation(format!(
+                    "output {name} is {data_type} {:?} but tensor '{handle}' is {} {:?}",
+                    output.shape, tensor.descriptor.data_type, tensor.descriptor.shape
+                )));
+            }
+            let count = tensor.element_count()?;
+            if output.data.len() != count {
+                return Err(RunnerError::RuntimeExecution(format!(
+                    "output {name}: runtime returned {} elements but tensor '{handle}' holds \
+                     {count}",
+                    output.data.len()
+                )));
+            }
+            let values = cast_output_data(
+                &output.data,
+                output.int64_data.as_deref(),
+                output.uint64_data.as_deref(),
+                &tensor.descriptor.data_type,
+            );
+            written.push((handle, storage_bytes(&tensor.descriptor, &values)?));
+        }
+        for (handle, bytes) in written {
+            if let Some(tensor) = tensors.get_mut(handle) {
+                tensor.bytes = bytes;
+            }
+        }
+        Ok(())
+    })?;
+    if !unbound.is_empty() {
+        unbound.sort_unstable();
+        warnings.push(WarningPayload {
+            kind: "OutputDiscarded".to_string(),
+            message: format!(
+                "outputs not bound to a tensor were discarded: {}",
+                unbound.join(", ")
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// Drop every tensor of `session`; called when its connection closes.
+pub fn end_session(session: &str) {
+    let mut sessions = store().lock().unwrap_or_else(|e| e.into_inner());
+    sessions.remove(session);
+}
+
+/// Tensors held across all sessions, for soak leak checks.
+pub fn tensor_count() -> usize {
+    let sessions = store().lock().unwrap_or_else(|e| e.into_inner());
+    sessions.values().map(HashMap::len).sum()
+}
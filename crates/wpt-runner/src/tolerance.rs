@@ -0,0 +1,215 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Tolerance checks against `expected_outputs` data inside the runner.
+//!
+//! The JS harness compares outputs after a JSON round trip of widened f64 values. With a
+//! `tolerance` on an expected output the runner compares the executor's values directly, at the
+//! precision of the output's `dataType`, and returns a verdict with the worst errors. An element
+//! passes when it is within any of the given bounds, like the harness's ULP-or-absolute check;
+//! with no bounds at all it must match exactly. Integer outputs count one ULP per unit.
+
+use half::{bf16, f16};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{RunnerError, RuntimeOutput};
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Tolerance {
+    /// Max distance in units in the last place of `dataType`.
+    #[serde(default)]
+    ulp: Option<u64>,
+    /// Max absolute difference.
+    #[serde(default)]
+    atol: Option<f64>,
+    /// Max difference relative to the expected value's magnitude.
+    #[serde(default)]
+    rtol: Option<f64>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct Verdict {
+    pass: bool,
+    /// Worst finite absolute difference.
+    #[serde(rename = "maxAbsError")]
+    max_abs_error: f64,
+    #[serde(rename = "maxUlpError")]
+    max_ulp_error: u64,
+    /// Number of elements outside the tolerance.
+    mismatches: usize,
+    #[serde(rename = "firstMismatch", skip_serializing_if = "Option::is_none")]
+    first_mismatch: Option<usize>,
+    /// Set when there was no reference output to compare against.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    missing: bool,
+}
+
+impl Tolerance {
+    fn accepts(&self, abs: f64, ulp: u64, expected: f64) -> bool {
+        if self.ulp.is_none() && self.atol.is_none() && self.rtol.is_none() {
+            return ulp == 0;
+        }
+        self.ulp.is_some_and(|max| ulp <= max)
+            || self.atol.is_some_and(|max| abs <= max)
+            || self.rtol.is_some_and(|max| abs <= max * expected.abs())
+    }
+}
+
+/// Sign-magnitude float bits mapped onto a monotonic integer line.
+fn ordered(bits: u64, sign: u64) -> i128 {
+    let (bits, sign) = (i128::from(bits), i128::from(sign));
+    if bits & sign != 0 {
+        sign - (bits & (sign - 1))
+    } else {
+        bits + sign
+    }
+}
+
+pub fn ulp_distance(data_type: &str, a: f64, b: f64) -> u64 {
+    let (a, b, sign) = match data_type {
+        "float16" => (
+            u64::from(f16::from_f64(a).to_bits()),
+            u64::from(f16::from_f64(b).to_bits()),
+            0x8000,
+        ),
+        "bfloat16" => (
+            u64::from(bf16::from_f64(a).to_bits()),
+            u64::from(bf16::from_f64(b).to_bits()),
+            0x8000,
+        ),
+        "float32" => (
+            u64::from((a as f32).to_bits()),
+            u64::from((b as f32).to_bits()),
+            0x8000_0000,
+        ),
+        "float64" => (a.to_bits(), b.to_bits(), 1 << 63),
+        // Integer types: one ULP per unit.
+        _ => return (a - b).abs() as u64,
+    };
+    u64::try_from(ordered(a, sign).abs_diff(ordered(b, sign))).unwrap_or(u64::MAX)
+}
+
+/// An executor value rounded to `data_type`, the way it is returned.
+fn actual_value(data_type: &str, value: f64) -> f64 {
+    match data_type {
+        "float64" => value,
+        "float32" => f64::from(value as f32),
+        "float16" => f64::from(f16::from_f64(value)),
+        "bfloat16" => f64::from(bf16::from_f64(value)),
+        _ => value.trunc(),
+    }
+}
+
+fn expected_value(value: &Value) -> Result<f64, RunnerError> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => match s.as_str() {
+            "Infinity" => Some(f64::INFINITY),
+            "-Infinity" => Some(f64::NEG_INFINITY),
+            s => s.parse().ok(),
+        },
+        _ => None,
+    }
+    .ok_or_else(|| RunnerError::BadRequest(format!("invalid expected value: {value}")))
+}
+
+fn expected_i128(value: &Value) -> Result<i128, RunnerError> {
+    match value {
+        Value::Number(n) => n
+            .as_i64()
+            .map(i128::from)
+            .or_else(|| n.as_u64().map(i128::from)),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+    .ok_or_else(|| RunnerError::BadRequest(format!("invalid expected integer: {value}")))
+}
+
+impl Verdict {
+    /// A failing verdict for an output whose reference is missing.
+    pub fn missing() -> Self {
+        Self {
+            missing: true,
+            ..Self::default()
+        }
+    }
+
+    fn record(&mut self, index: usize, within: bool, abs: f64, ulp: u64) {
+        // Non-finite mismatches are counted but kept out of the maxima.
+        if abs.is_finite() {
+            self.max_abs_error = self.max_abs_error.max(abs);
+            self.max_ulp_error = self.max_ulp_error.max(ulp);
+        }
+        if !within {
+            self.mismatches += 1;
+            self.first_mismatch.get_or_insert(index);
+        }
+    }
+}
+
+/// Compare every element of `output` against `expected` (one value per element, or a single
+/// value for all of them).
+pub fn check(
+    tolerance: &Tolerance,
+    data_type: &str,
+    output: &RuntimeOutput,
+    expected: &[Value],
+) -> Result<Verdict, RunnerError> {
+    let len = output.data.len();
+    if expected.len() != len && expected.len() != 1 {
+        return Err(RunnerError::BadRequest(format!(
+            "output {}: tolerance check needs 1 or {len} expected values, got {}",
+            output.name,
+            expected.len()
+        )));
+    }
+    let expected_at = |index: usize| &expected[if expected.len() == 1 { 0 } else { index }];
+
+    let mut verdict = Verdict::default();
+    for index in 0..len {
+        if matches!(data_type, "int64" | "uint64") {
+            let actual = match (&output.int64_data, &output.uint64_data) {
+                (Some(values), _) => i128::from(values[index]),
+                (_, Some(values)) => i128::from(values[index]),
+                _ => output.data[index] as i128,
+            };
+            let expected = expected_i128(expected_at(index))?;
+            let diff = actual.abs_diff(expected);
+            let ulp = u64::try_from(diff).unwrap_or(u64::MAX);
+            let abs = diff as f64;
+            let within = tolerance.accepts(abs, ulp, expected as f64);
+            verdict.record(index, within, abs, ulp);
+            continue;
+        }
+
+        let actual = actual_value(data_type, output.data[index]);
+        let expected = expected_value(expected_at(index))?;
+        if !actual.is_finite() || !expected.is_finite() {
+            let same = (actual.is_nan() && expected.is_nan()) || actual == expected;
+            let abs = if same { 0.0 } else { f64::INFINITY };
+            verdict.record(index, same, abs, 0);
+            continue;
+        }
+        let abs = (actual - expected).abs();
+        let ulp = ulp_distance(data_type, actual, expected);
+        verdict.record(index, tolerance.accepts(abs, ulp, expected), abs, ulp);
+    }
+    verdict.pass = verdict.mismatches == 0;
+    Ok(verdict)
+}
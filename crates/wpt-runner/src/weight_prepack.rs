@@ -0,0 +1,209 @@
+/*
+ * SPDX-FileCopyrightText: Copyright (c) 2026 Tarek Ziadé <tarek@ziade.org>
+ * SPDX-FileCopyrightText: Copyright (c) 2026 NVIDIA CORPORATION & AFFILIATES. All rights reserved.
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Compile-time weight preprocessing on webnn-graph-json.
+//!
+//! Converters lower non-default conv filter layouts with a runtime Transpose in front of the
+//! convolution. When the filter is an inline constant with a single consumer we can permute the
+//! bytes once up front and switch the node to the default layout, so the emitted model carries
+//! the packed weights instead of a transpose that skews performance comparisons.
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::RunnerError;
+use crate::element_count::{ElementCount, element_size};
+
+/// (op, filter layout option key, backend-native layout).
+const FILTER_LAYOUTS: &[(&str, &str, &str)] = &[
+    ("conv2d", "filterLayout", "oihw"),
+    ("convTranspose2d", "filterLayout", "iohw"),
+];
+
+/// Permute a row-major tensor so that output axis `i` is input axis `perm[i]`, or `None` when
+/// the strides of `shape` overflow or `bytes` does not hold exactly its elements.
+pub fn permute(bytes: &[u8], elem: usize, shape: &[usize], perm: &[usize]) -> Option<Vec<u8>> {
+    if shape.contains(&0) {
+        return Some(bytes.to_vec());
+    }
+    let rank = shape.len();
+    let mut in_strides = vec![1usize; rank];
+    for axis in (0..rank.saturating_sub(1)).rev() {
+        in_strides[axis] = in_strides[axis + 1].checked_mul(shape[axis + 1])?;
+    }
+    let out_shape = perm.iter().map(|&p| shape[p]).collect::<Vec<_>>();
+    let count = ElementCount::of(&out_shape).ok()?.get();
+    if count.checked_mul(elem)? != bytes.len() {
+        return None;
+    }
+
+    let mut out = vec![0u8; bytes.len()];
+    let mut index = vec![0usize; rank];
+    for flat in 0..count {
+        let src = index
+            .iter()
+            .zip(perm)
+            .map(|(&i, &p)| i * in_strides[p])
+            .sum::<usize>();
+        out[flat * elem..(flat + 1) * elem].copy_from_slice(&bytes[src * elem..(src + 1) * elem]);
+        for axis in (0..rank).rev() {
+            index[axis] += 1;
+            if index[axis] < out_shape[axis] {
+                break;
+            }
+            index[axis] = 0;
+        }
+    }
+    Some(out)
+}
+
+fn layout_perm(from: &str, to: &str) -> Option<Vec<usize>> {
+    if !from.is_ascii() || from.len() != to.len() {
+        return None;
+    }
+    to.chars().map(|c| from.find(c)).collect()
+}
+
+/// References to each operand from node inputs and graph outputs.
+pub fn consumer_counts(graph: &Value) -> HashMap<String, usize> {
+    let mut counts = HashMap::new();
+    for node in graph
+        .get("nodes")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+    {
+        for name in node
+            .get("inputs")
+            .and_then(Value::as_array)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(Value::as_str)
+        {
+            *counts.entry(name.to_string()).or_default() += 1;
+        }
+    }
+    if let Some(outputs) = graph.get("outputs").and_then(Value::as_object) {
+        for name in outputs.values().filter_map(Value::as_str) {
+            *counts.entry(name.to_string()).or_default() += 1;
+        }
+    }
+    counts
+}
+
+/// Bytes of an `inlineBytes` constant declaration.
+pub fn const_bytes(decl: &Value) -> Option<Vec<u8>> {
+    let init = decl.get("init")?;
+    if init.get("kind").and_then(Value::as_str) != Some("inlineBytes") {
+        return None;
+    }
+    init.get("bytes")?
+        .as_array()?
+        .iter()
+        .map(|b| b.as_u64().and_then(|b| u8::try_from(b).ok()))
+        .collect()
+}
+
+/// Pre-transpose constant conv filters into the backend-native layout.
+///
+/// Returns the names of the constants that were repacked.
+pub fn prepack_filters(graph: &mut Value) -> Result<Vec<String>, RunnerError> {
+    let consumers = consumer_counts(graph);
+    let mut repacked = Vec::new();
+
+    let node_count = graph
+        .get("nodes")
+        .and_then(Value::as_array)
+        .map_or(0, Vec::len);
+    for node_index in 0..node_count {
+        let node = &graph["nodes"][node_index];
+        let op = node.get("op").and_then(Value::as_str).unwrap_or_default();
+        let Some(&(_, key, native)) = FILTER_LAYOUTS.iter().find(|(name, _, _)| *name == op) else {
+            continue;
+        };
+        let Some(layout) = node
+            .get("options")
+            .and_then(|o| o.get(key))
+            .and_then(Value::as_str)
+            .map(str::to_ascii_lowercase)
+        else {
+            continue;
+        };
+        if layout == native {
+            continue;
+        }
+        let Some(filter) = node
+            .get("inputs")
+            .and_then(|i| i.get(1))
+            .and_then(Value::as_str)
+            .map(str::to_string)
+        else {
+            continue;
+        };
+        if consumers.get(&filter).copied().unwrap_or(0) != 1 {
+            continue;
+        }
+        let Some(decl) = graph.get("consts").and_then(|c| c.get(&filter)) else {
+            continue;
+        };
+        let Some(bytes) = const_bytes(decl) else {
+            continue;
+        };
+        let Some(perm) = layout_perm(&layout, native) else {
+            return Err(RunnerError::BadRequest(format!(
+                "{op} has invalid {key} '{layout}'"
+            )));
+        };
+        let shape = decl
+            .get("shape")
+            .and_then(Value::as_array)
+            .map(|dims| {
+                dims.iter()
+                    .filter_map(Value::as_u64)
+                    .map(|d| d as usize)
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        if shape.len() != perm.len() {
+            continue;
+        }
+        let data_type = decl
+            .get("dataType")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let elem = element_size(data_type);
+        let byte_len = ElementCount::of(&shape).and_then(|count| count.byte_len(data_type));
+        if shape.contains(&0) || byte_len.ok() != Some(bytes.len()) {
+            continue;
+        }
+
+        let Some(packed) = permute(&bytes, elem, &shape, &perm) else {
+            continue;
+        };
+        let packed_shape = perm.iter().map(|&p| shape[p]).collect::<Vec<_>>();
+        let decl = &mut graph["consts"][&filter];
+        decl["shape"] = Value::from(packed_shape);
+        decl["init"]["bytes"] = Value::from(packed);
+        graph["nodes"][node_index]["options"][key] = Value::from(native);
+        repacked.push(filter);
+    }
+
+    Ok(repacked)
+}
@@ -0,0 +1,105 @@
+#!/usr/bin/env python3
+# SPDX-License-Identifier: Apache-2.0
+"""External ONNX Runtime oracle for wpt-runner's `compareExternal` context option.
+
+Reads `run_compiled` request lines on stdin and answers each with one `raw_outputs` response
+line on stdout, running the onnx artifact with the onnxruntime Python package:
+
+    npm run test:wpt:run -- --op add --compare-external "python3 scripts/ort-oracle.py"
+"""
+
+import base64
+import json
+import sys
+
+import numpy as np
+import onnxruntime as ort
+
+# Input dtypes as rustnn's ONNX executor binds them (4-bit types arrive widened).
+NUMPY_TYPES = {
+    "float64": np.float64,
+    "float32": np.float32,
+    "float16": np.float16,
+    "int8": np.int8,
+    "uint8": np.uint8,
+    "int4": np.int32,
+    "uint4": np.uint8,
+    "int32": np.int32,
+    "uint32": np.uint32,
+    "int64": np.int64,
+    "uint64": np.uint64,
+}
+
+
+# Unsigned integers of the same width, for float elements sent as "0x..." bit patterns.
+BIT_TYPES = {np.float64: np.uint64, np.float32: np.uint32, np.float16: np.uint16}
+
+
+def float_element(value, dtype):
+    if isinstance(value, str) and value.startswith("0x"):
+        return np.array(int(value, 16), dtype=BIT_TYPES[dtype]).view(dtype)
+    return dtype(float(value))
+
+
+def to_array(tensor):
+    descriptor = tensor["descriptor"]
+    dtype = NUMPY_TYPES.get(descriptor["dataType"])
+    if dtype is None:
+        raise ValueError(f"unsupported input dataType {descriptor['dataType']}")
+    if np.issubdtype(dtype, np.floating):
+        values = [float_element(v, dtype) for v in tensor["data"]]
+    else:
+        values = [int(v) for v in tensor["data"]]
+    return np.array(values, dtype=dtype).reshape(descriptor["shape"])
+
+
+def to_raw_output(name, value):
+    value = np.asarray(value)
+    output = {
+        "name": name,
+        "shape": list(value.shape),
+        "data": base64.b64encode(value.astype("<f8").tobytes()).decode("ascii"),
+    }
+    if value.dtype == np.int64:
+        output["int64_data"] = value.reshape(-1).tolist()
+    elif value.dtype == np.uint64:
+        output["uint64_data"] = value.reshape(-1).tolist()
+    return output
+
+
+def run(request):
+    compiled = request["compiled"]
+    if compiled["target"] != "onnx":
+        raise ValueError(f"only onnx artifacts are supported, got {compiled['target']}")
+    if compiled.get("weights"):
+        raise ValueError("artifacts with external weights are not supported")
+    session = ort.InferenceSession(
+        base64.b64decode(compiled["artifact"]), providers=["CPUExecutionProvider"]
+    )
+    feeds = {name: to_array(tensor) for name, tensor in request["inputs"].items()}
+    names = [output.name for output in session.get_outputs()]
+    values = session.run(names, feeds)
+    return [to_raw_output(name, value) for name, value in zip(names, values)]
+
+
+def main():
+    for line in sys.stdin:
+        if not line.strip():
+            continue
+        request_id = None
+        try:
+            request = json.loads(line)
+            request_id = request.get("id")
+            response = {"id": request_id, "ok": True, "raw_outputs": run(request)}
+        except Exception as err:  # report every failure to the runner instead of exiting
+            response = {
+                "id": request_id,
+                "ok": False,
+                "error": {"kind": type(err).__name__, "message": str(err)},
+            }
+        sys.stdout.write(json.dumps(response) + "\n")
+        sys.stdout.flush()
+
+
+if __name__ == "__main__":
+    main()
@@ -17,12 +17,57 @@
  */
 import { spawn } from 'node:child_process';
 import { randomUUID } from 'node:crypto';
-import { existsSync, readdirSync } from 'node:fs';
+import { existsSync, readdirSync, readFileSync, rmSync } from 'node:fs';
 import { createInterface } from 'node:readline';
 import { join, sep } from 'node:path';
 import { env } from 'node:process';
 import path from 'node:path';
 
+const SPILLED_ARRAY_TYPES = {
+  float32: Float32Array,
+  float64: Float64Array,
+  float16: Float16Array,
+  int8: Int8Array,
+  uint8: Uint8Array,
+  int32: Int32Array,
+  uint32: Uint32Array,
+  int64: BigInt64Array,
+  uint64: BigUint64Array
+};
+
+/**
+ * Replace the `dataFile` of an output spilled past `spillThresholdBytes` with its values (a
+ * typed array) and keep the file's storage bytes as `rawBytes`. The file is deleted.
+ */
+function loadSpilledOutput(output) {
+  const bytes = readFileSync(output.dataFile);
+  rmSync(output.dataFile, { force: true });
+  const dataType = output.descriptor.dataType;
+  if (dataType === 'int4' || dataType === 'uint4') {
+    const count = output.descriptor.shape.reduce((a, b) => a * b, 1);
+    const values = new Int8Array(count);
+    for (let i = 0; i < count; i++) {
+      const nibble = (bytes[i >> 1] >> ((i & 1) * 4)) & 0x0f;
+      values[i] = dataType === 'int4' && nibble & 0x08 ? nibble - 16 : nibble;
+    }
+    output.data = values;
+  } else {
+    const Ctor = SPILLED_ARRAY_TYPES[dataType] ?? Float32Array;
+    // Copy into a fresh, aligned buffer before viewing it as wider elements.
+    output.data = new Ctor(new Uint8Array(bytes).buffer);
+  }
+  output.rawBytes = bytes;
+  delete output.dataFile;
+  delete output.byteLength;
+}
+
+function loadSpilledOutputs(msg) {
+  for (const output of Object.values(msg.outputs ?? {})) {
+    if (output.dataFile) loadSpilledOutput(output);
+  }
+  for (const response of msg.responses ?? []) loadSpilledOutputs(response);
+}
+
 function findOrtLibDirs(baseDir) {
   const root = path.join(baseDir, 'target', 'onnxruntime');
   if (!existsSync(root)) return [];
@@ -107,8 +152,37 @@ function resolveBinary(binName) {
   return binName; // Fallback to original and hope for the best
 }
 
+/**
+ * Wrap the runner command with `nice` / `taskset` so benchmark runs on shared machines are pinned.
+ * Returns the (possibly unchanged) command and args.
+ */
+function withSchedulingWrappers(command, args, { cpuAffinity, nice }) {
+  let wrapped = { command, args };
+  if (nice != null && process.platform !== 'win32') {
+    wrapped = { command: 'nice', args: ['-n', String(nice), wrapped.command, ...wrapped.args] };
+  }
+  if (cpuAffinity) {
+    if (process.platform === 'linux') {
+      wrapped = { command: 'taskset', args: ['-c', String(cpuAffinity), wrapped.command, ...wrapped.args] };
+    } else {
+      console.warn(`[runner] --cpu-affinity is only supported on Linux; ignoring ${cpuAffinity}`);
+    }
+  }
+  return wrapped;
+}
+
 export class RunnerClient {
-  constructor({ manifestPath = 'crates/wpt-runner/Cargo.toml', cwd = process.cwd(), runnerFeatures = [] } = {}) {
+  constructor({
+    manifestPath = 'crates/wpt-runner/Cargo.toml',
+    cwd = process.cwd(),
+    runnerFeatures = [],
+    cpuAffinity = null,
+    nice = null,
+    /** Start the runner with `--canonical-output` (byte-stable float formatting). */
+    canonicalOutput = false,
+    /** Runner worker threads (`--jobs N`); responses are matched by id, so order does not matter. */
+    jobs = 1
+  } = {}) {
     this.cwd = cwd;
     const features = Array.isArray(runnerFeatures)
       ? runnerFeatures.map((f) => String(f).trim()).filter(Boolean)
@@ -117,19 +191,31 @@ export class RunnerClient {
     if (features.length > 0) {
       cargoArgs.push('--no-default-features', '--features', features.join(','));
     }
+    const runnerArgs = [];
+    if (canonicalOutput) runnerArgs.push('--canonical-output');
+    if (jobs > 1) runnerArgs.push('--jobs', String(jobs));
+    if (runnerArgs.length > 0) {
+      cargoArgs.push('--', ...runnerArgs);
+    }
 
     const env = withCargoCheckCfgEnv(withOrtRuntimeEnv(cwd));
     // On Windows node.js does not find cargo in the path. Search for it.
     const cargoExecutable = resolveBinary('cargo');
 
-    this.proc = spawn(cargoExecutable, cargoArgs, {
-
+    const { command, args } = withSchedulingWrappers(cargoExecutable, cargoArgs, { cpuAffinity, nice });
 
+    this.proc = spawn(command, args, {
       cwd,
       stdio: ['pipe', 'pipe', 'inherit'],
       env
     });
     this.pending = new Map();
+    /** `stats` from the most recent response that carried them (see context option collectStats). */
+    this.lastStats = null;
+    /** Operand table (`{ id, name, kind, producer, consumers, outputs }`) of the last `validate_graph` or `plan_graph`. */
+    this.lastOperands = null;
+    /** Called with `(id, { stage, percent, elapsedMs })` for each compile progress frame. */
+    this.onProgress = null;
 
     const rl = createInterface({ input: this.proc.stdout });
     rl.on('line', (line) => {
@@ -142,12 +228,30 @@ export class RunnerClient {
       }
       const waiter = this.pending.get(msg.id);
       if (!waiter) return;
+      if (msg.progress) {
+        // Progress frames precede the response; the request is still running.
+        this.onProgress?.(msg.id, msg.progress);
+        return;
+      }
       this.pending.delete(msg.id);
+      if (msg.stats) this.lastStats = msg.stats;
+      if (msg.operands) this.lastOperands = msg.operands;
       if (msg.ok) {
-        waiter.resolve(msg.outputs ?? {});
+        try {
+          loadSpilledOutputs(msg);
+        } catch (err) {
+          waiter.reject(err);
+          return;
+        }
+        if (msg.opSupportLimits) {
+          waiter.resolve({ opSupportLimits: msg.opSupportLimits, backends: msg.backends });
+          return;
+        }
+        waiter.resolve(msg.responses ?? msg.backends ?? msg.output_descriptors ?? msg.compiled ?? msg.plan ?? msg.dump ?? msg.diff ?? msg.quantized ?? msg.deviation ?? msg.benchmark ?? msg.graph ?? msg.capabilities ?? msg.outputs ?? {});
       } else {
         const error = new Error(msg.error?.message ?? 'runner error');
         error.kind = msg.error?.kind ?? 'RuntimeExecutionError';
+        if (msg.error?.domException) error.domException = msg.error.domException;
         waiter.reject(error);
       }
     });
@@ -169,16 +273,255 @@ export class RunnerClient {
     });
   }
 
-  async executeGraph({ graph, inputs, expectedOutputs, contextOptions = {} }) {
-    const id = randomUUID();
-    const payload = {
+  /**
+   * `inputTensors` / `outputTensors` map graph inputs and outputs to handles from `createTensor`;
+   * bound outputs are stored in their tensor and left out of the resolved outputs.
+   */
+  async executeGraph({
+    graph,
+    inputs,
+    inputTensors,
+    outputTensors,
+    expectedOutputs,
+    contextOptions = {},
+    target,
+    timeoutMs
+  }) {
+    return this.send({
       cmd: 'execute_graph',
-      id,
       graph,
       inputs,
+      inputTensors,
+      outputTensors,
+      expected_outputs: expectedOutputs,
+      context_options: contextOptions,
+      target,
+      timeoutMs
+    });
+  }
+
+  /**
+   * Prepare, validate and compile `graph` once. Resolves to a graph handle for `compute`, which
+   * runs it on new inputs without rebuilding it, like WebNN's `MLGraph`.
+   */
+  async buildGraph({ graph, contextOptions = {}, target }) {
+    return this.send({
+      cmd: 'build_graph',
+      graph,
+      context_options: contextOptions,
+      target
+    });
+  }
+
+  async compute(handle, { inputs, inputTensors, outputTensors, expectedOutputs, timeoutMs } = {}) {
+    return this.send({
+      cmd: 'compute',
+      graph: handle,
+      inputs,
+      inputTensors,
+      outputTensors,
+      expected_outputs: expectedOutputs,
+      timeoutMs
+    });
+  }
+
+  /**
+   * Run a built graph from tensor handles into tensor handles, like `MLContext.dispatch()`.
+   * Outputs are written into their tensors in the runner; nothing comes back but the status.
+   */
+  async dispatch(handle, { inputs, outputs, timeoutMs } = {}) {
+    return this.send({ cmd: 'dispatch', graph: handle, inputs, outputs, timeoutMs });
+  }
+
+  async destroyGraph(handle) {
+    return this.send({ cmd: 'destroy_graph', graph: handle });
+  }
+
+  /** Execute a single op; the runner synthesizes the one-node graph. */
+  async runOp({ op, inputs, inputOrder, options = {}, outputs, expectedOutputs, contextOptions = {} }) {
+    return this.send({
+      cmd: 'run_op',
+      op,
+      inputs,
+      input_order: inputOrder,
+      options,
+      outputs,
       expected_outputs: expectedOutputs,
       context_options: contextOptions
-    };
+    });
+  }
+
+  /**
+   * Execute several requests in one round trip. Resolves to one raw response per item
+   * (`{ id, ok, outputs?, error? }`), in order; item failures do not reject the batch.
+   */
+  async executeBatch(items) {
+    return this.send({
+      cmd: 'execute_batch',
+      requests: items.map((item, index) => ({
+        cmd: 'execute_graph',
+        id: item.id ?? String(index),
+        graph: item.graph,
+        inputs: item.inputs,
+        expected_outputs: item.expectedOutputs,
+        context_options: item.contextOptions ?? {}
+      }))
+    });
+  }
+
+  /**
+   * Validate a graph without inputs. Resolves to `{ [output]: { dataType, shape } }`; validation
+   * failures reject like `executeGraph` (kind `GraphValidationError`).
+   */
+  async validateGraph({ graph, contextOptions = {} }) {
+    return this.send({
+      cmd: 'validate_graph',
+      graph,
+      context_options: contextOptions
+    });
+  }
+
+  /**
+   * Validate and convert a graph without executing it. Resolves to the `CompiledGraph`, whose
+   * `artifact` is the base64 converter output (the ONNX model for the default target).
+   */
+  async convertGraph({ graph, contextOptions = {}, target }) {
+    return this.send({
+      cmd: 'convert_graph',
+      graph,
+      context_options: contextOptions,
+      target
+    });
+  }
+
+  /** Resolve to the Graphviz DOT source of the graph as the selected backend would run it. */
+  async planGraph({ graph, contextOptions = {}, target }) {
+    return this.send({
+      cmd: 'plan_graph',
+      graph,
+      context_options: contextOptions,
+      target
+    });
+  }
+
+  async dumpGraph({ graph, contextOptions = {}, format = 'dot' }) {
+    return this.send({
+      cmd: 'dump_graph',
+      graph,
+      context_options: contextOptions,
+      format
+    });
+  }
+
+  async diffGraphs({ graph, other, contextOptions = {}, tolerance = 0 }) {
+    return this.send({
+      cmd: 'diff_graphs',
+      graph,
+      other,
+      context_options: contextOptions,
+      tolerance
+    });
+  }
+
+  /**
+   * Resolve to `{ graph, tensors }`: the int8 QDQ form of `graph`, calibrated on the
+   * `calibration` input maps, and the scale, zero point and round-trip error of each tensor.
+   */
+  async quantizeGraph({ graph, calibration, contextOptions = {} }) {
+    return this.send({
+      cmd: 'quantize_graph',
+      graph,
+      calibration,
+      context_options: contextOptions
+    });
+  }
+
+  /**
+   * Resolve to per-output ULP distance statistics between `reference` and `candidate`
+   * (`{ contextOptions, target, precision }` each) over `samples` generated input sets.
+   */
+  async deviationStats({ graph, inputs, samples, reference = {}, candidate }) {
+    const side = ({ contextOptions = {}, target, precision } = {}) => ({
+      context_options: contextOptions,
+      target,
+      precision
+    });
+    return this.send({
+      cmd: 'deviation_stats',
+      graph,
+      inputs,
+      samples,
+      reference: side(reference),
+      candidate: side(candidate)
+    });
+  }
+
+  async benchmark({ graph, inputs, contextOptions = {}, target, warmups, iterations }) {
+    return this.send({
+      cmd: 'benchmark',
+      graph,
+      inputs,
+      context_options: contextOptions,
+      target,
+      warmups,
+      iterations
+    });
+  }
+
+  /** Allocate a zero-filled runner-side tensor under `handle` (`descriptor` is `{ dataType, shape }`). */
+  async createTensor(handle, descriptor) {
+    return this.send({ cmd: 'create_tensor', handle, descriptor });
+  }
+
+  /** Replace a tensor's contents; `data` has one value per element, or is base64 with `dataEncoding`. */
+  async writeTensor(handle, data, dataEncoding) {
+    return this.send({ cmd: 'write_tensor', handle, data, dataEncoding });
+  }
+
+  /** Resolve to the tensor's `{ descriptor, data }`. */
+  async readTensor(handle, dataEncoding) {
+    const outputs = await this.send({ cmd: 'read_tensor', handle, dataEncoding });
+    return outputs[handle];
+  }
+
+  async destroyTensor(handle) {
+    return this.send({ cmd: 'destroy_tensor', handle });
+  }
+
+  /** Resolve to the runner's converters and executors (`{ name, available, devices, features, fingerprint }`). */
+  async listBackends() {
+    return this.send({ cmd: 'list_backends' });
+  }
+
+  /** Resolve to `{ opSupportLimits, backends }`, the data types and ops this runner accepts. */
+  async getCapabilities() {
+    return this.send({ cmd: 'get_capabilities' });
+  }
+
+  /**
+   * Override `opSupportLimits` entries for the rest of the session (`null` removes an operator,
+   * which validation then rejects); `{}` restores the runner's own. Resolves like `getCapabilities`.
+   */
+  async setCapabilities(overrides) {
+    return this.send({ cmd: 'set_capabilities', overrides });
+  }
+
+  /**
+   * Bind this connection to `sessionId` so its cached graphs are isolated from other harnesses.
+   * Resolves to the runner's capabilities (`{ losslessInt64 }`). With `progressIntervalMs`,
+   * graph builds report progress to `onProgress` at that interval.
+   */
+  async handshake(sessionId, { progressIntervalMs } = {}) {
+    return this.send({
+      cmd: 'handshake',
+      session_id: sessionId,
+      progress_interval_ms: progressIntervalMs
+    });
+  }
+
+  async send(request) {
+    const id = randomUUID();
+    const payload = { ...request, id };
 
     return new Promise((resolve, reject) => {
       if (!this.proc || this.proc.killed || this.proc.exitCode !== null) {
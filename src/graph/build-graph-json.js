@@ -95,6 +95,15 @@ function normalizeOptions(optionsObj) {
   return out;
 }
 
+/** Like `parseNumericLoose`, but integer strings become BigInts directly instead of via Number. */
+function parseBigIntLoose(v) {
+  if (typeof v === 'bigint') return v;
+  if (typeof v === 'string' && /^[+-]?\d+n?$/.test(v.trim())) {
+    return BigInt(v.trim().replace(/n$/, ''));
+  }
+  return BigInt(Math.trunc(parseNumericLoose(v)));
+}
+
 function shapeElementCount(shape) {
   if (!shape || shape.length === 0) return 1;
   return shape.reduce((a, b) => a * b, 1);
@@ -121,6 +130,7 @@ function calculateBytesPerElement(dt) {
     case 'uint32':
       return 4;
     case 'float16':
+    case 'bfloat16':
       return 2;
     case 'int64':
     case 'uint64':
@@ -175,11 +185,28 @@ function packConstantInlineBytes(input) {
       for (let i = 0; i < n; i++) ta[i] = parseNumericLoose(getNorm(i));
       return [...new Uint8Array(ta.buffer)];
     }
+    case 'float64': {
+      const ta = new Float64Array(n);
+      for (let i = 0; i < n; i++) ta[i] = parseNumericLoose(getNorm(i));
+      return [...new Uint8Array(ta.buffer)];
+    }
     case 'float16': {
       const ta = new Float16Array(n);
       for (let i = 0; i < n; i++) ta[i] = parseNumericLoose(getNorm(i));
       return [...new Uint8Array(ta.buffer)];
     }
+    case 'bfloat16': {
+      // Round-to-nearest-even float32 -> bfloat16 (the high 16 bits).
+      const f32 = new Float32Array(1);
+      const u32 = new Uint32Array(f32.buffer);
+      const out = new Uint16Array(n);
+      for (let i = 0; i < n; i++) {
+        f32[0] = parseNumericLoose(getNorm(i));
+        const bits = u32[0];
+        out[i] = Number.isNaN(f32[0]) ? 0x7fc0 : (bits + 0x7fff + ((bits >>> 16) & 1)) >>> 16;
+      }
+      return [...new Uint8Array(out.buffer)];
+    }
     case 'int8': {
       const ta = new Int8Array(n);
       for (let i = 0; i < n; i++) ta[i] = parseNumericLoose(getNorm(i)) | 0;
@@ -205,20 +232,14 @@ function packConstantInlineBytes(input) {
     case 'int64': {
       const ta = new BigInt64Array(n);
       for (let i = 0; i < n; i++) {
-        const v = getNorm(i);
-        ta[i] = typeof v === 'bigint' ? v : BigInt(Math.trunc(parseNumericLoose(v)));
+        ta[i] = BigInt.asIntN(64, parseBigIntLoose(getNorm(i)));
       }
       return [...new Uint8Array(ta.buffer)];
     }
     case 'uint64': {
       const ta = new BigUint64Array(n);
       for (let i = 0; i < n; i++) {
-        const v = getNorm(i);
-        const bi =
-          typeof v === 'bigint'
-            ? v
-            : BigInt.asUintN(64, BigInt(Math.trunc(parseNumericLoose(v))));
-        ta[i] = bi;
+        ta[i] = BigInt.asUintN(64, parseBigIntLoose(getNorm(i)));
       }
       return [...new Uint8Array(ta.buffer)];
     }
@@ -1,12 +1,9 @@
 import { buildExpectedOutputs, buildGraphJson, buildRuntimeInputs } from '../graph/build-graph-json.js';
 
-function product(shape) {
-  return shape.reduce((a, b) => a * b, 1);
-}
-
 function typedArrayCtor(dataType) {
   switch (dataType) {
     case 'float32': return Float32Array;
+    case 'float64': return Float64Array;
     case 'float16': return Uint16Array;
     case 'int8': return Int8Array;
     case 'uint8':
@@ -20,19 +17,6 @@ function typedArrayCtor(dataType) {
   }
 }
 
-function makeZeroData(descriptor) {
-  const Ctor = typedArrayCtor(descriptor.dataType);
-  const size = Math.max(product(descriptor.shape), 1);
-  return new Ctor(size);
-}
-
-function toPlainArray(typed, dataType) {
-  if (dataType === 'int64' || dataType === 'uint64') {
-    return Array.from(typed, (v) => v.toString());
-  }
-  return Array.from(typed);
-}
-
 class MLOperand {
   constructor(name, descriptor) {
     this.name = name;
@@ -48,74 +32,93 @@ class MLGraph {
   }
 }
 
+let nextTensorId = 0;
+
+/** Bytes of `data` in `dataType` storage layout, as `writeTensor` sends them base64-encoded. */
+function storageBytes(data, dataType) {
+  const typed = ArrayBuffer.isView(data) || data instanceof ArrayBuffer
+    ? new (typedArrayCtor(dataType))(data)
+    : new (typedArrayCtor(dataType))(Array.from(data));
+  return Buffer.from(typed.buffer, typed.byteOffset, typed.byteLength);
+}
+
+/**
+ * A runner-side tensor created with `create_tensor`. The runner holds its contents; reads,
+ * writes and dispatches go through the tensor's handle.
+ */
 class MLTensor {
-  constructor(descriptor) {
+  constructor(context, handle, descriptor) {
+    this.context = context;
+    this.handle = handle;
     this.descriptor = { dataType: descriptor.dataType, shape: descriptor.shape.slice() };
-    this.data = makeZeroData(this.descriptor);
+    /** The last write or dispatch into this tensor; reads reject if it failed. */
     this.pending = null;
   }
+
+  destroy() {
+    return this.context.enqueue(() => this.context.runnerClient.destroyTensor(this.handle));
+  }
 }
 
 export class MLContext {
-  constructor(runnerClient, options = {}) {
+  constructor(runnerClient, options = {}, limits = null) {
     this.runnerClient = runnerClient;
     this.options = options;
+    this.limits = limits;
+    // WebNN orders a context's timeline: writes, dispatches and reads run in call order.
+    this.tail = Promise.resolve();
+  }
+
+  /** Run `task` after every earlier timeline task; a failed task does not block later ones. */
+  enqueue(task) {
+    const result = this.tail.then(task);
+    this.tail = result.catch(() => {});
+    return result;
   }
 
   async createTensor(descriptor) {
-    return new MLTensor(descriptor);
+    const tensor = new MLTensor(this, `shim-tensor-${nextTensorId++}`, descriptor);
+    await this.enqueue(() => this.runnerClient.createTensor(tensor.handle, tensor.descriptor));
+    return tensor;
   }
 
   writeTensor(tensor, data) {
-    if (ArrayBuffer.isView(data)) {
-      tensor.data = new (typedArrayCtor(tensor.descriptor.dataType))(data);
-    } else if (data instanceof ArrayBuffer) {
-      tensor.data = new (typedArrayCtor(tensor.descriptor.dataType))(data);
-    } else {
-      tensor.data = new (typedArrayCtor(tensor.descriptor.dataType))(data);
-    }
+    const { dataType } = tensor.descriptor;
+    // int4/uint4 typed arrays hold one element per value; the runner packs them itself.
+    const payload = dataType === 'int4' || dataType === 'uint4'
+      ? [Array.from(new (typedArrayCtor(dataType))(data)), 'json']
+      : [storageBytes(data, dataType).toString('base64'), 'base64'];
+    tensor.pending = this.enqueue(() => this.runnerClient.writeTensor(tensor.handle, ...payload));
   }
 
+  /** Resolve to the tensor's storage bytes (`int4`/`uint4` packed two per byte). */
   async readTensor(tensor) {
-    if (tensor.pending) {
+    const out = await this.enqueue(async () => {
       await tensor.pending;
-    }
-    return tensor.data.buffer.slice(0);
+      return this.runnerClient.readTensor(tensor.handle, 'base64');
+    });
+    // Spilled outputs arrive with their bytes already loaded from the file.
+    const bytes = Buffer.isBuffer(out.rawBytes) ? out.rawBytes : Buffer.from(out.data, 'base64');
+    return bytes.buffer.slice(bytes.byteOffset, bytes.byteOffset + bytes.byteLength);
   }
 
   dispatch(graph, inputs, outputs) {
-    const inputPayload = {};
-    for (const [name, tensor] of Object.entries(inputs)) {
-      inputPayload[name] = {
-        descriptor: tensor.descriptor,
-        data: toPlainArray(tensor.data, tensor.descriptor.dataType)
-      };
-    }
-
-    const expectedOutputs = {};
-    for (const [name, tensor] of Object.entries(outputs)) {
-      expectedOutputs[name] = { descriptor: tensor.descriptor };
-    }
-
-    const pending = this.runnerClient.executeGraph({
+    const handles = (tensors) =>
+      Object.fromEntries(Object.entries(tensors).map(([name, tensor]) => [name, tensor.handle]));
+    const pending = this.enqueue(() => this.runnerClient.executeGraph({
       graph: graph.graphJson,
-      inputs: inputPayload,
-      expectedOutputs,
+      inputs: {},
+      inputTensors: handles(inputs),
+      outputTensors: handles(outputs),
       contextOptions: this.options
-    }).then((result) => {
-      for (const [name, tensor] of Object.entries(outputs)) {
-        const out = result[name];
-        const Ctor = typedArrayCtor(tensor.descriptor.dataType);
-        tensor.data = new Ctor(out.data);
-      }
-    });
-
+    }));
     for (const tensor of Object.values(outputs)) {
       tensor.pending = pending;
     }
   }
 
   opSupportLimits() {
+    if (this.limits) return this.limits;
     const types = ['float32', 'float16', 'int32', 'uint32', 'int8', 'uint8', 'int64', 'uint64', 'int4', 'uint4'];
     return {
       input: { dataTypes: types },
@@ -233,7 +236,12 @@ export class ML {
   }
 
   async createContext(options = {}) {
-    return new MLContext(this.runnerClient, options);
+    // Older runners predate `get_capabilities`; fall back to the shim's static limits.
+    let limits = null;
+    if (typeof this.runnerClient.getCapabilities === 'function') {
+      limits = (await this.runnerClient.getCapabilities()).opSupportLimits;
+    }
+    return new MLContext(this.runnerClient, options, limits);
   }
 }
 
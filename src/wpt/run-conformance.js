@@ -157,6 +157,24 @@ function parseArgs(argv) {
     variants: ['cpu'],
     runnerFeatures: null,
     skipUnimplemented: false,
+    emulateInt64: false,
+    /** Fold batchNormalization into conv2d and drop identity nodes before conversion. */
+    inferenceRewrites: false,
+    /** Fold reshape/transpose/cast chains over constants before conversion. */
+    foldConstants: false,
+    /** Drop nodes no graph output depends on before conversion. */
+    eliminateDeadNodes: false,
+    /** Linux CPU list (taskset -c) and nice value applied to the runner process. */
+    cpuAffinity: null,
+    nice: null,
+    /** host:port of a `wpt-runner --listen` instance that executes the compiled graphs. */
+    remote: null,
+    /** Optimization passes to disable (fusion, constantFolding, layoutRewrites, deadNodeElimination). */
+    disablePasses: [],
+    /** Oracle command line (e.g. `python3 scripts/ort-oracle.py`) to diff executor outputs against. */
+    compareExternal: null,
+    /** Outputs larger than this many bytes come back through a temp file instead of JSON. */
+    spillThreshold: null,
     stopOnFail: false,
     reportJson: null,
     reportHtml: null,
@@ -180,6 +198,20 @@ function parseArgs(argv) {
     else if (arg === '--runner-features') opts.runnerFeatures = argv[++i].split(',').map((s) => s.trim()).filter(Boolean);
     else if (arg === '--stop-on-fail') opts.stopOnFail = true;
     else if (arg === '--skip-unimplemented') opts.skipUnimplemented = true;
+    else if (arg === '--emulate-int64') opts.emulateInt64 = true;
+    else if (arg === '--inference-rewrites') opts.inferenceRewrites = true;
+    else if (arg === '--fold-constants') opts.foldConstants = true;
+    else if (arg === '--eliminate-dead-nodes') opts.eliminateDeadNodes = true;
+    else if (arg === '--cpu-affinity') opts.cpuAffinity = argv[++i];
+    else if (arg === '--nice') opts.nice = Number(argv[++i]);
+    else if (arg === '--remote') opts.remote = argv[++i];
+    else if (arg === '--compare-external') opts.compareExternal = argv[++i];
+    else if (arg === '--spill-threshold') opts.spillThreshold = Number(argv[++i]);
+    else if (arg === '--dump-failures') {
+      // Read by the runner, which inherits this environment.
+      process.env.RUSTNNPT_FAILURE_DIR = path.resolve(argv[++i]);
+    }
+    else if (arg === '--disable-passes') opts.disablePasses = argv[++i].split(',').map((s) => s.trim()).filter(Boolean);
     else if (arg === '--report-json') opts.reportJson = argv[++i];
     else if (arg === '--report-html') opts.reportHtml = argv[++i];
     else if (arg === '--exit-zero') opts.exitZero = true;
@@ -194,7 +226,16 @@ function parseArgs(argv) {
         'Usage: node src/wpt/run-conformance.js [options]\n' +
           '  [--wpt-dir PATH] [--op NAME] [--file FILE] [--limit-tests N] [--limit-files N]\n' +
           '  [--backend onnx|coreml|trtx] [--backends LIST] [--variants cpu,gpu,npu]\n' +
-          '  [--runner-features LIST] [--skip-unimplemented] [--stop-on-fail]\n' +
+          '  [--runner-features LIST] [--skip-unimplemented] [--stop-on-fail] [--emulate-int64]\n' +
+          '  [--inference-rewrites]  (fold batchNormalization into conv2d, drop identity nodes)\n' +
+          '  [--fold-constants]  (fold reshape/transpose/cast chains over constants)\n' +
+          '  [--eliminate-dead-nodes]  (drop nodes no graph output depends on)\n' +
+          '  [--cpu-affinity CPU_LIST] [--nice N]  (pin/prioritize the runner; reported in report meta)\n' +
+          '  [--remote HOST:PORT]  (execute on a `wpt-runner --listen` instance)\n' +
+          '  [--disable-passes fusion,constantFolding,layoutRewrites,deadNodeElimination]  (optimization ablation)\n' +
+          '  [--compare-external CMD]  (diff executor outputs against an oracle process)\n' +
+          '  [--dump-failures DIR]  (write a repro bundle per conversion/runtime failure)\n' +
+          '  [--spill-threshold BYTES]  (return larger outputs through temp files)\n' +
           '  [--report-json PATH] [--report-html PATH] [--exit-zero]\n' +
           '  [--all-failures | --failure-summary-limit N]  (default: first 20 failures; N<=0 means all)\n' +
           '  [--skiplist PATH]  (optional; default: ./test-skiplist.txt if present, or RUSTNNPT_TEST_SKIPLIST)\n' +
@@ -275,8 +316,18 @@ function normalizeOpName(opName) {
   return opName.replace(/([a-z0-9])([A-Z])/g, '$1_$2').toLowerCase();
 }
 
-function contextOptionsForRun(backend, variant) {
-  return { backend, deviceType: variant };
+function contextOptionsForRun(backend, variant, opts) {
+  const contextOptions = { backend, deviceType: variant };
+  if (opts.emulateInt64) contextOptions.emulateInt64 = true;
+  if (opts.inferenceRewrites) contextOptions.inferenceRewrites = true;
+  if (opts.foldConstants) contextOptions.foldConstants = true;
+  if (opts.eliminateDeadNodes) contextOptions.eliminateDeadNodes = true;
+  if (opts.remote) contextOptions.remoteAddress = opts.remote;
+  if (opts.disablePasses.length > 0) contextOptions.disablePasses = opts.disablePasses;
+  if (opts.compareExternal) contextOptions.compareExternal = opts.compareExternal;
+  if (opts.spillThreshold != null) contextOptions.spillThresholdBytes = opts.spillThreshold;
+  if (opts.cpuAffinity || opts.nice != null) contextOptions.collectStats = true;
+  return contextOptions;
 }
 
 function isRunnerCrashError(err) {
@@ -296,7 +347,7 @@ async function runSingleTest({ runner, test, backend, variant, opts, testName })
     return { status: 'skip', reason: skipReason };
   }
 
-  const outputs = await executeGraphResources(runner, graph, contextOptionsForRun(backend, variant));
+  const outputs = await executeGraphResources(runner, graph, contextOptionsForRun(backend, variant, opts));
   const lastOp = normalizeOpName(graph?.operators?.[graph.operators.length - 1]?.name ?? 'unknown');
   const graphOperatorNames = (graph.operators ?? []).map((o) => normalizeOpName(o?.name ?? ''));
 
@@ -314,6 +365,18 @@ async function runSingleTest({ runner, test, backend, variant, opts, testName })
         actual
       });
     }
+    for (const [name, actual] of Object.entries(outputs)) {
+      const external = actual?.external;
+      if (external?.missing) {
+        throw new Error(`output ${name}: external oracle returned no value for it`);
+      }
+      if (external && !external.pass) {
+        throw new Error(
+          `output ${name}: executor and external oracle differ in ${external.mismatches} elements ` +
+            `(first at ${external.firstMismatch}, max abs error ${external.maxAbsError})`
+        );
+      }
+    }
   } catch (err) {
     logFailureDetail(testName, graph, outputs);
     throw err;
@@ -334,6 +397,16 @@ function serializeOptions(opts) {
     variants: opts.variants,
     runnerFeatures: opts.runnerFeatures,
     skipUnimplemented: opts.skipUnimplemented,
+    emulateInt64: opts.emulateInt64,
+    inferenceRewrites: opts.inferenceRewrites,
+    foldConstants: opts.foldConstants,
+    eliminateDeadNodes: opts.eliminateDeadNodes,
+    cpuAffinity: opts.cpuAffinity,
+    nice: opts.nice,
+    remote: opts.remote,
+    disablePasses: opts.disablePasses,
+    compareExternal: opts.compareExternal,
+    spillThreshold: numberOrNull(opts.spillThreshold),
     stopOnFail: opts.stopOnFail,
     reportJson: opts.reportJson,
     reportHtml: opts.reportHtml,
@@ -391,7 +464,12 @@ async function main() {
     process.exit(2);
   }
 
-  let runner = new RunnerClient({ runnerFeatures: opts.runnerFeatures ?? [] });
+  const runnerOptions = {
+    runnerFeatures: opts.runnerFeatures ?? [],
+    cpuAffinity: opts.cpuAffinity,
+    nice: opts.nice
+  };
+  let runner = new RunnerClient(runnerOptions);
 
   let passed = 0;
   let failed = 0;
@@ -404,7 +482,8 @@ async function main() {
       endedAt: null,
       options: serializeOptions(opts),
       cwd: process.cwd(),
-      rustnn: rustnnMetaFromEnv()
+      rustnn: rustnnMetaFromEnv(),
+      runnerScheduling: null
     },
     summary: {
       passed: 0,
@@ -543,7 +622,7 @@ async function main() {
               console.log(`  - FAIL ${testName}`);
               if (isRunnerCrashError(err)) {
                 await runner.close();
-                runner = new RunnerClient({ runnerFeatures: opts.runnerFeatures ?? [] });
+                runner = new RunnerClient(runnerOptions);
                 console.log('  - INFO restarted runner after backend crash');
               }
               if (opts.stopOnFail) {
@@ -563,6 +642,7 @@ async function main() {
     failed += 1;
     failures.push(`FATAL :: ${err.message}`);
   } finally {
+    report.meta.runnerScheduling = runner.lastStats?.scheduling ?? null;
     await runner.close();
   }
 
@@ -47,6 +47,28 @@ function ulpDistanceF16(a, b, scratch) {
   return Math.abs(toOrdered(aBits) - toOrdered(bBits));
 }
 
+/** Nearest bfloat16 bit pattern of `v` (round-to-nearest-even on the float32 bits). */
+/** @param {number} v @param {{ f32: Float32Array, u32: Uint32Array }} scratch */
+function bfloat16Bits(v, scratch) {
+  scratch.f32[0] = v;
+  const bits = scratch.u32[0];
+  return ((bits + 0x7fff + ((bits >>> 16) & 1)) >>> 16) & 0xffff;
+}
+
+/** ULP distance in bfloat16 (8-bit mantissa); the runner rounds bfloat16 outputs before returning them. */
+/** @param {number} a @param {number} b @param {{ f32: Float32Array, u32: Uint32Array }} scratch */
+function ulpDistanceBf16(a, b, scratch) {
+  if (Object.is(a, b)) return 0;
+  if (!Number.isFinite(a) || !Number.isFinite(b)) {
+    return a === b ? 0 : Number.POSITIVE_INFINITY;
+  }
+  const aBits = bfloat16Bits(a, scratch);
+  const bBits = bfloat16Bits(b, scratch);
+  const toOrdered = (bits) =>
+    bits & F16_SIGN_MASK ? F16_SIGN_MASK - (bits & F16_NOT_SIGN_MASK) : bits + F16_SIGN_MASK;
+  return Math.abs(toOrdered(aBits) - toOrdered(bBits));
+}
+
 const OP_ULP = {
   add: 1,
   sub: 1,
@@ -103,7 +125,7 @@ function mergedFloatTolerance(primaryOp, allOps, dataType) {
   if (ulpTol === undefined) ulpTol = 4;
 
   let absTol =
-    OP_ABS_TOL[primaryOp]?.[dataType] ?? (dataType.startsWith('float') ? 1e-4 : 0);
+    OP_ABS_TOL[primaryOp]?.[dataType] ?? (dataType.startsWith('float') || dataType === 'bfloat16' ? 1e-4 : 0);
 
   if (Array.isArray(allOps)) {
     for (const op of allOps) {
@@ -144,8 +166,12 @@ export function assertOutputClose({
   actual
 }) {
   const dataType = expected.descriptor.dataType;
-  const expectedData = Array.isArray(expected.data) ? expected.data : [expected.data];
   const actualData = actual.data ?? [];
+  // A single expected value applies to every element (the runner returns full outputs).
+  const rawExpected = Array.isArray(expected.data) ? expected.data : [expected.data];
+  const expectedData = rawExpected.length === 1 && actualData.length > 1
+    ? new Array(actualData.length).fill(rawExpected[0])
+    : rawExpected;
 
   if (expectedData.length !== actualData.length) {
     throw new Error(`length mismatch for ${outputName}: expected ${expectedData.length}, got ${actualData.length}`);
@@ -170,7 +196,7 @@ export function assertOutputClose({
 
   let { ulpTol, absTol } = mergedFloatTolerance(operatorName, graphOperatorNames, dataType);
   // relu/reduce_* use 0 = "exact" for that op alone; float16 error still comes from earlier ops.
-  if (dataType === 'float16' && ulpTol === 0) ulpTol = 4;
+  if ((dataType === 'float16' || dataType === 'bfloat16') && ulpTol === 0) ulpTol = 4;
 
   let f32BitScratch;
   let f16BitScratch;
@@ -198,6 +224,8 @@ export function assertOutputClose({
     let ulp;
     if (dataType === 'float16') {
       ulp = ulpDistanceF16(a, e, f16BitScratch);
+    } else if (dataType === 'bfloat16') {
+      ulp = ulpDistanceBf16(a, e, f32BitScratch);
     } else {
       ulp = ulpDistanceF32(a, e, f32BitScratch);
     }